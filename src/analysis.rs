@@ -0,0 +1,278 @@
+//! Game analysis: every position of a game is searched and each move is
+//! judged by how much it lowered the mover's winning chances.
+//!
+//! Scores are turned into winning chances with the logistic curve
+//! `50 + 50 * (2 / (1 + exp(-0.00368208 * cp)) - 1)`, clamping to 1000
+//! centipawns first so mates and huge advantages count the same. A move's
+//! accuracy is `103.1668 * exp(-0.04354 * loss) - 3.1669` for a loss of
+//! `loss` percentage points, and a player's accuracy is the mean over their
+//! moves.
+
+use std::fmt;
+
+use crate::pgn::{Annotation, Game, ReplayError, NAG_BLUNDER, NAG_DUBIOUS, NAG_MISTAKE};
+use crate::search::{mate_in, Limits, Searcher, MATE};
+use crate::{Board, Move, Outcome, Side, WHITE};
+
+#[derive(Clone, Debug)]
+pub struct AnalysisOptions {
+    /// Limits for every position, normally a depth or a move time.
+    pub limits: Limits,
+    /// Least loss of winning chances, in percentage points, for each class.
+    pub inaccuracy: f64,
+    pub mistake: f64,
+    pub blunder: f64,
+    /// Length of the best line written for a bad move.
+    pub variation_plies: usize,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self {
+            limits: Limits {
+                depth: Some(12),
+                ..Limits::default()
+            },
+            inaccuracy: 10.0,
+            mistake: 20.0,
+            blunder: 30.0,
+            variation_plies: 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MoveClass {
+    /// The move the search preferred.
+    Best,
+    /// Another move that loses less than an inaccuracy.
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveClass {
+    /// The NAG for the class: `?!`, `?` or `??`.
+    pub fn nag(self) -> Option<u8> {
+        match self {
+            MoveClass::Best | MoveClass::Good => None,
+            MoveClass::Inaccuracy => Some(NAG_DUBIOUS),
+            MoveClass::Mistake => Some(NAG_MISTAKE),
+            MoveClass::Blunder => Some(NAG_BLUNDER),
+        }
+    }
+}
+
+impl fmt::Display for MoveClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MoveClass::Best => "Best move",
+            MoveClass::Good => "Good move",
+            MoveClass::Inaccuracy => "Inaccuracy",
+            MoveClass::Mistake => "Mistake",
+            MoveClass::Blunder => "Blunder",
+        })
+    }
+}
+
+/// The verdict on one move of the game.
+#[derive(Clone, Debug)]
+pub struct MoveAnalysis {
+    pub played: Move,
+    /// The search's choice in the position before the move.
+    pub best: Move,
+    /// The search's principal variation, starting with `best`.
+    pub best_line: Vec<Move>,
+    /// Centipawns from white's point of view before and after the move; see
+    /// `mate_in`.
+    pub score_before: i32,
+    pub score_after: i32,
+    pub class: MoveClass,
+    /// From 0 to 100.
+    pub accuracy: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayerSummary {
+    pub moves: u32,
+    /// Mean move accuracy, from 0 to 100.
+    pub accuracy: f64,
+    pub inaccuracies: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+}
+
+impl fmt::Display for PlayerSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |count: u32, one: &str, many: &str| format!("{count} {}", if count == 1 { one } else { many });
+        write!(
+            f,
+            "accuracy {:.1}%, {}, {}, {}",
+            self.accuracy,
+            count(self.inaccuracies, "inaccuracy", "inaccuracies"),
+            count(self.mistakes, "mistake", "mistakes"),
+            count(self.blunders, "blunder", "blunders")
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GameAnalysis {
+    pub moves: Vec<MoveAnalysis>,
+    /// Indexed by side.
+    pub players: [PlayerSummary; 2],
+}
+
+/// Searches every position of `game` with a fresh transposition table.
+pub fn analyze_game(
+    game: &Game,
+    searcher: &mut Searcher,
+    options: &AnalysisOptions,
+) -> Result<GameAnalysis, ReplayError> {
+    searcher.clear();
+    let mut positions = Vec::new();
+    let last = game.replay(|board, mv| positions.push((board.clone(), *mv)))?;
+
+    // Scores of every position, white's point of view, and the search's line.
+    let mut searched: Vec<(i32, Vec<Move>)> = positions
+        .iter()
+        .map(|(board, _)| search(board, searcher, options))
+        .collect();
+    searched.push(search(&last, searcher, options));
+
+    let mut moves = Vec::new();
+    let mut players = [PlayerSummary::default(); 2];
+    let mut total_accuracy = [0.0; 2];
+    for (index, (board, played)) in positions.iter().enumerate() {
+        let side = board.side_to_move();
+        let (score_before, best_line) = &searched[index];
+        let score_after = searched[index + 1].0;
+        let best = best_line.first().copied().unwrap_or_default();
+        let loss = (win_chance(*score_before, side) - win_chance(score_after, side)).max(0.0);
+        let class = if best == *played {
+            MoveClass::Best
+        } else if loss >= options.blunder {
+            MoveClass::Blunder
+        } else if loss >= options.mistake {
+            MoveClass::Mistake
+        } else if loss >= options.inaccuracy {
+            MoveClass::Inaccuracy
+        } else {
+            MoveClass::Good
+        };
+        let accuracy = if class == MoveClass::Best {
+            100.0
+        } else {
+            (103.1668 * (-0.04354 * loss).exp() - 3.1669).clamp(0.0, 100.0)
+        };
+
+        let player = &mut players[side];
+        player.moves += 1;
+        total_accuracy[side] += accuracy;
+        match class {
+            MoveClass::Inaccuracy => player.inaccuracies += 1,
+            MoveClass::Mistake => player.mistakes += 1,
+            MoveClass::Blunder => player.blunders += 1,
+            MoveClass::Best | MoveClass::Good => {}
+        }
+        moves.push(MoveAnalysis {
+            played: *played,
+            best,
+            best_line: best_line.clone(),
+            score_before: *score_before,
+            score_after,
+            class,
+            accuracy,
+        });
+    }
+    for (player, total) in players.iter_mut().zip(total_accuracy) {
+        if player.moves > 0 {
+            player.accuracy = total / player.moves as f64;
+        }
+    }
+    Ok(GameAnalysis { moves, players })
+}
+
+/// The position's score from white's point of view and the best line.
+/// Positions without a move to play score as the rules decide; draws that
+/// have to be claimed are searched like any other position.
+fn search(board: &Board, searcher: &mut Searcher, options: &AnalysisOptions) -> (i32, Vec<Move>) {
+    let white = |score: i32| if board.side_to_move() == WHITE { score } else { -score };
+    if let Some(outcome @ (Outcome::Checkmate(_) | Outcome::Stalemate | Outcome::Variant(_))) = board.outcome() {
+        let score = match outcome.winner() {
+            None => 0,
+            Some(side) if side == board.side_to_move() => MATE,
+            Some(_) => -MATE,
+        };
+        return (white(score), Vec::new());
+    }
+    let result = searcher.search(board, &options.limits, |_| {});
+    let line = match result.lines.first() {
+        Some(line) if line.pv.first() == Some(&result.best_move) => line.pv.clone(),
+        _ => vec![result.best_move],
+    };
+    (white(result.score), line)
+}
+
+/// `side`'s winning chances in percent for a score from white's point of view.
+fn win_chance(white_score: i32, side: Side) -> f64 {
+    let score = if side == WHITE { white_score } else { -white_score };
+    let cp = score.clamp(-1000, 1000) as f64;
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * cp).exp()) - 1.0)
+}
+
+/// A score from white's point of view as PGN's `[%eval]` writes it: pawns,
+/// or `#n` for a mate in n moves, negative when black mates. Mate scores count
+/// from `side`, the side to move; a mate on the board gives `None`.
+fn eval_text(white_score: i32, side: Side) -> Option<String> {
+    let score = if side == WHITE { white_score } else { -white_score };
+    match mate_in(score) {
+        Some(0) => None,
+        Some(moves) => Some(format!("#{}", if side == WHITE { moves } else { -moves })),
+        None => Some(format!("{:.2}", white_score as f64 / 100.0)),
+    }
+}
+
+impl GameAnalysis {
+    /// `game` with each move's evaluation, NAGs for bad moves and the best line
+    /// in their place, and the players' accuracy as `WhiteAccuracy` and
+    /// `BlackAccuracy` tags. `game` must be the analysed game.
+    pub fn annotate(&self, game: &Game, options: &AnalysisOptions) -> Game {
+        let mut annotated = game.clone();
+        annotated.tags.retain(|(name, _)| name != "WhiteAccuracy" && name != "BlackAccuracy");
+        for (name, player) in [("WhiteAccuracy", &self.players[0]), ("BlackAccuracy", &self.players[1])] {
+            annotated.tags.push((name.to_string(), format!("{:.1}", player.accuracy)));
+        }
+
+        let mut annotations = Vec::new();
+        game.replay(|board, _| {
+            let analysis = &self.moves[annotations.len()];
+            let mut annotation = Annotation::default();
+            let eval = eval_text(analysis.score_after, !board.side_to_move())
+                .map_or(String::new(), |eval| format!("[%eval {eval}] "));
+            if let Some(nag) = analysis.class.nag() {
+                let mut line = board.clone();
+                let sans: Vec<String> = analysis
+                    .best_line
+                    .iter()
+                    .take(options.variation_plies.max(1))
+                    .map(|mv| {
+                        let san = line.san(mv);
+                        line.make_move(mv);
+                        san
+                    })
+                    .collect();
+                annotation.nags.push(nag);
+                annotation.comment = Some(format!("{eval}{}. {} was best.", analysis.class, sans[0]));
+                annotation.variations.push(sans);
+            } else if !eval.is_empty() {
+                annotation.comment = Some(eval.trim_end().to_string());
+            }
+            annotations.push(annotation);
+        })
+        .expect("the analysed game replays");
+        annotated.annotations = annotations;
+        annotated
+    }
+}
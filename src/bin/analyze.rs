@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use chess::analysis::{analyze_game, AnalysisOptions};
+use chess::pgn::PgnReader;
+use chess::search::Searcher;
+
+const USAGE: &str = "Usage: analyze <pgn> [--depth N | --movetime MS] [--threads N] [--hash MB]
+               [--variation-plies N] [--output FILE]
+
+Searches every position of the games in the PGN file and writes them back out
+(to standard output by default) with each move's evaluation as [%eval], NAGs
+and the best line for inaccuracies (?!), mistakes (?) and blunders (??), and
+the players' accuracy as WhiteAccuracy and BlackAccuracy tags.";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    let mut options = AnalysisOptions::default();
+    let mut threads = 1;
+    let mut hash = 64;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("missing value for {name}\n\n{USAGE}");
+                std::process::exit(2)
+            })
+        };
+        match arg.as_str() {
+            "--depth" => {
+                options.limits.depth = Some(value("--depth").parse().expect("invalid --depth"));
+                options.limits.movetime = None;
+            }
+            "--movetime" => {
+                let millis = value("--movetime").parse().expect("invalid --movetime");
+                options.limits.movetime = Some(Duration::from_millis(millis));
+                options.limits.depth = None;
+            }
+            "--threads" => threads = value("--threads").parse().expect("invalid --threads"),
+            "--hash" => hash = value("--hash").parse().expect("invalid --hash"),
+            "--variation-plies" => {
+                options.variation_plies = value("--variation-plies").parse().expect("invalid --variation-plies")
+            }
+            "--output" => output = Some(value("--output")),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ if arg.starts_with("--") || path.is_some() => {
+                eprintln!("unexpected argument '{arg}'\n\n{USAGE}");
+                std::process::exit(2);
+            }
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    };
+
+    let file = File::open(&path).unwrap_or_else(|err| panic!("failed to open {path}: {err}"));
+    let mut writer: Box<dyn Write> = match &output {
+        Some(output) => {
+            let file = File::create(output).unwrap_or_else(|err| panic!("failed to create {output}: {err}"));
+            Box::new(BufWriter::new(file))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut searcher = Searcher::new(threads, hash);
+    for (i, game) in PgnReader::new(BufReader::new(file)).enumerate() {
+        let game = game.unwrap_or_else(|err| panic!("failed to read {path}: {err}"));
+        let now = Instant::now();
+        let analysis = match analyze_game(&game, &mut searcher, &options) {
+            Ok(analysis) => analysis,
+            Err(err) => {
+                eprintln!("{path}: skipping game {}: {err}", i + 1);
+                continue;
+            }
+        };
+        eprintln!("Game {} ({} plies) in {:.2?}", i + 1, analysis.moves.len(), now.elapsed());
+        for (name, player) in [("White", &analysis.players[0]), ("Black", &analysis.players[1])] {
+            eprintln!("  {} ({name}): {player}", game.tag(name).unwrap_or("?"));
+        }
+        let annotated = analysis.annotate(&game, &options);
+        write!(writer, "{annotated}").and_then(|()| writer.flush()).expect("failed to write PGN");
+    }
+}
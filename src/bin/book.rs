@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::time::Instant;
+
+use chess::book::builder::{BookBuilder, BuildOptions};
+use chess::pgn::PgnReader;
+
+const USAGE: &str = "Usage: book <pgn>... [--max-ply N] [--min-elo N] [--min-games N]
+            [--output FILE] [--json FILE] [--csv FILE]
+
+Builds a Polyglot opening book (default book.bin) from the games in the PGN
+files, optionally also writing the move statistics as JSON or CSV.";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut options = BuildOptions::default();
+    let mut output = String::from("book.bin");
+    let mut json = None;
+    let mut csv = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("missing value for {name}\n\n{USAGE}");
+                std::process::exit(2)
+            })
+        };
+        match arg.as_str() {
+            "--max-ply" => options.max_ply = value("--max-ply").parse().expect("invalid --max-ply"),
+            "--min-elo" => options.min_elo = Some(value("--min-elo").parse().expect("invalid --min-elo")),
+            "--min-games" => options.min_games = value("--min-games").parse().expect("invalid --min-games"),
+            "--output" => output = value("--output"),
+            "--json" => json = Some(value("--json")),
+            "--csv" => csv = Some(value("--csv")),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("unexpected argument '{arg}'\n\n{USAGE}");
+                std::process::exit(2);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    }
+
+    let now = Instant::now();
+    let mut builder = BookBuilder::new(options);
+    let mut skipped = 0;
+    for path in &paths {
+        let file = File::open(path).unwrap_or_else(|err| panic!("failed to open {path}: {err}"));
+        for (i, game) in PgnReader::new(BufReader::new(file)).enumerate() {
+            let game = game.unwrap_or_else(|err| panic!("failed to read {path}: {err}"));
+            if let Err(err) = builder.add_game(&game) {
+                eprintln!("{path}: skipping game {}: {err}", i + 1);
+                skipped += 1;
+            }
+        }
+    }
+    eprintln!(
+        "Read {} games ({skipped} skipped), {} positions in {:.2?}",
+        builder.games(),
+        builder.positions(),
+        now.elapsed()
+    );
+
+    let book = builder.build();
+    book.save(&output).expect("failed to write book");
+    eprintln!("Wrote {} entries to {output}", book.len());
+    if let Some(json) = json {
+        let file = File::create(&json).expect("failed to create JSON file");
+        builder.write_json(BufWriter::new(file)).expect("failed to write JSON");
+    }
+    if let Some(csv) = csv {
+        let file = File::create(&csv).expect("failed to create CSV file");
+        builder.write_csv(BufWriter::new(file)).expect("failed to write CSV");
+    }
+}
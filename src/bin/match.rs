@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
+
+use chess::tournament::sprt::{Sprt, SprtStatus};
+use chess::tournament::{load_openings, run_match, EngineConfig, MatchConfig, Opening, TimeControl};
+use chess::Board;
+
+const USAGE: &str = "Usage: match --engine1 CMD --engine2 CMD [--name1 NAME] [--name2 NAME]
+             [--option1 NAME=VALUE]... [--option2 NAME=VALUE]... [--openings FILE]
+             [--tc BASE+INC] [--time-margin MS] [--games N] [--concurrency N]
+             [--pgn FILE] [--event NAME] [--sprt] [--elo0 E] [--elo1 E] [--alpha A] [--beta B]
+
+Plays a match between two UCI engines, each opening once with either color,
+and reports the first engine's Elo difference. Openings come from a PGN, EPD or
+FEN file; without one every game starts from the initial position. Any of the
+SPRT parameters (default elo0 0, elo1 5, alpha and beta 0.05) runs a sequential
+probability ratio test that ends the match once it accepts a hypothesis.";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut engines = [EngineConfig::default(), EngineConfig::default()];
+    let mut openings = None;
+    let mut time_control = TimeControl {
+        base: Duration::from_secs(10),
+        increment: Duration::from_millis(100),
+    };
+    let mut time_margin = Duration::from_millis(100);
+    let mut games = 100;
+    let mut concurrency = 1;
+    let mut pgn = None;
+    let mut event = String::from("Engine match");
+    let mut sprt = None::<Sprt>;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("missing value for {name}\n\n{USAGE}");
+                std::process::exit(2)
+            })
+        };
+        match arg.as_str() {
+            "--engine1" | "--engine2" => {
+                let engine = &mut engines[(arg == "--engine2") as usize];
+                let command = value(&arg);
+                let mut words = command.split_whitespace().map(String::from);
+                engine.command = words.next().unwrap_or_default();
+                engine.args = words.collect();
+            }
+            "--name1" | "--name2" => engines[(arg == "--name2") as usize].name = Some(value(&arg)),
+            "--option1" | "--option2" => {
+                let option = value(&arg);
+                let Some((name, value)) = option.split_once('=') else {
+                    eprintln!("expected NAME=VALUE for {arg}\n\n{USAGE}");
+                    std::process::exit(2);
+                };
+                engines[(arg == "--option2") as usize].options.push((name.to_string(), value.to_string()));
+            }
+            "--openings" => openings = Some(value("--openings")),
+            "--tc" => time_control = value("--tc").parse().expect("invalid --tc"),
+            "--time-margin" => {
+                time_margin = Duration::from_millis(value("--time-margin").parse().expect("invalid --time-margin"))
+            }
+            "--games" => games = value("--games").parse().expect("invalid --games"),
+            "--concurrency" => concurrency = value("--concurrency").parse().expect("invalid --concurrency"),
+            "--pgn" => pgn = Some(value("--pgn")),
+            "--event" => event = value("--event"),
+            "--sprt" => {
+                sprt.get_or_insert_default();
+            }
+            "--elo0" => sprt.get_or_insert_default().elo0 = value("--elo0").parse().expect("invalid --elo0"),
+            "--elo1" => sprt.get_or_insert_default().elo1 = value("--elo1").parse().expect("invalid --elo1"),
+            "--alpha" => sprt.get_or_insert_default().alpha = value("--alpha").parse().expect("invalid --alpha"),
+            "--beta" => sprt.get_or_insert_default().beta = value("--beta").parse().expect("invalid --beta"),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => {
+                eprintln!("unexpected argument '{arg}'\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+    if engines.iter().any(|engine| engine.command.is_empty()) {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    }
+
+    let openings = match openings {
+        Some(path) => load_openings(&path).unwrap_or_else(|err| panic!("failed to read {path}: {err}")),
+        None => vec![Opening {
+            start: Board::new(),
+            moves: Vec::new(),
+        }],
+    };
+    let config = MatchConfig {
+        engines,
+        time_control,
+        time_margin,
+        openings,
+        games,
+        concurrency,
+        sprt,
+        event,
+    };
+    let mut pgn = pgn.map(|path| {
+        let file = File::create(&path).unwrap_or_else(|err| panic!("failed to create {path}: {err}"));
+        BufWriter::new(file)
+    });
+    if let Some(sprt) = sprt {
+        let (lower, upper) = sprt.bounds();
+        eprintln!(
+            "SPRT elo0 {} elo1 {} alpha {} beta {}, bounds [{lower:.2}, {upper:.2}]",
+            sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta
+        );
+    }
+
+    let score = run_match(&config, |game, score| {
+        eprintln!(
+            "Game {} ({} vs {}): {} {{{}}}",
+            score.games(),
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?"),
+            game.tag("Result").unwrap_or("*"),
+            game.tag("Termination").unwrap_or("?")
+        );
+        match sprt {
+            Some(sprt) => eprintln!("Score {score}, LLR {:.2}", sprt.llr(score)),
+            None => eprintln!("Score {score}"),
+        }
+        if let Some(pgn) = &mut pgn {
+            write!(pgn, "{game}").and_then(|()| pgn.flush()).expect("failed to write PGN");
+        }
+    })
+    .unwrap_or_else(|err| panic!("match failed: {err}"));
+
+    println!("Score {score}");
+    if let Some(sprt) = sprt {
+        let verdict = match sprt.status(&score) {
+            SprtStatus::AcceptH0 => "H0 accepted",
+            SprtStatus::AcceptH1 => "H1 accepted",
+            SprtStatus::Continue => "no decision",
+        };
+        println!("SPRT LLR {:.2}: {verdict}", sprt.llr(&score));
+    }
+}
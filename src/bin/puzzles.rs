@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::time::Instant;
+
+use chess::pgn::PgnReader;
+use chess::puzzle::{find_puzzles, PuzzleOptions, CSV_HEADER};
+use chess::search::Searcher;
+
+const USAGE: &str = "Usage: puzzles <pgn>... [--depth N] [--win-score CP] [--min-gain PAWNS]
+               [--max-moves N] [--threads N] [--hash MB] [--output FILE]
+
+Searches every position of the games for a single winning move, a mate or a
+material gain that stays the only solution at each of the solver's moves, and
+writes the puzzles (default puzzles.csv) as CSV lines of FEN, solution in UCI
+notation, estimated rating and themes.";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut options = PuzzleOptions::default();
+    let mut threads = 1;
+    let mut hash = 64;
+    let mut output = String::from("puzzles.csv");
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("missing value for {name}\n\n{USAGE}");
+                std::process::exit(2)
+            })
+        };
+        match arg.as_str() {
+            "--depth" => options.limits.depth = Some(value("--depth").parse().expect("invalid --depth")),
+            "--win-score" => options.win_score = value("--win-score").parse().expect("invalid --win-score"),
+            "--min-gain" => options.min_gain = value("--min-gain").parse().expect("invalid --min-gain"),
+            "--max-moves" => options.max_moves = value("--max-moves").parse().expect("invalid --max-moves"),
+            "--threads" => threads = value("--threads").parse().expect("invalid --threads"),
+            "--hash" => hash = value("--hash").parse().expect("invalid --hash"),
+            "--output" => output = value("--output"),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("unexpected argument '{arg}'\n\n{USAGE}");
+                std::process::exit(2);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    }
+
+    let file = File::create(&output).unwrap_or_else(|err| panic!("failed to create {output}: {err}"));
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "{CSV_HEADER}").expect("failed to write puzzles");
+    let now = Instant::now();
+    let mut searcher = Searcher::new(threads, hash);
+    let (mut games, mut puzzles) = (0, 0);
+    for path in &paths {
+        let file = File::open(path).unwrap_or_else(|err| panic!("failed to open {path}: {err}"));
+        for (i, game) in PgnReader::new(BufReader::new(file)).enumerate() {
+            let game = game.unwrap_or_else(|err| panic!("failed to read {path}: {err}"));
+            let found = match find_puzzles(&game, &mut searcher, &options) {
+                Ok(found) => found,
+                Err(err) => {
+                    eprintln!("{path}: skipping game {}: {err}", i + 1);
+                    continue;
+                }
+            };
+            for puzzle in &found {
+                writeln!(writer, "{puzzle}").expect("failed to write puzzles");
+            }
+            writer.flush().expect("failed to write puzzles");
+            games += 1;
+            puzzles += found.len();
+            eprintln!("{games} games, {puzzles} puzzles in {:.2?}", now.elapsed());
+        }
+    }
+    eprintln!("Wrote {puzzles} puzzles to {output}");
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+
+use chess::book::Book;
+use chess::pgn::GameResult;
+use chess::search::Searcher;
+use chess::selfplay::{play_game, OutputFormat, SelfPlayOptions};
+
+const USAGE: &str = "Usage: selfplay [--games N] [--depth N | --nodes N] [--threads N] [--seed N]
+            [--book FILE] [--book-plies N] [--random-plies N] [--hash MB]
+            [--resign-score CP] [--resign-moves N] [--draw-score CP] [--draw-plies N]
+            [--draw-after N] [--max-plies N] [--format packed|epd] [--output FILE]
+
+Plays games of the engine against itself and writes every quiet position with
+its search score and the game's result, as 32-byte packed records (default
+selfplay.bin) or EPD lines. Game i uses seed + i, so a run is reproducible
+whatever the number of threads.";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut options = SelfPlayOptions::default();
+    let mut games = 100;
+    let mut threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut seed = 0u64;
+    let mut hash = 16;
+    let mut format = OutputFormat::Packed;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("missing value for {name}\n\n{USAGE}");
+                std::process::exit(2)
+            })
+        };
+        match arg.as_str() {
+            "--games" => games = value("--games").parse().expect("invalid --games"),
+            "--depth" => {
+                options.limits.depth = Some(value("--depth").parse().expect("invalid --depth"));
+                options.limits.nodes = None;
+            }
+            "--nodes" => {
+                options.limits.nodes = Some(value("--nodes").parse().expect("invalid --nodes"));
+                options.limits.depth = None;
+            }
+            "--threads" => threads = value("--threads").parse().expect("invalid --threads"),
+            "--seed" => seed = value("--seed").parse().expect("invalid --seed"),
+            "--book" => {
+                let path = value("--book");
+                let book = Book::load(&path).unwrap_or_else(|err| panic!("failed to load {path}: {err}"));
+                options.book = Some(Arc::new(book));
+            }
+            "--book-plies" => options.book_plies = value("--book-plies").parse().expect("invalid --book-plies"),
+            "--random-plies" => {
+                options.random_plies = value("--random-plies").parse().expect("invalid --random-plies")
+            }
+            "--hash" => hash = value("--hash").parse().expect("invalid --hash"),
+            "--resign-score" => {
+                options.resign_score = value("--resign-score").parse().expect("invalid --resign-score")
+            }
+            "--resign-moves" => {
+                options.resign_moves = value("--resign-moves").parse().expect("invalid --resign-moves")
+            }
+            "--draw-score" => options.draw_score = value("--draw-score").parse().expect("invalid --draw-score"),
+            "--draw-plies" => options.draw_plies = value("--draw-plies").parse().expect("invalid --draw-plies"),
+            "--draw-after" => options.draw_after = value("--draw-after").parse().expect("invalid --draw-after"),
+            "--max-plies" => options.max_plies = value("--max-plies").parse().expect("invalid --max-plies"),
+            "--format" => {
+                format = match value("--format").as_str() {
+                    "packed" => OutputFormat::Packed,
+                    "epd" => OutputFormat::Epd,
+                    other => {
+                        eprintln!("unknown format '{other}'\n\n{USAGE}");
+                        std::process::exit(2);
+                    }
+                }
+            }
+            "--output" => output = Some(value("--output")),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => {
+                eprintln!("unexpected argument '{arg}'\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+    let output = output.unwrap_or_else(|| {
+        String::from(if format == OutputFormat::Packed { "selfplay.bin" } else { "selfplay.epd" })
+    });
+    let file = File::create(&output).unwrap_or_else(|err| panic!("failed to create {output}: {err}"));
+    let mut writer = BufWriter::new(file);
+
+    let now = Instant::now();
+    let next_game = AtomicU64::new(0);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, games.max(1) as usize) {
+            let (options, next_game, sender) = (&options, &next_game, sender.clone());
+            scope.spawn(move || {
+                let mut searcher = Searcher::new(1, hash);
+                loop {
+                    let index = next_game.fetch_add(1, Ordering::Relaxed);
+                    if index >= games {
+                        break;
+                    }
+                    let game = play_game(options, &mut searcher, seed.wrapping_add(index));
+                    if sender.send((index, game)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // Games are written in order so the output does not depend on thread timing.
+        let mut pending = BTreeMap::new();
+        let mut written = 0;
+        let (mut positions, mut results) = (0, [0; 3]);
+        for (index, game) in receiver {
+            pending.insert(index, game);
+            while let Some(game) = pending.remove(&written) {
+                format.write_game(&mut writer, &game).expect("failed to write positions");
+                positions += game.positions.len();
+                results[match game.result {
+                    GameResult::WhiteWins => 0,
+                    GameResult::Draw => 1,
+                    GameResult::BlackWins => 2,
+                }] += 1;
+                written += 1;
+                if written % 10 == 0 || written == games {
+                    eprintln!(
+                        "{written}/{games} games, {positions} positions, +{} ={} -{} in {:.2?}",
+                        results[0],
+                        results[1],
+                        results[2],
+                        now.elapsed()
+                    );
+                }
+            }
+        }
+    });
+    writer.flush().expect("failed to write positions");
+    eprintln!("Wrote {output}");
+}
@@ -0,0 +1,70 @@
+use std::time::Instant;
+
+use chess::eval::EvalWeights;
+use chess::tuner::{load_positions, to_rust_source, Tuner};
+
+const USAGE: &str = "Usage: tuner <positions> [--epochs N] [--lr RATE] [--threads N] [--output FILE]
+
+<positions> has one labeled position per line, either a FEN followed by the result
+(1-0, 0-1, 1/2-1/2, or a score like [0.5]) or an EPD with a c9 result opcode.";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    let mut epochs = 1000;
+    let mut lr = 1.0;
+    let mut threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("missing value for {name}\n\n{USAGE}");
+                std::process::exit(2)
+            })
+        };
+        match arg.as_str() {
+            "--epochs" => epochs = value("--epochs").parse().expect("invalid --epochs"),
+            "--lr" => lr = value("--lr").parse().expect("invalid --lr"),
+            "--threads" => threads = value("--threads").parse().expect("invalid --threads"),
+            "--output" => output = Some(value("--output")),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("unexpected argument '{arg}'\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    };
+
+    let now = Instant::now();
+    let (positions, skipped) = load_positions(&path, threads).expect("failed to read positions");
+    eprintln!(
+        "Loaded {} positions ({skipped} skipped) in {:.2?}",
+        positions.len(),
+        now.elapsed()
+    );
+
+    let mut tuner = Tuner::new(positions, EvalWeights::default(), threads);
+    let k = tuner.optimize_k();
+    eprintln!("K = {k:.4}, error = {:.6}", tuner.error(k));
+
+    for epoch in 1..=epochs {
+        tuner.step(lr);
+        if epoch % 50 == 0 || epoch == epochs {
+            eprintln!("epoch {epoch}: error = {:.6}", tuner.error(tuner.k));
+        }
+    }
+
+    let source = to_rust_source(&tuner.weights());
+    match output {
+        Some(output) => std::fs::write(output, source).expect("failed to write output"),
+        None => print!("{source}"),
+    }
+}
@@ -0,0 +1,208 @@
+use std::io;
+use std::path::Path;
+
+use crate::{Board, Move, Moves, Side};
+
+pub mod builder;
+mod random64;
+use random64::RANDOM64;
+
+/// Size of one entry in a Polyglot `.bin` file.
+pub const ENTRY_SIZE: usize = 16;
+
+/// One Polyglot book entry. Files store these big-endian, sorted by key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    /// Polyglot move encoding; see `decode_move`.
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> Self {
+        Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().expect("8 bytes")),
+            mv: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().expect("4 bytes")),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+/// A Polyglot opening book held in memory.
+#[derive(Clone, Debug, Default)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Book {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(invalid("Polyglot book size is not a multiple of 16 bytes"));
+        }
+        let entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry::from_bytes(chunk.try_into().expect("16 bytes")))
+            .collect();
+        if !entries.is_sorted_by_key(|entry| entry.key) {
+            return Err(invalid("Polyglot book entries are not sorted by key"));
+        }
+        Ok(Self { entries })
+    }
+
+    /// Serializes the book as a Polyglot `.bin` file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|entry| entry.to_bytes()).collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All entries for `key`, found by binary search.
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = start + self.entries[start..].partition_point(|entry| entry.key == key);
+        &self.entries[start..end]
+    }
+
+    /// Legal book moves for `board` with their weights. Entries that do not
+    /// decode to a legal move are skipped.
+    pub fn moves(&self, board: &Board) -> Vec<(Move, u16)> {
+        self.entries(board.polyglot_key())
+            .iter()
+            .filter_map(|entry| Some((decode_move(board, entry.mv)?, entry.weight)))
+            .collect()
+    }
+
+    /// The book move with the highest weight, preferring the first entry on ties.
+    pub fn best_move(&self, board: &Board) -> Option<Move> {
+        self.moves(board)
+            .into_iter()
+            .rev()
+            .max_by_key(|&(_, weight)| weight)
+            .map(|(mv, _)| mv)
+    }
+
+    /// A book move chosen with probability proportional to its weight. Moves
+    /// with weight zero are only played if every move has weight zero.
+    pub fn pick_move(&self, board: &Board, rng: &mut fastrand::Rng) -> Option<Move> {
+        let moves = self.moves(board);
+        let total: u32 = moves.iter().map(|&(_, weight)| weight as u32).sum();
+        if total == 0 {
+            return (!moves.is_empty()).then(|| moves[rng.usize(..moves.len())].0);
+        }
+        let mut choice = rng.u32(..total);
+        for (mv, weight) in moves {
+            if choice < weight as u32 {
+                return Some(mv);
+            }
+            choice -= weight as u32;
+        }
+        unreachable!("choice is below the total weight")
+    }
+}
+
+impl Board {
+    /// Zobrist key of the position as defined by Polyglot, which differs from
+    /// `hash`. En passant only counts if a pawn can capture there.
+    pub fn polyglot_key(&self) -> u64 {
+        let mut key = 0;
+        for square in 0..64 {
+            let piece = self.arr[square];
+            if let Some(side) = self.side_at(square) {
+                // Black pawn, white pawn, black knight, ...
+                let kind = 2 * (piece as usize - 1) + (side == Side::WHITE) as usize;
+                key ^= RANDOM64[64 * kind + square];
+            }
+        }
+        let rights = [
+            self.castling.white_kingside,
+            self.castling.white_queenside,
+            self.castling.black_kingside,
+            self.castling.black_queenside,
+        ];
+        for (i, &right) in rights.iter().enumerate() {
+            if right {
+                key ^= RANDOM64[768 + i];
+            }
+        }
+        if let Some(file) = self.ep {
+            let side = self.side_to_move;
+            let target = match side {
+                Side::WHITE => 40 + file as usize,
+                Side::BLACK => 16 + file as usize,
+            };
+            if self.pawn_attack_table[!side][target] & self.pawns[side] != 0 {
+                key ^= RANDOM64[772 + file as usize];
+            }
+        }
+        if self.side_to_move == Side::WHITE {
+            key ^= RANDOM64[780];
+        }
+        key
+    }
+}
+
+/// Decodes a Polyglot move into the matching legal move of `board`.
+///
+/// Bits 0-5 hold the destination and bits 6-11 the origin, each as file
+/// plus 8 * rank; bits 12-14 the promotion piece (1 = knight .. 4 = queen).
+/// Castling is written as the king capturing its own rook, e.g. `e1h1`,
+/// which is also how `Move` stores it.
+pub fn decode_move(board: &Board, raw: u16) -> Option<Move> {
+    let from = ((raw >> 6) & 0x3F) as usize;
+    let to = (raw & 0x3F) as usize;
+    let promotion = ((raw >> 12) & 0x7) as u8;
+
+    let mut moves = Moves::new();
+    board.generate_moves(&mut moves, board.side_to_move);
+    moves.into_iter().find(|mv| {
+        mv.from_square() == from
+            && mv.to_square() == to
+            && match promotion {
+                0 => !mv.is_promotion(),
+                piece => mv.is_promotion() && mv.promotion_piece() == piece - 1,
+            }
+    })
+}
+
+/// Encodes `mv` in the Polyglot move format; see `decode_move`.
+pub fn encode_move(mv: &Move) -> u16 {
+    let from = mv.from_square();
+    let to = mv.to_square();
+    let promotion = if mv.is_promotion() {
+        mv.promotion_piece() as u16 + 1
+    } else {
+        0
+    };
+    (promotion << 12) | ((from as u16) << 6) | to as u16
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use super::{encode_move, Book, BookEntry};
+use crate::pgn::{Game, GameResult, ReplayError};
+use crate::Side;
+
+#[derive(Clone, Debug)]
+pub struct BuildOptions {
+    /// Plies from the start of each game to include.
+    pub max_ply: usize,
+    /// Only count moves played by someone rated at least this. Moves by
+    /// unrated players are skipped when this is set.
+    pub min_elo: Option<u32>,
+    /// Leave out moves played in fewer games than this.
+    pub min_games: u32,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            max_ply: 30,
+            min_elo: None,
+            min_games: 1,
+        }
+    }
+}
+
+/// Results after a move, from the point of view of the side that played it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveStats {
+    pub count: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MoveStats {
+    /// Polyglot-style score: two points per win and one per draw.
+    pub fn score(&self) -> u32 {
+        2 * self.wins + self.draws
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MoveRecord {
+    uci: String,
+    san: String,
+    stats: MoveStats,
+}
+
+#[derive(Clone, Debug)]
+struct PositionRecord {
+    fen: String,
+    /// Keyed by Polyglot move.
+    moves: HashMap<u16, MoveRecord>,
+}
+
+/// A position ready for output, with its moves ordered best first and the
+/// weight to store for each.
+struct Row<'a> {
+    key: u64,
+    position: &'a PositionRecord,
+    moves: Vec<(u16, &'a MoveRecord, u16)>,
+}
+
+/// Aggregates move statistics from games into an opening book.
+#[derive(Clone, Debug, Default)]
+pub struct BookBuilder {
+    options: BuildOptions,
+    positions: HashMap<u64, PositionRecord>,
+    games: usize,
+}
+
+impl BookBuilder {
+    pub fn new(options: BuildOptions) -> Self {
+        Self {
+            options,
+            positions: HashMap::new(),
+            games: 0,
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    pub fn positions(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Replays `game` up to `max_ply` and counts every move played. Moves of
+    /// unfinished games are counted but earn no score.
+    pub fn add_game(&mut self, game: &Game) -> Result<(), ReplayError> {
+        let mut truncated = game.clone();
+        truncated.moves.truncate(self.options.max_ply);
+        let elo = [game.elo(Side::WHITE), game.elo(Side::BLACK)];
+
+        let mut seen = Vec::new();
+        truncated.replay(|board, mv| {
+            let side = board.side_to_move();
+            if let Some(min_elo) = self.options.min_elo
+                && elo[side].is_none_or(|elo| elo < min_elo)
+            {
+                return;
+            }
+            seen.push((board.polyglot_key(), board.to_fen(), side, *mv, board.san(mv)));
+        })?;
+
+        self.games += 1;
+        for (key, fen, side, mv, san) in seen {
+            let position = self.positions.entry(key).or_insert_with(|| PositionRecord {
+                fen,
+                moves: HashMap::new(),
+            });
+            let record = position.moves.entry(encode_move(&mv)).or_insert_with(|| MoveRecord {
+                uci: mv.to_string(),
+                san,
+                stats: MoveStats::default(),
+            });
+            let stats = &mut record.stats;
+            stats.count += 1;
+            match (game.result, side) {
+                (Some(GameResult::Draw), _) => stats.draws += 1,
+                (Some(GameResult::WhiteWins), Side::WHITE) | (Some(GameResult::BlackWins), Side::BLACK) => {
+                    stats.wins += 1
+                }
+                (Some(_), _) => stats.losses += 1,
+                (None, _) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Positions sorted by key. Moves with no score are left out.
+    fn rows(&self) -> Vec<Row<'_>> {
+        let mut keys: Vec<u64> = self.positions.keys().copied().collect();
+        keys.sort_unstable();
+        keys.into_iter()
+            .filter_map(|key| {
+                let position = &self.positions[&key];
+                let mut moves: Vec<(u16, &MoveRecord)> = position
+                    .moves
+                    .iter()
+                    .filter(|(_, record)| {
+                        record.stats.count >= self.options.min_games && record.stats.score() > 0
+                    })
+                    .map(|(&mv, record)| (mv, record))
+                    .collect();
+                if moves.is_empty() {
+                    return None;
+                }
+                moves.sort_by_key(|&(mv, record)| (std::cmp::Reverse(record.stats.score()), mv));
+                // Scale down so the best move's weight fits in 16 bits.
+                let best = moves[0].1.stats.score() as u64;
+                let scale = |score: u32| {
+                    let weight = (score as u64 * u16::MAX as u64).div_ceil(best.max(u16::MAX as u64));
+                    weight.max(1) as u16
+                };
+                let moves = moves
+                    .into_iter()
+                    .map(|(mv, record)| (mv, record, scale(record.stats.score())))
+                    .collect();
+                Some(Row { key, position, moves })
+            })
+            .collect()
+    }
+
+    /// The book, with entries sorted by key and then by weight.
+    pub fn build(&self) -> Book {
+        let entries = self
+            .rows()
+            .into_iter()
+            .flat_map(|row| {
+                row.moves.into_iter().map(move |(mv, _, weight)| BookEntry {
+                    key: row.key,
+                    mv,
+                    weight,
+                    learn: 0,
+                })
+            })
+            .collect();
+        Book { entries }
+    }
+
+    /// Writes every position and its move statistics as JSON, for an opening explorer.
+    pub fn write_json(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "[")?;
+        let rows = self.rows();
+        for (i, row) in rows.iter().enumerate() {
+            writeln!(out, "  {{\"fen\": \"{}\", \"key\": \"{:016x}\", \"moves\": [", row.position.fen, row.key)?;
+            for (j, (_, record, weight)) in row.moves.iter().enumerate() {
+                let stats = record.stats;
+                write!(
+                    out,
+                    "    {{\"uci\": \"{}\", \"san\": \"{}\", \"count\": {}, \"wins\": {}, \"draws\": {}, \"losses\": {}, \"weight\": {weight}}}",
+                    record.uci, record.san, stats.count, stats.wins, stats.draws, stats.losses,
+                )?;
+                writeln!(out, "{}", if j + 1 < row.moves.len() { "," } else { "" })?;
+            }
+            writeln!(out, "  ]}}{}", if i + 1 < rows.len() { "," } else { "" })?;
+        }
+        writeln!(out, "]")
+    }
+
+    /// Writes one row per position and move as CSV.
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "fen,key,uci,san,count,wins,draws,losses,weight")?;
+        for row in self.rows() {
+            for (_, record, weight) in row.moves {
+                let stats = record.stats;
+                writeln!(
+                    out,
+                    "{},{:016x},{},{},{},{},{},{},{weight}",
+                    row.position.fen, row.key, record.uci, record.san, stats.count, stats.wins, stats.draws, stats.losses,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
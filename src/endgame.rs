@@ -0,0 +1,399 @@
+//! Endgames solved without search. The KPK bitbase corrects the evaluation;
+//! distance-to-mate tables for a few pawnless endings against a lone king
+//! are generated by retrograde analysis and probed by the search.
+
+use std::io;
+use std::path::Path;
+
+use crate::utils::lsb1;
+use crate::{
+    king_attack_table, knight_attack_table, slider_tables, Board, Side, BISHOP_PIECE_TYPE,
+    KING_PIECE_TYPE, KNIGHT_PIECE_TYPE, QUEEN_PIECE_TYPE, ROOK_PIECE_TYPE, Variant,
+};
+
+pub mod kpk;
+
+/// Bonus for the stronger side in a won KPK position, on top of the evaluation.
+const KPK_WIN_BONUS: i32 = 200;
+
+/// Exact result of a position from the side to move's point of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dtm {
+    Draw,
+    /// Mates in this many plies.
+    Win(u32),
+    /// Gets mated in this many plies.
+    Loss(u32),
+}
+
+/// Distance to mate for every position of one endgame, e.g. `KRvK`.
+///
+/// Only endings of a lone king against pieces that cannot win if one of
+/// them is taken are supported: one piece, or two minor pieces. Positions
+/// are stored once per side to move with the attacking king mirrored into
+/// the a1-d4 quarter, one byte each: plies to mate plus one, or 0 if drawn.
+#[derive(Clone, Debug)]
+pub struct DtmTable {
+    name: String,
+    /// Piece types of the attacker besides the king, in index order.
+    pieces: Vec<u8>,
+    dtm: Vec<u8>,
+}
+
+/// Piece types of a supported `name` such as `KBNvK`, strongest first.
+fn parse_name(name: &str) -> Option<Vec<u8>> {
+    let pieces = name.strip_prefix('K')?.strip_suffix("vK")?;
+    let mut pieces = pieces
+        .chars()
+        .map(|letter| match letter {
+            'Q' => Some(QUEEN_PIECE_TYPE),
+            'R' => Some(ROOK_PIECE_TYPE),
+            'B' => Some(BISHOP_PIECE_TYPE),
+            'N' => Some(KNIGHT_PIECE_TYPE),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    pieces.sort_unstable_by(|a, b| b.cmp(a));
+    let minors = pieces.iter().all(|&piece| piece <= BISHOP_PIECE_TYPE);
+    match pieces.len() {
+        1 => Some(pieces),
+        2 if minors => Some(pieces),
+        _ => None,
+    }
+}
+
+fn piece_letter(piece: u8) -> char {
+    match piece {
+        QUEEN_PIECE_TYPE => 'Q',
+        ROOK_PIECE_TYPE => 'R',
+        BISHOP_PIECE_TYPE => 'B',
+        _ => 'N',
+    }
+}
+
+/// Mirrors the position so the attacking king, first in `squares`, is on a1-d4.
+fn canonical(squares: &mut [usize]) {
+    if squares[0] % 8 > 3 {
+        squares.iter_mut().for_each(|square| *square ^= 7);
+    }
+    if squares[0] / 8 > 3 {
+        squares.iter_mut().for_each(|square| *square ^= 56);
+    }
+}
+
+/// Index of a canonical position: side to move (0 for the attacker), the
+/// attacking king, the defending king and then the other pieces.
+fn index(defender_to_move: bool, squares: &[usize]) -> usize {
+    let king = squares[0] / 8 * 4 + squares[0] % 8;
+    let mut index = (defender_to_move as usize) * 16 + king;
+    for &square in &squares[1..] {
+        index = index * 64 + square;
+    }
+    index
+}
+
+/// The squares of the position at `index`.
+fn decode(mut index: usize, squares: &mut [usize]) {
+    for square in squares[1..].iter_mut().rev() {
+        *square = index % 64;
+        index /= 64;
+    }
+    let king = index % 16;
+    squares[0] = king / 4 * 8 + king % 4;
+}
+
+/// Attack tables used while generating.
+struct Attacks {
+    king: [u64; 64],
+    knight: [u64; 64],
+}
+
+impl Attacks {
+    fn of(&self, piece: u8, square: usize, occupied: u64) -> u64 {
+        let (rooks, bishops) = slider_tables();
+        match piece {
+            KING_PIECE_TYPE => self.king[square],
+            KNIGHT_PIECE_TYPE => self.knight[square],
+            BISHOP_PIECE_TYPE => bishops.lookup(square, occupied),
+            ROOK_PIECE_TYPE => rooks.lookup(square, occupied),
+            _ => bishops.lookup(square, occupied) | rooks.lookup(square, occupied),
+        }
+    }
+
+    /// Whether the attacker's pieces, except the one on `taken`, attack `target`.
+    fn attacked(&self, types: &[u8], squares: &[usize], target: usize, occupied: u64, taken: usize) -> bool {
+        types.iter().zip(squares).enumerate().any(|(i, (&piece, &square))| {
+            i != 1 && square != taken && self.of(piece, square, occupied) & (1 << target) != 0
+        })
+    }
+}
+
+impl DtmTable {
+    /// Solves the endgame named like `KQvK` or `KBNvK`, or returns `None`
+    /// if it is not supported.
+    pub fn generate(name: &str) -> Option<Self> {
+        let pieces = parse_name(name)?;
+        let name = format!("K{}vK", pieces.iter().map(|&piece| piece_letter(piece)).collect::<String>());
+        let attacks = Attacks {
+            king: king_attack_table(),
+            knight: knight_attack_table(),
+        };
+        // The defending king is second and never moves as an attacker.
+        let mut types = vec![KING_PIECE_TYPE, KING_PIECE_TYPE];
+        types.extend(&pieces);
+        let size = 2 * 16 * 64usize.pow(types.len() as u32 - 1);
+        let mut dtm = vec![0u8; size];
+        // Legal defender moves not yet known to lose, for defender-to-move positions.
+        let mut replies = vec![0u8; size / 2];
+        let mut squares = vec![0; types.len()];
+
+        let mut lost = Vec::new();
+        for i in size / 2..size {
+            decode(i, &mut squares);
+            let occupied = squares.iter().fold(0u64, |bb, &square| bb | 1 << square);
+            let (king, defender) = (squares[0], squares[1]);
+            if occupied.count_ones() as usize != squares.len() || attacks.king[king] & (1 << defender) != 0 {
+                continue;
+            }
+            let mut count = 0;
+            let mut escapes = false;
+            let mut targets = attacks.king[defender] & !attacks.king[king];
+            while targets != 0 {
+                let to = lsb1(targets);
+                targets &= targets - 1;
+                if !attacks.attacked(&types, &squares, to, occupied ^ (1 << defender), to) {
+                    // Taking a piece leaves too little to mate with.
+                    escapes |= occupied & (1 << to) != 0;
+                    count += 1;
+                }
+            }
+            if escapes {
+                continue;
+            }
+            if count == 0 {
+                if attacks.attacked(&types, &squares, defender, occupied, 64) {
+                    dtm[i] = 1;
+                    lost.push(i);
+                }
+                continue;
+            }
+            replies[i - size / 2] = count;
+        }
+
+        // Positions are reached in order of distance, so the first one found is the shortest mate
+        // and a defender only loses once its last reply is known to lose.
+        let mut plies = 0u8;
+        while !lost.is_empty() {
+            let mut won = Vec::new();
+            for &i in &lost {
+                decode(i, &mut squares);
+                let occupied = squares.iter().fold(0u64, |bb, &square| bb | 1 << square);
+                for piece in (0..types.len()).filter(|&piece| piece != 1) {
+                    let mut origins = attacks.of(types[piece], squares[piece], occupied) & !occupied;
+                    if piece == 0 {
+                        origins &= !attacks.king[squares[1]];
+                    }
+                    while origins != 0 {
+                        let from = lsb1(origins);
+                        origins &= origins - 1;
+                        let mut before = squares.clone();
+                        before[piece] = from;
+                        let occupied = occupied ^ (1 << squares[piece]) ^ (1 << from);
+                        if attacks.attacked(&types, &before, before[1], occupied, 64) {
+                            continue;
+                        }
+                        canonical(&mut before);
+                        let j = index(false, &before);
+                        if dtm[j] == 0 {
+                            dtm[j] = plies + 2;
+                            won.push(j);
+                        }
+                    }
+                }
+            }
+            plies += 1;
+
+            lost.clear();
+            for &i in &won {
+                decode(i, &mut squares);
+                let occupied = squares.iter().fold(0u64, |bb, &square| bb | 1 << square);
+                let mut origins = attacks.king[squares[1]] & !occupied & !attacks.king[squares[0]];
+                while origins != 0 {
+                    let from = lsb1(origins);
+                    origins &= origins - 1;
+                    let mut before = squares.clone();
+                    before[1] = from;
+                    canonical(&mut before);
+                    let j = index(true, &before);
+                    let count = &mut replies[j - size / 2];
+                    if *count > 0 {
+                        *count -= 1;
+                        if *count == 0 {
+                            dtm[j] = plies + 2;
+                            lost.push(j);
+                        }
+                    }
+                }
+            }
+            plies += 1;
+        }
+        Some(Self { name, pieces, dtm })
+    }
+
+    /// The canonical name, e.g. `KBNvK`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of pieces on the board including both kings.
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len() + 2
+    }
+
+    /// The exact result of `board`, or `None` if it has different material
+    /// or castling rights.
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        if board.castling.any() || board.occupied.count_ones() as usize != self.piece_count() {
+            return None;
+        }
+        let strong = [Side::WHITE, Side::BLACK]
+            .into_iter()
+            .find(|&side| board.pieces[!side] == board.kings[!side])?;
+        let mut squares = vec![lsb1(board.kings[strong]), lsb1(board.kings[!strong])];
+        let mut taken = 0u64;
+        for &piece in &self.pieces {
+            let bitboard = match piece {
+                QUEEN_PIECE_TYPE => board.queens[strong],
+                ROOK_PIECE_TYPE => board.rooks[strong],
+                BISHOP_PIECE_TYPE => board.bishops[strong],
+                _ => board.knights[strong],
+            } & !taken;
+            if bitboard == 0 {
+                return None;
+            }
+            squares.push(lsb1(bitboard));
+            taken |= bitboard & bitboard.wrapping_neg();
+        }
+        if taken | board.kings[strong] != board.pieces[strong] {
+            return None;
+        }
+        canonical(&mut squares);
+        let defender_to_move = board.side_to_move != strong;
+        Some(match self.dtm[index(defender_to_move, &squares)] {
+            0 => Dtm::Draw,
+            dtm if defender_to_move => Dtm::Loss(dtm as u32 - 1),
+            dtm => Dtm::Win(dtm as u32 - 1),
+        })
+    }
+
+    /// The longest mate in plies.
+    pub fn longest_mate(&self) -> u32 {
+        self.dtm.iter().max().map_or(0, |&dtm| (dtm as u32).saturating_sub(1))
+    }
+
+    /// The raw table, one byte per position; see `from_bytes`.
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.dtm
+    }
+
+    /// Reads a table written by `to_bytes` for the endgame `name`.
+    pub fn from_bytes(name: &str, bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let pieces = parse_name(name).ok_or_else(|| invalid("unsupported endgame"))?;
+        if bytes.len() != 2 * 16 * 64usize.pow(pieces.len() as u32 + 1) {
+            return Err(invalid("endgame table has the wrong size"));
+        }
+        let name = format!("K{}vK", pieces.iter().map(|&piece| piece_letter(piece)).collect::<String>());
+        Ok(Self {
+            name,
+            pieces,
+            dtm: bytes.to_vec(),
+        })
+    }
+
+    pub fn load(name: &str, path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_bytes(name, &std::fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, &self.dtm)
+    }
+}
+
+/// A set of distance-to-mate tables for the search to probe.
+#[derive(Clone, Debug, Default)]
+pub struct Endgames {
+    tables: Vec<DtmTable>,
+    max_pieces: usize,
+}
+
+impl Endgames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates KQvK, KRvK and KBNvK, which takes a few seconds.
+    pub fn standard() -> Self {
+        let mut endgames = Self::new();
+        for name in ["KQvK", "KRvK", "KBNvK"] {
+            endgames.add(DtmTable::generate(name).expect("supported endgame"));
+        }
+        endgames
+    }
+
+    pub fn add(&mut self, table: DtmTable) {
+        self.max_pieces = self.max_pieces.max(table.piece_count());
+        self.tables.retain(|other| other.name != table.name);
+        self.tables.push(table);
+    }
+
+    pub fn tables(&self) -> &[DtmTable] {
+        &self.tables
+    }
+
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        if board.occupied.count_ones() as usize > self.max_pieces || board.variant != Variant::Standard {
+            return None;
+        }
+        self.tables.iter().find_map(|table| table.probe(board))
+    }
+}
+
+impl Board {
+    /// Whether the evaluation of this position is overridden by a known result,
+    /// which `trace` does not follow.
+    pub fn is_known_endgame(&self) -> bool {
+        self.occupied.count_ones() == 3
+            && (self.pawns[0] | self.pawns[1]).count_ones() == 1
+            && self.variant == Variant::Standard
+    }
+
+    /// Corrects `score` for endgames whose result is known: drawn KPK
+    /// positions score 0 and won ones get a bonus.
+    pub(crate) fn known_endgame(&self, score: i32) -> i32 {
+        if !self.is_known_endgame() {
+            return score;
+        }
+        let strong = if self.pawns[Side::WHITE] != 0 { Side::WHITE } else { Side::BLACK };
+        let mut squares = [
+            lsb1(self.kings[strong]),
+            lsb1(self.pawns[strong]),
+            lsb1(self.kings[!strong]),
+        ];
+        if strong == Side::BLACK {
+            squares.iter_mut().for_each(|square| *square ^= 56);
+        }
+        if squares[1] % 8 > 3 {
+            squares.iter_mut().for_each(|square| *square ^= 7);
+        }
+        let [king, pawn, defender] = squares;
+        if !kpk::probe(king, pawn, defender, self.side_to_move == strong) {
+            return 0;
+        }
+        if self.side_to_move == strong {
+            score.max(0) + KPK_WIN_BONUS
+        } else {
+            score.min(0) - KPK_WIN_BONUS
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! King and pawn against king bitbase, built by retrograde iteration. The
+//! search builds it up front with `init`; otherwise the first probe does.
+//! Positions are stored with the pawn white and on files a-d.
+
+use std::sync::OnceLock;
+
+use crate::utils::lsb1;
+use crate::{king_attack_table, pawn_attack_table, WHITE};
+
+/// White king, black king, side to move, pawn file a-d and pawn rank 2-7.
+const MAX_INDEX: usize = 2 * 24 * 64 * 64;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+fn index(black_to_move: bool, black_king: usize, white_king: usize, pawn: usize) -> usize {
+    white_king | black_king << 6 | (black_to_move as usize) << 12 | (pawn % 8) << 13 | (6 - pawn / 8) << 15
+}
+
+/// Whether white wins with its king on `white_king` and pawn on `pawn`
+/// against the black king on `black_king`. The pawn must be on files a-d.
+pub fn probe(white_king: usize, pawn: usize, black_king: usize, white_to_move: bool) -> bool {
+    debug_assert!(pawn % 8 < 4 && (8..56).contains(&pawn));
+    let bits = bitbase();
+    let index = index(!white_to_move, black_king, white_king, pawn);
+    bits[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn distance(a: usize, b: usize) -> usize {
+    (a % 8).abs_diff(b % 8).max((a / 8).abs_diff(b / 8))
+}
+
+/// Builds the bitbase now, which takes a few milliseconds, so that the first
+/// probe does not.
+pub fn init() {
+    bitbase();
+}
+
+fn bitbase() -> &'static [u64] {
+    static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+    BITBASE.get_or_init(generate)
+}
+
+struct Position {
+    black_to_move: bool,
+    kings: [usize; 2],
+    pawn: usize,
+}
+
+impl Position {
+    fn from_index(index: usize) -> Self {
+        let file = (index >> 13) & 3;
+        let rank = 6 - ((index >> 15) & 7);
+        Self {
+            black_to_move: (index >> 12) & 1 != 0,
+            kings: [index & 63, (index >> 6) & 63],
+            pawn: rank * 8 + file,
+        }
+    }
+}
+
+fn generate() -> Vec<u64> {
+    let king = king_attack_table();
+    let pawn_attacks = pawn_attack_table()[WHITE];
+
+    let mut db: Vec<u8> = (0..MAX_INDEX)
+        .map(|index| {
+            let Position { black_to_move, kings: [wk, bk], pawn } = Position::from_index(index);
+            let push = pawn + 8;
+            let black_in_check = pawn_attacks[pawn] & (1 << bk) != 0;
+            if distance(wk, bk) <= 1 || wk == pawn || bk == pawn || (!black_to_move && black_in_check) {
+                INVALID
+            } else if !black_to_move
+                && pawn / 8 == 6
+                && wk != push
+                && (distance(bk, push) > 1 || king[wk] & (1 << push) != 0)
+            {
+                // The pawn promotes and the queen cannot be taken.
+                WIN
+            } else if black_to_move
+                && (king[bk] & !(king[wk] | pawn_attacks[pawn]) == 0 || king[bk] & !king[wk] & (1 << pawn) != 0)
+            {
+                // Stalemate, or the black king takes the undefended pawn.
+                DRAW
+            } else {
+                UNKNOWN
+            }
+        })
+        .collect();
+
+    // A position is won if white can reach a win or black cannot avoid one.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..MAX_INDEX {
+            if db[index] != UNKNOWN {
+                continue;
+            }
+            let Position { black_to_move, kings: [wk, bk], pawn } = Position::from_index(index);
+            let mut reachable = INVALID;
+            if black_to_move {
+                let mut targets = king[bk];
+                while targets != 0 {
+                    reachable |= db[self::index(false, lsb1(targets), wk, pawn)];
+                    targets &= targets - 1;
+                }
+            } else {
+                let mut targets = king[wk];
+                while targets != 0 {
+                    reachable |= db[self::index(true, bk, lsb1(targets), pawn)];
+                    targets &= targets - 1;
+                }
+                if pawn / 8 < 6 {
+                    reachable |= db[self::index(true, bk, wk, pawn + 8)];
+                }
+                if pawn / 8 == 1 && pawn + 8 != wk && pawn + 8 != bk {
+                    reachable |= db[self::index(true, bk, wk, pawn + 16)];
+                }
+            }
+            let (good, bad) = if black_to_move { (DRAW, WIN) } else { (WIN, DRAW) };
+            let result = if reachable & good != 0 {
+                good
+            } else if reachable & UNKNOWN != 0 {
+                UNKNOWN
+            } else {
+                bad
+            };
+            if result != UNKNOWN {
+                db[index] = result;
+                changed = true;
+            }
+        }
+    }
+
+    let mut bits = vec![0u64; MAX_INDEX / 64];
+    for (index, &result) in db.iter().enumerate() {
+        if result == WIN {
+            bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bits
+}
@@ -0,0 +1,501 @@
+//! A client for UCI engines running as local processes.
+//!
+//! `UciEngine::go` blocks until the engine's best move. For callers that
+//! cannot block, `start_search` returns at once and `try_event` polls for the
+//! parsed `info` lines and the final `bestmove` as they arrive. Moves the
+//! engine sends are checked against the position that was sent to it.
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+use crate::search::{Bound, Limits};
+use crate::{Board, Move, STARTPOS, WHITE};
+
+/// How long an engine may take to answer anything but `go`.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum EngineError {
+    Io(io::Error),
+    /// The engine did not answer in time.
+    Timeout,
+    /// The engine closed its output, normally because it exited.
+    Exited,
+    /// The engine sent a best move that is not legal in the position.
+    IllegalMove(String),
+    /// A search was started before any position was set.
+    NoPosition,
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Io(err) => write!(f, "engine I/O error: {err}"),
+            EngineError::Timeout => write!(f, "engine timed out"),
+            EngineError::Exited => write!(f, "engine exited"),
+            EngineError::IllegalMove(mv) => write!(f, "engine sent illegal move '{mv}'"),
+            EngineError::NoPosition => write!(f, "no position set"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EngineError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for EngineError {
+    fn from(err: io::Error) -> Self {
+        EngineError::Io(err)
+    }
+}
+
+/// An option the engine declared during the handshake.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EngineOption {
+    pub name: String,
+    /// `check`, `spin`, `combo`, `button` or `string`.
+    pub kind: String,
+    pub default: Option<String>,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    /// The choices of a `combo` option.
+    pub vars: Vec<String>,
+}
+
+/// A score from the side to move's point of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineScore {
+    Cp(i32),
+    /// Moves to mate, negative when the side to move is mated.
+    Mate(i32),
+}
+
+/// One `info` line. Fields the engine left out are `None` or empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EngineInfo {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<usize>,
+    pub score: Option<EngineScore>,
+    /// Whether `score` is exact or only a bound.
+    pub bound: Bound,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time: Option<Duration>,
+    /// Permille of the hash table in use.
+    pub hashfull: Option<usize>,
+    /// The principal variation up to its first move that is not legal.
+    pub pv: Vec<Move>,
+    pub string: Option<String>,
+}
+
+impl Default for EngineInfo {
+    fn default() -> Self {
+        Self {
+            depth: None,
+            seldepth: None,
+            multipv: None,
+            score: None,
+            bound: Bound::Exact,
+            nodes: None,
+            nps: None,
+            time: None,
+            hashfull: None,
+            pv: Vec::new(),
+            string: None,
+        }
+    }
+}
+
+/// The engine's answer to `go`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BestMove {
+    /// `None` if the engine had no move to play (`bestmove 0000` or `(none)`).
+    pub best: Option<Move>,
+    /// The expected reply, `None` if the engine sent none or an illegal one.
+    pub ponder: Option<Move>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EngineEvent {
+    Info(EngineInfo),
+    BestMove(BestMove),
+}
+
+/// A UCI engine process. It is told to quit, and killed if it does not, when
+/// dropped.
+pub struct UciEngine {
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<EngineOption>,
+    chess960: bool,
+    /// The position last sent, after its moves.
+    position: Option<Board>,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl UciEngine {
+    /// Starts the engine and waits for `uciok`.
+    pub fn spawn(
+        command: impl AsRef<OsStr>,
+        args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    ) -> Result<Self, EngineError> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        // Lines come through a channel so reads can time out or be polled.
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            name: None,
+            author: None,
+            options: Vec::new(),
+            chess960: false,
+            position: None,
+            child,
+            stdin,
+            lines,
+        };
+
+        engine.send("uci")?;
+        loop {
+            let line = engine.read_line(Some(RESPONSE_TIMEOUT))?;
+            let line = line.trim();
+            if line == "uciok" {
+                break;
+            } else if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = Some(name.trim().to_string());
+            } else if let Some(author) = line.strip_prefix("id author ") {
+                engine.author = Some(author.trim().to_string());
+            } else if let Some(option) = line.strip_prefix("option ").and_then(parse_option) {
+                engine.options.push(option);
+            }
+        }
+        Ok(engine)
+    }
+
+    /// The engine's `id name`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn options(&self) -> &[EngineOption] {
+        &self.options
+    }
+
+    /// Sends a raw command line.
+    pub fn send(&mut self, command: &str) -> Result<(), EngineError> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// Sets an option. Setting `UCI_Chess960` also switches how castling moves
+    /// are written to the engine.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError> {
+        if name.eq_ignore_ascii_case("UCI_Chess960") {
+            self.chess960 = value.eq_ignore_ascii_case("true");
+        }
+        self.send(&format!("setoption name {name} value {value}"))
+    }
+
+    /// Waits for `readyok`, dropping anything sent before it.
+    pub fn is_ready(&mut self) -> Result<(), EngineError> {
+        self.send("isready")?;
+        while self.read_line(Some(RESPONSE_TIMEOUT))?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    pub fn new_game(&mut self) -> Result<(), EngineError> {
+        self.send("ucinewgame")?;
+        self.position = None;
+        self.is_ready()
+    }
+
+    /// Sends `start` followed by `moves`, which must be legal.
+    pub fn set_position(&mut self, start: &Board, moves: &[Move]) -> Result<(), EngineError> {
+        let mut command = match start.to_fen() {
+            fen if fen == STARTPOS => String::from("position startpos"),
+            fen => format!("position fen {fen}"),
+        };
+        let mut board = start.clone();
+        if !moves.is_empty() {
+            command.push_str(" moves");
+        }
+        for mv in moves {
+            command.push(' ');
+            command.push_str(&mv.to_uci(self.chess960));
+            board.make_move(mv);
+        }
+        self.send(&command)?;
+        self.position = Some(board);
+        Ok(())
+    }
+
+    /// The position last sent with `set_position`.
+    pub fn position(&self) -> Option<&Board> {
+        self.position.as_ref()
+    }
+
+    /// Starts searching the current position without waiting. `limits.time`
+    /// is sent as the side to move's clock. MultiPV is an engine option, so
+    /// `limits.multipv` is not sent; use `set_option`.
+    pub fn start_search(&mut self, limits: &Limits) -> Result<(), EngineError> {
+        let Some(board) = &self.position else {
+            return Err(EngineError::NoPosition);
+        };
+        let (clock, inc) = if board.side_to_move() == WHITE { ("wtime", "winc") } else { ("btime", "binc") };
+        let mut command = String::from("go");
+        if let Some(time) = limits.time {
+            command.push_str(&format!(" {clock} {} {inc} {}", time.as_millis(), limits.increment.as_millis()));
+        }
+        if let Some(moves_to_go) = limits.moves_to_go {
+            command.push_str(&format!(" movestogo {moves_to_go}"));
+        }
+        if let Some(depth) = limits.depth {
+            command.push_str(&format!(" depth {depth}"));
+        }
+        if let Some(nodes) = limits.nodes {
+            command.push_str(&format!(" nodes {nodes}"));
+        }
+        if let Some(movetime) = limits.movetime {
+            command.push_str(&format!(" movetime {}", movetime.as_millis()));
+        }
+        if limits.infinite {
+            command.push_str(" infinite");
+        }
+        if !limits.searchmoves.is_empty() {
+            command.push_str(" searchmoves");
+            for mv in &limits.searchmoves {
+                command.push(' ');
+                command.push_str(&mv.to_uci(self.chess960));
+            }
+        }
+        self.send(&command)
+    }
+
+    /// Asks the engine to finish searching; its `bestmove` still has to be read.
+    pub fn stop(&mut self) -> Result<(), EngineError> {
+        self.send("stop")
+    }
+
+    /// The next event if one has already arrived, without blocking.
+    pub fn try_event(&mut self) -> Result<Option<EngineEvent>, EngineError> {
+        loop {
+            let line = match self.lines.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => return Err(EngineError::Exited),
+            };
+            if let Some(event) = self.parse_event(&line)? {
+                return Ok(Some(event));
+            }
+        }
+    }
+
+    /// Waits for the next event, at most `timeout` if given.
+    pub fn next_event(&mut self, timeout: Option<Duration>) -> Result<EngineEvent, EngineError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let line = self.read_line(deadline.map(|d| d.saturating_duration_since(Instant::now())))?;
+            if let Some(event) = self.parse_event(&line)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Searches the current position and waits for the best move, calling
+    /// `on_info` with every `info` line on the way. After `timeout` the search
+    /// is stopped and its `bestmove` dropped, so the engine is ready for the
+    /// next command.
+    pub fn go(
+        &mut self,
+        limits: &Limits,
+        timeout: Option<Duration>,
+        mut on_info: impl FnMut(&EngineInfo),
+    ) -> Result<BestMove, EngineError> {
+        self.start_search(limits)?;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            match self.next_event(deadline.map(|d| d.saturating_duration_since(Instant::now()))) {
+                Ok(EngineEvent::Info(info)) => on_info(&info),
+                Ok(EngineEvent::BestMove(best)) => return Ok(best),
+                Err(EngineError::Timeout) => {
+                    // A late `bestmove` would otherwise answer the next search.
+                    self.stop()?;
+                    while !self.read_line(Some(RESPONSE_TIMEOUT))?.trim_start().starts_with("bestmove") {}
+                    return Err(EngineError::Timeout);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn read_line(&self, timeout: Option<Duration>) -> Result<String, EngineError> {
+        match timeout {
+            Some(timeout) => self.lines.recv_timeout(timeout).map_err(|err| match err {
+                RecvTimeoutError::Timeout => EngineError::Timeout,
+                RecvTimeoutError::Disconnected => EngineError::Exited,
+            }),
+            None => self.lines.recv().map_err(|_| EngineError::Exited),
+        }
+    }
+
+    /// Parses `info` and `bestmove` lines; anything else is ignored.
+    fn parse_event(&self, line: &str) -> Result<Option<EngineEvent>, EngineError> {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("info") {
+            return Ok(Some(EngineEvent::Info(parse_info(rest, self.position.as_ref()))));
+        }
+        let Some(rest) = line.strip_prefix("bestmove") else {
+            return Ok(None);
+        };
+        let mut tokens = rest.split_whitespace();
+        let best = tokens.next().unwrap_or("0000");
+        if matches!(best, "0000" | "(none)") {
+            return Ok(Some(EngineEvent::BestMove(BestMove { best: None, ponder: None })));
+        }
+        let mut board = self.position.clone().ok_or_else(|| EngineError::IllegalMove(best.to_string()))?;
+        let mv = board.parse_uci_move(best).ok_or_else(|| EngineError::IllegalMove(best.to_string()))?;
+        board.make_move(&mv);
+        let ponder = match (tokens.next(), tokens.next()) {
+            (Some("ponder"), Some(ponder)) => board.parse_uci_move(ponder),
+            _ => None,
+        };
+        Ok(Some(EngineEvent::BestMove(BestMove { best: Some(mv), ponder })))
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Parses what follows `option`, like `name Hash type spin default 16 min 1 max 1024`.
+fn parse_option(s: &str) -> Option<EngineOption> {
+    const KEYWORDS: [&str; 6] = ["name", "type", "default", "min", "max", "var"];
+    let mut option = EngineOption::default();
+    let mut tokens = s.split_whitespace().peekable();
+    while let Some(keyword) = tokens.next() {
+        let mut words = Vec::new();
+        while let Some(word) = tokens.next_if(|word| !KEYWORDS.contains(word)) {
+            words.push(word);
+        }
+        let value = words.join(" ");
+        match keyword {
+            "name" => option.name = value,
+            "type" => option.kind = value,
+            "default" => option.default = Some(if value == "<empty>" { String::new() } else { value }),
+            "min" => option.min = value.parse().ok(),
+            "max" => option.max = value.parse().ok(),
+            "var" => option.vars.push(value),
+            _ => {}
+        }
+    }
+    (!option.name.is_empty()).then_some(option)
+}
+
+/// Parses what follows `info`. The pv is replayed on `board` and cut at its
+/// first illegal move; without a board it is left empty.
+fn parse_info(s: &str, board: Option<&Board>) -> EngineInfo {
+    const KEYWORDS: [&str; 14] = [
+        "depth",
+        "seldepth",
+        "multipv",
+        "score",
+        "nodes",
+        "nps",
+        "time",
+        "hashfull",
+        "tbhits",
+        "cpuload",
+        "currmove",
+        "currmovenumber",
+        "pv",
+        "string",
+    ];
+    let mut info = EngineInfo::default();
+    let mut tokens = s.split_whitespace().peekable();
+    while let Some(keyword) = tokens.next() {
+        match keyword {
+            "depth" => info.depth = tokens.next().and_then(|t| t.parse().ok()),
+            "seldepth" => info.seldepth = tokens.next().and_then(|t| t.parse().ok()),
+            "multipv" => info.multipv = tokens.next().and_then(|t| t.parse().ok()),
+            "nodes" => info.nodes = tokens.next().and_then(|t| t.parse().ok()),
+            "nps" => info.nps = tokens.next().and_then(|t| t.parse().ok()),
+            "hashfull" => info.hashfull = tokens.next().and_then(|t| t.parse().ok()),
+            "time" => info.time = tokens.next().and_then(|t| t.parse().ok()).map(Duration::from_millis),
+            "score" => {
+                let kind = tokens.next();
+                let value = tokens.next().and_then(|t| t.parse().ok());
+                info.score = match (kind, value) {
+                    (Some("cp"), Some(cp)) => Some(EngineScore::Cp(cp)),
+                    (Some("mate"), Some(moves)) => Some(EngineScore::Mate(moves)),
+                    _ => None,
+                };
+                info.bound = match tokens.next_if(|t| matches!(*t, "lowerbound" | "upperbound")) {
+                    Some("lowerbound") => Bound::Lower,
+                    Some("upperbound") => Bound::Upper,
+                    _ => Bound::Exact,
+                };
+            }
+            "pv" => {
+                let mut board = board.cloned();
+                let mut legal = board.is_some();
+                while let Some(token) = tokens.next_if(|t| !KEYWORDS.contains(t)) {
+                    let Some(board) = board.as_mut().filter(|_| legal) else { continue };
+                    match board.parse_uci_move(token) {
+                        Some(mv) => {
+                            board.make_move(&mv);
+                            info.pv.push(mv);
+                        }
+                        None => legal = false,
+                    }
+                }
+            }
+            // The rest of the line is free text.
+            "string" => {
+                info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" "));
+            }
+            _ => {}
+        }
+    }
+    info
+}
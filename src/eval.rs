@@ -0,0 +1,590 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::sync::Arc;
+
+use crate::utils::lsb1;
+use crate::{Board, Side, Variant, BLACK, WHITE};
+
+mod king_safety;
+mod mobility;
+mod pawns;
+pub use mobility::AttackInfo;
+pub use pawns::{PawnEntry, PawnTable};
+
+/// Phase of a position with all minor and major pieces still on the board.
+pub const MAX_PHASE: i32 = 24;
+
+/// A middlegame/endgame pair of centipawn values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const ZERO: Score = Score { mg: 0, eg: 0 };
+
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+    /// Interpolates between the middlegame and endgame value.
+    /// `phase` goes from `MAX_PHASE` (opening) down to 0 (bare kings and pawns).
+    pub fn taper(self, phase: i32) -> i32 {
+        let phase = phase.clamp(0, MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Score::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+impl AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Score::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+impl SubAssign for Score {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Self::Output {
+        Score::new(-self.mg, -self.eg)
+    }
+}
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        Score::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+/// Every evaluation parameter. The tuner reuses the layout with `T = [i32; 2]` to
+/// count how often each term applies to either side; see `EvalTrace`.
+///
+/// Arrays indexed by piece use `piece - 1`, so index 0 is the pawn and 5 the king.
+/// Piece-square tables are written from white's point of view with a8 first, the way
+/// they read on a diagram; `pst_index` does the flipping.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Weights<T> {
+    pub material: [T; 6],
+    pub pst: [[T; 64]; 6],
+    pub tempo: T,
+
+    // Pawn structure. Arrays indexed by rank are relative to the pawn's side.
+    pub doubled: T,
+    pub isolated: T,
+    pub backward: T,
+    pub connected: [T; 8],
+    pub passed: [T; 8],
+    pub passed_free_path: [T; 8],
+    /// Per square of distance to the passer's stop square, scaled by how far it has advanced.
+    pub passed_own_king_distance: T,
+    pub passed_enemy_king_distance: T,
+    pub candidate: [T; 8],
+    /// For every pawn island beyond the first.
+    pub pawn_island: T,
+
+    // Mobility, indexed by the number of reachable squares not occupied by own pieces
+    // or attacked by enemy pawns.
+    pub mobility_knight: [T; 9],
+    pub mobility_bishop: [T; 14],
+    pub mobility_rook: [T; 15],
+    pub mobility_queen: [T; 28],
+
+    // King safety
+    /// Indexed by the total attack weight on the zone, when at least two pieces attack it.
+    pub king_danger: [T; 64],
+    /// Own pawn one or two ranks in front of the king on its file or an adjacent one,
+    /// or no such pawn.
+    pub pawn_shield: [T; 3],
+    /// Enemy pawn one to four ranks in front of the king.
+    pub pawn_storm: [T; 4],
+    pub king_semi_open_file: T,
+    pub king_open_file: T,
+
+    // These decide which of the parameters above apply rather than adding a value of
+    // their own, so they are not tuned.
+    /// How much each piece contributes to the game phase.
+    pub phase: [i32; 6],
+    /// Weight of each piece type attacking a king zone square, indexed by `piece - 1`.
+    pub king_attack_weight: [i32; 6],
+}
+
+pub type EvalWeights = Weights<Score>;
+
+/// Per-side counts of how often each evaluation parameter was applied.
+pub type EvalTrace = Weights<[i32; 2]>;
+
+impl<T: Copy> Weights<T> {
+    /// All parameters set to `value`.
+    pub fn splat(value: T) -> Self {
+        Self {
+            material: [value; 6],
+            pst: [[value; 64]; 6],
+            tempo: value,
+            doubled: value,
+            isolated: value,
+            backward: value,
+            connected: [value; 8],
+            passed: [value; 8],
+            passed_free_path: [value; 8],
+            passed_own_king_distance: value,
+            passed_enemy_king_distance: value,
+            candidate: [value; 8],
+            pawn_island: value,
+            mobility_knight: [value; 9],
+            mobility_bishop: [value; 14],
+            mobility_rook: [value; 15],
+            mobility_queen: [value; 28],
+            king_danger: [value; 64],
+            pawn_shield: [value; 3],
+            pawn_storm: [value; 4],
+            king_semi_open_file: value,
+            king_open_file: value,
+            phase: [0, 1, 1, 2, 4, 0],
+            king_attack_weight: [0, 2, 2, 3, 5, 0],
+        }
+    }
+
+    fn slices(&self) -> [&[T]; 22] {
+        [
+            &self.material,
+            self.pst.as_flattened(),
+            std::slice::from_ref(&self.tempo),
+            std::slice::from_ref(&self.doubled),
+            std::slice::from_ref(&self.isolated),
+            std::slice::from_ref(&self.backward),
+            &self.connected,
+            &self.passed,
+            &self.passed_free_path,
+            std::slice::from_ref(&self.passed_own_king_distance),
+            std::slice::from_ref(&self.passed_enemy_king_distance),
+            &self.candidate,
+            std::slice::from_ref(&self.pawn_island),
+            &self.mobility_knight,
+            &self.mobility_bishop,
+            &self.mobility_rook,
+            &self.mobility_queen,
+            &self.king_danger,
+            &self.pawn_shield,
+            &self.pawn_storm,
+            std::slice::from_ref(&self.king_semi_open_file),
+            std::slice::from_ref(&self.king_open_file),
+        ]
+    }
+
+    // Same order as `slices`.
+    fn slices_mut(&mut self) -> [&mut [T]; 22] {
+        [
+            &mut self.material,
+            self.pst.as_flattened_mut(),
+            std::slice::from_mut(&mut self.tempo),
+            std::slice::from_mut(&mut self.doubled),
+            std::slice::from_mut(&mut self.isolated),
+            std::slice::from_mut(&mut self.backward),
+            &mut self.connected,
+            &mut self.passed,
+            &mut self.passed_free_path,
+            std::slice::from_mut(&mut self.passed_own_king_distance),
+            std::slice::from_mut(&mut self.passed_enemy_king_distance),
+            &mut self.candidate,
+            std::slice::from_mut(&mut self.pawn_island),
+            &mut self.mobility_knight,
+            &mut self.mobility_bishop,
+            &mut self.mobility_rook,
+            &mut self.mobility_queen,
+            &mut self.king_danger,
+            &mut self.pawn_shield,
+            &mut self.pawn_storm,
+            std::slice::from_mut(&mut self.king_semi_open_file),
+            std::slice::from_mut(&mut self.king_open_file),
+        ]
+    }
+
+    /// The tunable parameters flattened into one list, in declaration order.
+    pub fn params(&self) -> Vec<T> {
+        self.slices().concat()
+    }
+
+    /// Inverse of `params`.
+    pub fn set_params(&mut self, params: &[T]) {
+        let mut params = params.iter().copied();
+        for slice in self.slices_mut() {
+            for value in slice {
+                *value = params.next().expect("too few parameters");
+            }
+        }
+        assert!(params.next().is_none(), "too many parameters");
+    }
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        let mut pst = [[Score::ZERO; 64]; 6];
+        for (piece, table) in pst.iter_mut().enumerate() {
+            for (square, score) in table.iter_mut().enumerate() {
+                *score = Score::new(PST_MG[piece][square], PST_EG[piece][square]);
+            }
+        }
+        let mut material = [Score::ZERO; 6];
+        for (piece, score) in material.iter_mut().enumerate() {
+            *score = Score::new(MATERIAL_MG[piece], MATERIAL_EG[piece]);
+        }
+        Self {
+            material,
+            pst,
+            tempo: Score::new(10, 10),
+            doubled: Score::new(-10, -20),
+            isolated: Score::new(-5, -15),
+            backward: Score::new(-8, -10),
+            connected: by_rank([0, 5, 8, 12, 20, 35, 60, 0], [0, 3, 5, 10, 18, 30, 45, 0]),
+            passed: by_rank([0, 0, 0, 5, 20, 45, 80, 0], [0, 10, 15, 25, 45, 80, 130, 0]),
+            passed_free_path: by_rank([0, 0, 0, 2, 5, 10, 15, 0], [0, 2, 5, 10, 20, 35, 60, 0]),
+            passed_own_king_distance: Score::new(0, -3),
+            passed_enemy_king_distance: Score::new(0, 5),
+            candidate: by_rank([0, 2, 3, 5, 10, 15, 0, 0], [0, 5, 8, 12, 20, 35, 0, 0]),
+            pawn_island: Score::new(-3, -8),
+            mobility_knight: linear((-20, 4), (-25, 5)),
+            mobility_bishop: linear((-20, 3), (-25, 4)),
+            mobility_rook: linear((-10, 2), (-20, 4)),
+            mobility_queen: linear((-10, 1), (-20, 2)),
+            king_danger: std::array::from_fn(|units| {
+                let units = units as i32;
+                Score::new(-(units * units / 2).min(500), -units)
+            }),
+            pawn_shield: [Score::new(15, 0), Score::new(8, 0), Score::new(-15, 0)],
+            pawn_storm: [
+                Score::new(-10, 0),
+                Score::new(-20, 0),
+                Score::new(-10, 0),
+                Score::new(-5, 0),
+            ],
+            king_semi_open_file: Score::new(-15, 0),
+            king_open_file: Score::new(-25, 0),
+            phase: [0, 1, 1, 2, 4, 0],
+            king_attack_weight: [0, 2, 2, 3, 5, 0],
+        }
+    }
+}
+
+/// `(base, step)` pairs for the middlegame and endgame, growing with the index.
+fn linear<const N: usize>(mg: (i32, i32), eg: (i32, i32)) -> [Score; N] {
+    std::array::from_fn(|i| Score::new(mg.0 + mg.1 * i as i32, eg.0 + eg.1 * i as i32))
+}
+
+fn by_rank(mg: [i32; 8], eg: [i32; 8]) -> [Score; 8] {
+    std::array::from_fn(|rank| Score::new(mg[rank], eg[rank]))
+}
+
+impl EvalWeights {
+    /// Material plus piece-square value of `piece` standing on `square`.
+    pub fn piece_square(&self, side: Side, piece: u8, square: usize) -> Score {
+        let piece = piece as usize - 1;
+        self.material[piece] + self.pst[piece][pst_index(side, square)]
+    }
+}
+
+/// Maps a board square (a1 = 0) to an index into a piece-square table.
+pub fn pst_index(side: Side, square: usize) -> usize {
+    match side {
+        WHITE => square ^ 56,
+        BLACK => square,
+    }
+}
+
+/// The incrementally updated part of the evaluation: material, piece-square
+/// values and the game phase. `Board` keeps one up to date in make/unmake.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Psqt {
+    pub score: [Score; 2],
+    pub phase: i32,
+}
+
+impl Psqt {
+    pub fn add(&mut self, weights: &EvalWeights, side: Side, piece: u8, square: usize) {
+        self.score[side] += weights.piece_square(side, piece, square);
+        self.phase += weights.phase[piece as usize - 1];
+    }
+    pub fn remove(&mut self, weights: &EvalWeights, side: Side, piece: u8, square: usize) {
+        self.score[side] -= weights.piece_square(side, piece, square);
+        self.phase -= weights.phase[piece as usize - 1];
+    }
+    /// Score from `side`'s point of view.
+    pub fn relative(&self, side: Side) -> Score {
+        self.score[side] - self.score[!side]
+    }
+}
+
+/// Receives every evaluation parameter use while evaluating with tracing.
+pub trait Tracer {
+    fn record(&mut self, field: impl FnOnce(&mut EvalTrace) -> &mut [i32; 2], side: Side, count: i32);
+}
+
+/// Tracer for normal evaluation, compiles down to nothing.
+pub struct NoTrace;
+
+impl Tracer for NoTrace {
+    #[inline(always)]
+    fn record(&mut self, _: impl FnOnce(&mut EvalTrace) -> &mut [i32; 2], _: Side, _: i32) {}
+}
+
+impl Tracer for EvalTrace {
+    fn record(&mut self, field: impl FnOnce(&mut EvalTrace) -> &mut [i32; 2], side: Side, count: i32) {
+        field(self)[side] += count;
+    }
+}
+
+impl Board {
+    /// Static evaluation in centipawns from the side to move's point of view.
+    /// Uses the NNUE network if one is set.
+    pub fn evaluate(&self) -> i32 {
+        if self.variant == Variant::Antichess {
+            return self.evaluate_antichess();
+        }
+        let score = match &self.nnue {
+            Some(nnue) => nnue.evaluate(self.side_to_move),
+            None => self.evaluate_with_pawns(&self.pawn_structure()),
+        };
+        let score = self.known_endgame(score);
+        if self.variant == Variant::Crazyhouse { score + self.pocket_score() } else { score }
+    }
+    /// Same as `evaluate`, but looks the pawn structure up in `pawn_table` first.
+    pub fn evaluate_cached(&self, pawn_table: &mut PawnTable) -> i32 {
+        if self.variant == Variant::Antichess {
+            return self.evaluate_antichess();
+        }
+        let score = match &self.nnue {
+            Some(nnue) => nnue.evaluate(self.side_to_move),
+            None => self.evaluate_with_pawns(&pawn_table.probe(self)),
+        };
+        let score = self.known_endgame(score);
+        if self.variant == Variant::Crazyhouse { score + self.pocket_score() } else { score }
+    }
+    fn evaluate_with_pawns(&self, pawns: &PawnEntry) -> i32 {
+        // Terms below are from white's point of view.
+        let attacks = self.attack_info();
+        let score = pawns.score
+            + self.passed_pawns(pawns)
+            + self.mobility(&attacks)
+            + self.king_safety(&attacks);
+
+        let side = self.side_to_move;
+        let score = match side {
+            WHITE => score,
+            BLACK => -score,
+        };
+        (self.psqt.relative(side) + score + self.weights.tempo).taper(self.psqt.phase)
+    }
+    /// Runs the hand-written evaluation from scratch, counting how often each parameter
+    /// applies to either side. The evaluation is linear in its parameters, so summing
+    /// `(white - black) * weight` and tapering by the phase gives white's score.
+    pub fn trace(&self) -> EvalTrace {
+        let mut trace = EvalTrace::splat([0, 0]);
+        self.compute_psqt_traced(&mut trace);
+        let pawns = self.pawn_structure_traced(&mut trace);
+        self.passed_pawns_traced(&pawns, &mut trace);
+        let attacks = self.attack_info_traced(&mut trace);
+        self.king_safety_traced(&attacks, &mut trace);
+        trace.record(|t| &mut t.tempo, self.side_to_move, 1);
+        trace
+    }
+    pub fn weights(&self) -> &EvalWeights {
+        &self.weights
+    }
+    /// Swaps in a new set of weights and rebuilds the incremental state with them.
+    pub fn set_weights(&mut self, weights: Arc<EvalWeights>) {
+        self.weights = weights;
+        self.psqt = self.compute_psqt();
+    }
+    pub fn psqt(&self) -> &Psqt {
+        &self.psqt
+    }
+    /// Computes the incremental evaluation state from scratch.
+    pub fn compute_psqt(&self) -> Psqt {
+        self.compute_psqt_traced(&mut NoTrace)
+    }
+    fn compute_psqt_traced(&self, trace: &mut impl Tracer) -> Psqt {
+        let mut psqt = Psqt::default();
+        for side in [WHITE, BLACK] {
+            let bitboards = [
+                self.pawns[side],
+                self.knights[side],
+                self.bishops[side],
+                self.rooks[side],
+                self.queens[side],
+                self.kings[side],
+            ];
+            for (i, mut bb) in bitboards.into_iter().enumerate() {
+                while bb != 0 {
+                    let lsb = lsb1(bb);
+                    bb &= !(1 << lsb);
+                    psqt.add(&self.weights, side, i as u8 + 1, lsb);
+                    trace.record(|t| &mut t.material[i], side, 1);
+                    trace.record(|t| &mut t.pst[i][pst_index(side, lsb)], side, 1);
+                }
+            }
+        }
+        psqt
+    }
+}
+
+// Default values are PeSTO's (Ronald Friederich), tuned with material kept separate.
+#[rustfmt::skip]
+const MATERIAL_MG: [i32; 6] = [82, 337, 365, 477, 1025, 0];
+#[rustfmt::skip]
+const MATERIAL_EG: [i32; 6] = [94, 281, 297, 512, 936, 0];
+
+#[rustfmt::skip]
+const PST_MG: [[i32; 64]; 6] = [
+    // Pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Knight
+    [
+       -167, -89, -34, -49,  61, -97, -15,-107,
+        -73, -41,  72,  36,  23,  62,   7, -17,
+        -47,  60,  37,  65,  84, 129,  73,  44,
+         -9,  17,  19,  53,  37,  69,  18,  22,
+        -13,   4,  16,  13,  28,  19,  21,  -8,
+        -23,  -9,  12,  10,  19,  17,  25, -16,
+        -29, -53, -12,  -3,  -1,  18, -14, -19,
+       -105, -21, -58, -33, -17, -28, -19, -23,
+    ],
+    // Bishop
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    // Rook
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    // Queen
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    // King
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+];
+
+#[rustfmt::skip]
+const PST_EG: [[i32; 64]; 6] = [
+    // Pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Knight
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    // Bishop
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    // Rook
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    // Queen
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    // King
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+];
@@ -0,0 +1,82 @@
+use super::pawns::relative_rank;
+use super::{AttackInfo, NoTrace, Score, Tracer};
+use crate::utils::lsb1;
+use crate::{Board, Side, BLACK, FILES, WHITE};
+
+impl Board {
+    /// King safety from white's point of view.
+    pub fn king_safety(&self, info: &AttackInfo) -> Score {
+        self.king_safety_traced(info, &mut NoTrace)
+    }
+
+    pub(crate) fn king_safety_traced(&self, info: &AttackInfo, trace: &mut impl Tracer) -> Score {
+        self.king_safety_side(info, WHITE, trace) - self.king_safety_side(info, BLACK, trace)
+    }
+
+    fn king_safety_side(&self, info: &AttackInfo, side: Side, trace: &mut impl Tracer) -> Score {
+        let weights = &self.weights;
+        // Horde has a side without a king.
+        if self.kings[side] == 0 {
+            return Score::ZERO;
+        }
+        let king = lsb1(self.kings[side]);
+        let king_rank = relative_rank(side, king);
+        let king_file = king % 8;
+        let mut score = Score::ZERO;
+
+        // A lone attacker rarely gets anywhere, so only count coordinated attacks.
+        if info.king_attackers[!side] >= 2 {
+            let units = info.king_attack_units[!side].clamp(0, 63) as usize;
+            score += weights.king_danger[units];
+            trace.record(|t| &mut t.king_danger[units], side, 1);
+        }
+
+        let own_pawns = self.pawns[side];
+        let enemy_pawns = self.pawns[!side];
+        let files = king_file.saturating_sub(1)..=(king_file + 1).min(7);
+        for &file_bb in &FILES[files] {
+            // Closest own pawn in front of the king on this file.
+            let shield = own_pawns & file_bb;
+            let shield_distance = closest_in_front(side, shield, king_rank);
+            let shield_index = match shield_distance {
+                Some(1) => 0,
+                Some(2) => 1,
+                _ => 2,
+            };
+            score += weights.pawn_shield[shield_index];
+            trace.record(|t| &mut t.pawn_shield[shield_index], side, 1);
+
+            let storm = enemy_pawns & file_bb;
+            if let Some(distance @ 1..=4) = closest_in_front(side, storm, king_rank) {
+                score += weights.pawn_storm[distance - 1];
+                trace.record(|t| &mut t.pawn_storm[distance - 1], side, 1);
+            }
+
+            if own_pawns & file_bb == 0 {
+                if enemy_pawns & file_bb == 0 {
+                    score += weights.king_open_file;
+                    trace.record(|t| &mut t.king_open_file, side, 1);
+                } else {
+                    score += weights.king_semi_open_file;
+                    trace.record(|t| &mut t.king_semi_open_file, side, 1);
+                }
+            }
+        }
+        score
+    }
+}
+
+/// Rank distance from `king_rank` to the nearest pawn of `pawns` in front of the king.
+fn closest_in_front(side: Side, mut pawns: u64, king_rank: usize) -> Option<usize> {
+    let mut closest = None;
+    while pawns != 0 {
+        let sq = lsb1(pawns);
+        pawns &= !(1 << sq);
+        let rank = relative_rank(side, sq);
+        if rank > king_rank {
+            let distance = rank - king_rank;
+            closest = Some(closest.map_or(distance, |c: usize| c.min(distance)));
+        }
+    }
+    closest
+}
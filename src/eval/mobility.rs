@@ -0,0 +1,117 @@
+use super::{NoTrace, Score, Tracer};
+use crate::movegen::*;
+use crate::utils::lsb1;
+use crate::{Board, Side, BLACK, WHITE};
+
+/// Attack maps gathered in one pass over the pieces, shared by the mobility and
+/// king safety terms.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AttackInfo {
+    /// Squares attacked by each side's pieces, indexed by `piece - 1`.
+    pub by_piece: [[u64; 6]; 2],
+    pub all: [u64; 2],
+    /// Squares around each side's king that the enemy attacks into.
+    pub king_zone: [u64; 2],
+    /// Number of pieces of a side attacking the enemy king zone.
+    pub king_attackers: [i32; 2],
+    /// Sum of attack weights of those pieces over the zone squares they hit.
+    pub king_attack_units: [i32; 2],
+    /// From each side's own point of view.
+    pub mobility: [Score; 2],
+}
+
+impl Board {
+    pub(crate) fn king_zone(&self, side: Side) -> u64 {
+        if self.kings[side] == 0 {
+            return 0;
+        }
+        let king = lsb1(self.kings[side]);
+        let zone = self.king_attack_table[king] | (1 << king);
+        // Extend one rank towards the enemy, where the attacks come from.
+        match side {
+            WHITE => zone | (zone << 8),
+            BLACK => zone | (zone >> 8),
+        }
+    }
+
+    pub fn attack_info(&self) -> AttackInfo {
+        self.attack_info_traced(&mut NoTrace)
+    }
+
+    pub(crate) fn attack_info_traced(&self, trace: &mut impl Tracer) -> AttackInfo {
+        let weights = &self.weights;
+        let mut info = AttackInfo::default();
+        for side in [WHITE, BLACK] {
+            let pawn_attacks = match side {
+                WHITE => self.w_pawn_attacks(),
+                BLACK => self.b_pawn_attacks(),
+            };
+            let king_attacks = self.attacks_of_kings(side);
+            info.by_piece[side][PAWN_PIECE_TYPE as usize - 1] = pawn_attacks;
+            info.by_piece[side][KING_PIECE_TYPE as usize - 1] = king_attacks;
+            info.all[side] = pawn_attacks | king_attacks;
+            info.king_zone[side] = self.king_zone(side);
+        }
+
+        for side in [WHITE, BLACK] {
+            let enemy_pawn_attacks = info.by_piece[!side][PAWN_PIECE_TYPE as usize - 1];
+            let mobility_area = !self.pieces[side] & !enemy_pawn_attacks;
+            let enemy_zone = info.king_zone[!side];
+            let pieces = [
+                (KNIGHT_PIECE_TYPE, self.knights[side]),
+                (BISHOP_PIECE_TYPE, self.bishops[side]),
+                (ROOK_PIECE_TYPE, self.rooks[side]),
+                (QUEEN_PIECE_TYPE, self.queens[side]),
+            ];
+            for (piece, mut bb) in pieces {
+                while bb != 0 {
+                    let sq = lsb1(bb);
+                    bb &= !(1 << sq);
+                    let attacks = match piece {
+                        KNIGHT_PIECE_TYPE => self.knight_attack_table[sq],
+                        BISHOP_PIECE_TYPE => self.bishop_table.lookup(sq, self.occupied),
+                        ROOK_PIECE_TYPE => self.rook_table.lookup(sq, self.occupied),
+                        _ => {
+                            self.bishop_table.lookup(sq, self.occupied)
+                                | self.rook_table.lookup(sq, self.occupied)
+                        }
+                    };
+                    info.by_piece[side][piece as usize - 1] |= attacks;
+                    info.all[side] |= attacks;
+
+                    let count = (attacks & mobility_area).count_ones() as usize;
+                    info.mobility[side] += match piece {
+                        KNIGHT_PIECE_TYPE => weights.mobility_knight[count],
+                        BISHOP_PIECE_TYPE => weights.mobility_bishop[count],
+                        ROOK_PIECE_TYPE => weights.mobility_rook[count],
+                        _ => weights.mobility_queen[count],
+                    };
+                    trace.record(
+                        |t| match piece {
+                            KNIGHT_PIECE_TYPE => &mut t.mobility_knight[count],
+                            BISHOP_PIECE_TYPE => &mut t.mobility_bishop[count],
+                            ROOK_PIECE_TYPE => &mut t.mobility_rook[count],
+                            _ => &mut t.mobility_queen[count],
+                        },
+                        side,
+                        1,
+                    );
+
+                    let zone_attacks = attacks & enemy_zone;
+                    if zone_attacks != 0 {
+                        info.king_attackers[side] += 1;
+                        info.king_attack_units[side] += weights.king_attack_weight
+                            [piece as usize - 1]
+                            * zone_attacks.count_ones() as i32;
+                    }
+                }
+            }
+        }
+        info
+    }
+
+    /// Mobility from white's point of view.
+    pub fn mobility(&self, info: &AttackInfo) -> Score {
+        info.mobility[WHITE] - info.mobility[BLACK]
+    }
+}
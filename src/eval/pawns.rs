@@ -0,0 +1,236 @@
+use super::{NoTrace, Score, Tracer};
+use crate::utils::lsb1;
+use crate::{Board, Side, BLACK, FILES, RANKS, WHITE};
+
+/// Cached pawn structure evaluation. Everything here depends on pawns only;
+/// passed pawn terms that look at other pieces are added on top in `passed_pawns`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PawnEntry {
+    pub key: u64,
+    /// From white's point of view.
+    pub score: Score,
+    pub passed: [u64; 2],
+}
+
+/// Pawn hash table indexed by `Board::pawn_hash`.
+#[derive(Clone, Debug)]
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+    mask: usize,
+}
+
+impl PawnTable {
+    /// Creates a table with at least `entries` slots, rounded up to a power of two.
+    pub fn new(entries: usize) -> Self {
+        let len = entries.max(1).next_power_of_two();
+        Self {
+            entries: vec![PawnEntry::default(); len],
+            mask: len - 1,
+        }
+    }
+    /// Returns the cached entry for `board`, evaluating and storing it on a miss.
+    pub fn probe(&mut self, board: &Board) -> PawnEntry {
+        let key = board.pawn_hash();
+        let slot = &mut self.entries[key as usize & self.mask];
+        // An empty entry has key 0, which is also the key of a position without pawns,
+        // so those are always recomputed. That is cheap without pawns.
+        if slot.key != key || key == 0 {
+            *slot = board.pawn_structure();
+        }
+        *slot
+    }
+    pub fn clear(&mut self) {
+        self.entries.fill(PawnEntry::default());
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new(1 << 14)
+    }
+}
+
+pub fn relative_rank(side: Side, square: usize) -> usize {
+    match side {
+        WHITE => square / 8,
+        BLACK => 7 - square / 8,
+    }
+}
+
+/// All ranks strictly in front of `rank` from `side`'s point of view.
+pub fn forward_ranks(side: Side, rank: usize) -> u64 {
+    let ranks = match side {
+        WHITE => &RANKS[rank + 1..],
+        BLACK => &RANKS[..rank],
+    };
+    ranks.iter().fold(0, |acc, r| acc | r)
+}
+
+pub fn adjacent_files(file: usize) -> u64 {
+    let left = if file > 0 { FILES[file - 1] } else { 0 };
+    let right = if file < 7 { FILES[file + 1] } else { 0 };
+    left | right
+}
+
+/// Squares that must be free of enemy pawns for a pawn on `square` to be passed.
+pub fn passed_mask(side: Side, square: usize) -> u64 {
+    let file = square % 8;
+    forward_ranks(side, square / 8) & (FILES[file] | adjacent_files(file))
+}
+
+pub fn distance(a: usize, b: usize) -> i32 {
+    let file = (a % 8).abs_diff(b % 8);
+    let rank = (a / 8).abs_diff(b / 8);
+    file.max(rank) as i32
+}
+
+fn pawn_islands(pawns: u64) -> i32 {
+    let mut islands = 0;
+    let mut in_island = false;
+    for file in FILES {
+        let occupied = pawns & file != 0;
+        if occupied && !in_island {
+            islands += 1;
+        }
+        in_island = occupied;
+    }
+    islands
+}
+
+impl Board {
+    /// Evaluates the pawn-only terms for both sides from scratch.
+    pub fn pawn_structure(&self) -> PawnEntry {
+        self.pawn_structure_traced(&mut NoTrace)
+    }
+
+    pub(crate) fn pawn_structure_traced(&self, trace: &mut impl Tracer) -> PawnEntry {
+        let mut entry = PawnEntry {
+            key: self.pawn_hash,
+            ..Default::default()
+        };
+        for side in [WHITE, BLACK] {
+            let (score, passed) = self.pawn_structure_side(side, trace);
+            entry.passed[side] = passed;
+            match side {
+                WHITE => entry.score += score,
+                BLACK => entry.score -= score,
+            }
+        }
+        entry
+    }
+
+    fn pawn_structure_side(&self, side: Side, trace: &mut impl Tracer) -> (Score, u64) {
+        let weights = &self.weights;
+        let own = self.pawns[side];
+        let enemy = self.pawns[!side];
+        let mut score = Score::ZERO;
+        let mut passed = 0u64;
+
+        let mut pawns = own;
+        while pawns != 0 {
+            let sq = lsb1(pawns);
+            pawns &= !(1 << sq);
+            let file = sq % 8;
+            let rank = sq / 8;
+            let r = relative_rank(side, sq);
+            let forward = forward_ranks(side, rank);
+            let adjacent = adjacent_files(file);
+
+            let doubled = own & FILES[file] & forward != 0;
+            let isolated = own & adjacent == 0;
+            // A friendly pawn attacking this square from behind.
+            let supported = self.pawn_attack_table[!side][sq] & own != 0;
+            let phalanx = own & adjacent & RANKS[rank] != 0;
+            let is_passed = enemy & passed_mask(side, sq) == 0 && !doubled;
+
+            if doubled {
+                score += weights.doubled;
+                trace.record(|t| &mut t.doubled, side, 1);
+            }
+            if isolated {
+                score += weights.isolated;
+                trace.record(|t| &mut t.isolated, side, 1);
+            } else {
+                // No friendly pawn on an adjacent file can come up to support it,
+                // and advancing walks into an enemy pawn's attack.
+                let stop = if side == WHITE { sq + 8 } else { sq - 8 };
+                let can_be_supported = own & adjacent & !forward != 0;
+                let stop_attacked = self.pawn_attack_table[side][stop] & enemy != 0;
+                if !can_be_supported && stop_attacked && !is_passed {
+                    score += weights.backward;
+                    trace.record(|t| &mut t.backward, side, 1);
+                }
+            }
+            if supported || phalanx {
+                score += weights.connected[r];
+                trace.record(|t| &mut t.connected[r], side, 1);
+            }
+            if is_passed {
+                passed |= 1 << sq;
+                score += weights.passed[r];
+                trace.record(|t| &mut t.passed[r], side, 1);
+            } else if enemy & FILES[file] & forward == 0 {
+                // Half-open file: it can become passed if the pawns that could help
+                // outnumber the enemy pawns guarding its path.
+                let sentries = (enemy & adjacent & forward).count_ones();
+                let helpers = (own & adjacent & !forward).count_ones();
+                if helpers >= sentries {
+                    score += weights.candidate[r];
+                    trace.record(|t| &mut t.candidate[r], side, 1);
+                }
+            }
+        }
+
+        let islands = pawn_islands(own);
+        if islands > 1 {
+            score += weights.pawn_island * (islands - 1);
+            trace.record(|t| &mut t.pawn_island, side, islands - 1);
+        }
+        (score, passed)
+    }
+
+    /// Passed pawn terms that depend on pieces other than pawns, from white's point of view.
+    pub fn passed_pawns(&self, entry: &PawnEntry) -> Score {
+        self.passed_pawns_traced(entry, &mut NoTrace)
+    }
+
+    pub(crate) fn passed_pawns_traced(&self, entry: &PawnEntry, trace: &mut impl Tracer) -> Score {
+        let weights = &self.weights;
+        let mut total = Score::ZERO;
+        for side in [WHITE, BLACK] {
+            let own_king = lsb1(self.kings[side]);
+            let enemy_king = lsb1(self.kings[!side]);
+            let mut score = Score::ZERO;
+            let mut passed = entry.passed[side];
+            while passed != 0 {
+                let sq = lsb1(passed);
+                passed &= !(1 << sq);
+                let r = relative_rank(side, sq);
+                let stop = if side == WHITE { sq + 8 } else { sq - 8 };
+
+                let path = FILES[sq % 8] & forward_ranks(side, sq / 8);
+                if path & self.occupied == 0 {
+                    score += weights.passed_free_path[r];
+                    trace.record(|t| &mut t.passed_free_path[r], side, 1);
+                }
+                // King proximity only matters once the pawn has made some progress.
+                let factor = if self.kings[side] != 0 && self.kings[!side] != 0 {
+                    r.saturating_sub(2) as i32
+                } else {
+                    0
+                };
+                let own_distance = distance(own_king, stop) * factor;
+                let enemy_distance = distance(enemy_king, stop) * factor;
+                score += weights.passed_own_king_distance * own_distance;
+                score += weights.passed_enemy_king_distance * enemy_distance;
+                trace.record(|t| &mut t.passed_own_king_distance, side, own_distance);
+                trace.record(|t| &mut t.passed_enemy_king_distance, side, enemy_distance);
+            }
+            match side {
+                WHITE => total += score,
+                BLACK => total -= score,
+            }
+        }
+        total
+    }
+}
@@ -0,0 +1,401 @@
+use std::fmt;
+
+use crate::movegen::*;
+use crate::variant::CHECKS_TO_WIN;
+use crate::{Board, CastlingRights, Side, Variant, BLACK, WHITE};
+
+pub const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// FEN of Chess960 start position `index` in Scharnagl's numbering, where
+/// 518 is the standard position, or `None` if `index` is not below 960.
+pub fn chess960_fen(index: u16) -> Option<String> {
+    if index >= 960 {
+        return None;
+    }
+    let mut rank = [None; 8];
+    let mut n = index as usize;
+    // Light-squared bishop on b, d, f or h, then the dark-squared one on a, c, e or g.
+    rank[n % 4 * 2 + 1] = Some('b');
+    n /= 4;
+    rank[n % 4 * 2] = Some('b');
+    n /= 4;
+    let mut place = |nth: usize, piece: char| {
+        let file = (0..8).filter(|&file| rank[file].is_none()).nth(nth).expect("enough empty files");
+        rank[file] = Some(piece);
+    };
+    place(n % 6, 'q');
+    n /= 6;
+    // The knights take two of the five files left.
+    let (first, second) = [(0, 0), (0, 1), (0, 2), (0, 3), (1, 1), (1, 2), (1, 3), (2, 2), (2, 3), (3, 3)][n];
+    place(first, 'n');
+    place(second, 'n');
+    // Rook, king, rook on whatever is left.
+    for piece in ['r', 'k', 'r'] {
+        place(0, piece);
+    }
+    let black: String = rank.iter().map(|piece| piece.expect("every file is filled")).collect();
+    Some(format!("{black}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1", black.to_ascii_uppercase()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    MissingField(&'static str),
+    InvalidPiece(char),
+    InvalidRank(usize),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidClock(String),
+    InvalidKings(Side),
+    PawnOnBackRank(usize),
+    InvalidChecks(String),
+    InvalidPocket(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {field} field"),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece '{c}'"),
+            FenError::InvalidRank(rank) => write!(f, "rank {} does not have 8 squares", rank + 1),
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move '{s}'"),
+            FenError::InvalidCastling(s) => write!(f, "invalid castling rights '{s}'"),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en passant square '{s}'"),
+            FenError::InvalidClock(s) => write!(f, "invalid move clock '{s}'"),
+            FenError::InvalidKings(side) => write!(f, "{side:?} must have exactly one king"),
+            FenError::PawnOnBackRank(square) => write!(f, "pawn on back rank square {}", square_name(*square)),
+            FenError::InvalidChecks(s) => write!(f, "invalid check count '{s}'"),
+            FenError::InvalidPocket(s) => write!(f, "invalid pocket '{s}'"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+pub fn piece_from_char(c: char) -> Option<(Side, u8)> {
+    let side = if c.is_ascii_uppercase() { WHITE } else { BLACK };
+    let piece = match c.to_ascii_lowercase() {
+        'p' => PAWN_PIECE_TYPE,
+        'n' => KNIGHT_PIECE_TYPE,
+        'b' => BISHOP_PIECE_TYPE,
+        'r' => ROOK_PIECE_TYPE,
+        'q' => QUEEN_PIECE_TYPE,
+        'k' => KING_PIECE_TYPE,
+        _ => return None,
+    };
+    Some((side, piece))
+}
+
+pub fn piece_to_char(side: Side, piece: u8) -> char {
+    let c = b" pnbrqk"[piece as usize] as char;
+    match side {
+        WHITE => c.to_ascii_uppercase(),
+        BLACK => c,
+    }
+}
+
+/// Parses a square name like `e4` into a square index (a1 = 0).
+pub fn parse_square(s: &str) -> Option<usize> {
+    let &[file, rank] = s.as_bytes() else {
+        return None;
+    };
+    if !(b'a'..=b'h').contains(&file) || !(b'1'..=b'8').contains(&rank) {
+        return None;
+    }
+    Some(((rank - b'1') * 8 + (file - b'a')) as usize)
+}
+
+/// The piece placement field of a FEN, with `~` after the pieces on `promoted`.
+pub(crate) fn placement(squares: &[Option<(Side, u8)>; 64], promoted: u64) -> String {
+    let mut fen = String::new();
+    for rank in (0..8).rev() {
+        let mut empty = 0;
+        for (square, piece) in squares.iter().enumerate().skip(rank * 8).take(8) {
+            let &Some((side, piece)) = piece else {
+                empty += 1;
+                continue;
+            };
+            if empty != 0 {
+                fen.push_str(&empty.to_string());
+                empty = 0;
+            }
+            fen.push(piece_to_char(side, piece));
+            if promoted & (1 << square) != 0 {
+                fen.push('~');
+            }
+        }
+        if empty != 0 {
+            fen.push_str(&empty.to_string());
+        }
+        if rank != 0 {
+            fen.push('/');
+        }
+    }
+    fen
+}
+
+/// A Crazyhouse pocket like `[QNnp]`: white first, queens to pawns.
+pub(crate) fn pocket(pockets: &[[u8; 5]; 2]) -> String {
+    let mut fen = String::from("[");
+    for side in [WHITE, BLACK] {
+        for piece in (PAWN_PIECE_TYPE..=QUEEN_PIECE_TYPE).rev() {
+            for _ in 0..pockets[side][piece as usize - 1] {
+                fen.push(piece_to_char(side, piece));
+            }
+        }
+    }
+    fen.push(']');
+    fen
+}
+
+pub fn square_name(square: usize) -> String {
+    let file = (b'a' + (square % 8) as u8) as char;
+    let rank = (b'1' + (square / 8) as u8) as char;
+    format!("{file}{rank}")
+}
+
+impl Board {
+    /// Chess960 start position `index`; see `chess960_fen`.
+    pub fn chess960(index: u16) -> Option<Self> {
+        Some(Self::from_fen(&chess960_fen(index)?).expect("Chess960 start positions are valid"))
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        Self::from_variant_fen(fen, Variant::Standard)
+    }
+
+    /// Parses `fen` as a position of `variant`, which may allow positions that
+    /// standard chess does not, such as Antichess ones without kings.
+    pub fn from_variant_fen(fen: &str, variant: Variant) -> Result<Self, FenError> {
+        let mut board = Board::empty();
+        board.variant = variant;
+        board.set_fen(fen)?;
+        Ok(board)
+    }
+
+    /// Sets up the position from `fen`, keeping the attack tables, weights, network and
+    /// variant, which makes it much cheaper than `from_fen`. On error the board is unchanged.
+    ///
+    /// Three-check counts are read from a field like `3+2`, the checks each side has
+    /// left, after the en passant square, or from a trailing `+1+0`, the checks given.
+    /// Crazyhouse pockets follow the placement in brackets, as in `RNBQKBNR[Qpp]`, or
+    /// as a ninth rank, and a `~` marks a piece as promoted.
+    pub fn set_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        let mut fields = fen.split_whitespace();
+
+        let placement = fields.next().ok_or(FenError::MissingField("placement"))?;
+        let (placement, pocket) = match placement.split_once('[') {
+            Some((placement, pocket)) => {
+                let invalid = || FenError::InvalidPocket(pocket.to_string());
+                (placement, Some(pocket.strip_suffix(']').ok_or_else(invalid)?))
+            }
+            None => (placement, None),
+        };
+        let mut ranks = placement.split('/').collect::<Vec<_>>();
+        let pocket = match pocket {
+            None if ranks.len() == 9 => ranks.pop(),
+            pocket => pocket,
+        };
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidRank(ranks.len().min(7)));
+        }
+        let mut pockets = [[0u8; 5]; 2];
+        for c in pocket.unwrap_or_default().chars().filter(|&c| c != '-') {
+            match piece_from_char(c) {
+                Some((side, piece)) if piece != KING_PIECE_TYPE => pockets[side][piece as usize - 1] += 1,
+                _ => return Err(FenError::InvalidPocket(pocket.unwrap_or_default().to_string())),
+            }
+        }
+        let mut squares = [None; 64];
+        let mut promoted = 0u64;
+        let mut kings = [0; 2];
+        for (i, rank_str) in ranks.into_iter().enumerate() {
+            let rank = 7 - i;
+            let mut file = 0;
+            for c in rank_str.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    file += skip as usize;
+                    continue;
+                }
+                if c == '~' && file > 0 && squares[rank * 8 + file - 1].is_some() {
+                    promoted |= 1 << (rank * 8 + file - 1);
+                    continue;
+                }
+                let (side, piece) = piece_from_char(c).ok_or(FenError::InvalidPiece(c))?;
+                if file >= 8 {
+                    return Err(FenError::InvalidRank(rank));
+                }
+                if piece == KING_PIECE_TYPE {
+                    kings[side] += 1;
+                }
+                // Horde pawns start on the first rank; no pawn may stand on the last.
+                let horde_pawn = self.variant == Variant::Horde && side == WHITE && rank == 0;
+                if piece == PAWN_PIECE_TYPE && (rank == 0 || rank == 7) && !horde_pawn {
+                    return Err(FenError::PawnOnBackRank(rank * 8 + file));
+                }
+                squares[rank * 8 + file] = Some((side, piece));
+                file += 1;
+            }
+            if file != 8 {
+                return Err(FenError::InvalidRank(rank));
+            }
+        }
+        for side in [WHITE, BLACK] {
+            if !self.variant.allows_kings(side, kings[side]) {
+                return Err(FenError::InvalidKings(side));
+            }
+        }
+
+        let side_to_move = match fields.next().ok_or(FenError::MissingField("side to move"))? {
+            "w" => WHITE,
+            "b" => BLACK,
+            other => return Err(FenError::InvalidSideToMove(other.to_string())),
+        };
+
+        // Besides `KQkq` this takes Shredder-FEN, which names the rook files as in
+        // `HAha`, and X-FEN, which only does so when `K` or `Q` would be ambiguous.
+        let mut castling = CastlingRights::default();
+        let castling_str = fields.next().ok_or(FenError::MissingField("castling"))?;
+        if castling_str != "-" {
+            for c in castling_str.chars() {
+                let side = if c.is_ascii_uppercase() { WHITE } else { BLACK };
+                let rank = if side == WHITE { 0 } else { 56 };
+                let is_rook = |file: &u8| squares[rank + *file as usize] == Some((side, ROOK_PIECE_TYPE));
+                let invalid = || FenError::InvalidCastling(castling_str.to_string());
+                let king = (0..8u8).find(|&file| squares[rank + file as usize] == Some((side, KING_PIECE_TYPE)));
+                let (kingside, file) = match c.to_ascii_lowercase() {
+                    // The outermost rook on that wing.
+                    'k' => (true, (king.unwrap_or(4) + 1..8).rev().find(is_rook).unwrap_or(7)),
+                    'q' => (false, (0..king.unwrap_or(4)).find(is_rook).unwrap_or(0)),
+                    letter @ 'a'..='h' => {
+                        let file = letter as u8 - b'a';
+                        (file > king.ok_or_else(invalid)?, file)
+                    }
+                    _ => return Err(invalid()),
+                };
+                castling.set(side, kingside, true);
+                castling.rook_files[side][!kingside as usize] = file;
+            }
+        }
+
+        let ep_str = fields.next().ok_or(FenError::MissingField("en passant"))?;
+        let mut ep = None;
+        if ep_str != "-" {
+            let invalid = || FenError::InvalidEnPassant(ep_str.to_string());
+            let square = parse_square(ep_str).ok_or_else(invalid)?;
+            let expected_rank = if side_to_move == WHITE { 5 } else { 2 };
+            if square / 8 != expected_rank {
+                return Err(invalid());
+            }
+            ep = Some((square % 8) as u8);
+        }
+
+        let (check_fields, fields): (Vec<_>, Vec<_>) = fields.partition(|field| field.contains('+'));
+        let mut checks = [0; 2];
+        for field in check_fields {
+            let invalid = || FenError::InvalidChecks(field.to_string());
+            let (given, counts) = match field.strip_prefix('+') {
+                Some(counts) => (true, counts),
+                None => (false, field),
+            };
+            let (white, black) = counts.split_once('+').ok_or_else(invalid)?;
+            for (side, count) in [(WHITE, white), (BLACK, black)] {
+                let count: u8 = count.parse().map_err(|_| invalid())?;
+                if count > CHECKS_TO_WIN {
+                    return Err(invalid());
+                }
+                checks[side] = if given { count } else { CHECKS_TO_WIN - count };
+            }
+        }
+        let mut fields = fields.into_iter();
+
+        // The move clocks are commonly left out, so they are optional.
+        let mut halfmove_clock = 0;
+        if let Some(halfmove) = fields.next() {
+            halfmove_clock = halfmove
+                .parse()
+                .map_err(|_| FenError::InvalidClock(halfmove.to_string()))?;
+        }
+        let mut fullmove_number = 1;
+        if let Some(fullmove) = fields.next() {
+            fullmove_number = fullmove
+                .parse()
+                .map_err(|_| FenError::InvalidClock(fullmove.to_string()))?;
+        }
+
+        self.clear();
+        for (square, piece) in squares.into_iter().enumerate() {
+            if let Some((side, piece)) = piece {
+                self.put_piece(side, piece, square);
+            }
+        }
+        self.side_to_move = side_to_move;
+        self.castling = castling;
+        if self.variant == Variant::Antichess {
+            self.castling = CastlingRights { rook_files: castling.rook_files, ..CastlingRights::default() };
+        }
+        self.ep = ep;
+        self.checks = checks;
+        self.pockets = pockets;
+        self.promoted = promoted;
+        self.halfmove_clock = halfmove_clock;
+        self.fullmove_number = fullmove_number;
+        (self.hash, self.pawn_hash) = self.compute_hashes();
+        if let Some(mut nnue) = self.nnue.take() {
+            nnue.reset(self);
+            self.nnue = Some(nnue);
+        }
+        Ok(())
+    }
+
+    pub fn to_fen(&self) -> String {
+        let squares = std::array::from_fn(|square| Some((self.side_at(square)?, self.arr[square])));
+        let mut fen = placement(&squares, self.promoted);
+        if self.variant == Variant::Crazyhouse {
+            fen.push_str(&pocket(&self.pockets));
+        }
+
+        fen.push_str(if self.side_to_move == WHITE { " w " } else { " b " });
+
+        // X-FEN: the rook's file is only named if another rook stands further out.
+        let len = fen.len();
+        for side in [WHITE, BLACK] {
+            for kingside in [true, false] {
+                if !self.castling.has(side, kingside) {
+                    continue;
+                }
+                let rook = self.castling.rook_square(side, kingside);
+                let rank = rook / 8 * 8;
+                let outside = if kingside { rook + 1..rank + 8 } else { rank..rook };
+                let c = if outside.into_iter().any(|square| self.rooks[side] & (1 << square) != 0) {
+                    (b'a' + (rook % 8) as u8) as char
+                } else if kingside {
+                    'k'
+                } else {
+                    'q'
+                };
+                fen.push(if side == WHITE { c.to_ascii_uppercase() } else { c });
+            }
+        }
+        if fen.len() == len {
+            fen.push('-');
+        }
+
+        match self.ep {
+            Some(file) => {
+                let rank = if self.side_to_move == WHITE { 5 } else { 2 };
+                fen.push(' ');
+                fen.push_str(&square_name(rank * 8 + file as usize));
+            }
+            None => fen.push_str(" -"),
+        }
+
+        if self.variant == Variant::ThreeCheck {
+            let left = |side: Side| CHECKS_TO_WIN.saturating_sub(self.checks[side]);
+            fen.push_str(&format!(" {}+{}", left(WHITE), left(BLACK)));
+        }
+
+        fen.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
+        fen
+    }
+}
@@ -5,6 +5,9 @@
 mod utils;
 
 mod movegen {
+    use std::fmt;
+
+    use crate::fen::square_name;
     use crate::utils::lsb1;
 
     use super::*;
@@ -50,8 +53,13 @@ mod movegen {
     //    26: black queenside
     //    27: black kingside
     // bit 28: current move is en passant?
+    // bit 29: current move is castling? The king's move is then encoded as
+    //     taking its own rook, so the target is the rook's origin.
+    // bit 30: promotion to a king, which only Antichess allows
+    // bit 31: current move drops the moving piece from the pocket (Crazyhouse)?
+    //     "from" is then the target square as well.
     #[repr(transparent)]
-    #[derive(Debug, Clone, Default)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
     pub struct Move(u32);
 
     impl Move {
@@ -85,11 +93,52 @@ mod movegen {
                 white_kingside: ((self.0 >> 25) & 0b1) != 0,
                 black_queenside: ((self.0 >> 26) & 0b1) != 0,
                 black_kingside: ((self.0 >> 27) & 0b1) != 0,
+                ..CastlingRights::default()
             }
         }
         pub fn is_ep(&self) -> bool {
             ((self.0 >> 28) & 0b1) != 0
         }
+        pub fn is_promotion(&self) -> bool {
+            self.moving_piece() == PAWN_PIECE_TYPE && matches!(self.to_square() / 8, 0 | 7)
+        }
+        /// Piece type a promoting pawn turns into.
+        pub fn promoted_to(&self) -> u8 {
+            if (self.0 >> 30) & 0b1 != 0 {
+                return KING_PIECE_TYPE;
+            }
+            self.promotion_piece() + KNIGHT_PIECE_TYPE
+        }
+        pub fn is_castling(&self) -> bool {
+            ((self.0 >> 29) & 0b1) != 0
+        }
+        /// Square the moving piece lands on: the target, except for castling,
+        /// where the king ends on the g- or c-file.
+        pub fn destination(&self) -> usize {
+            let to = self.to_square();
+            if !self.is_castling() {
+                return to;
+            }
+            let rank = to / 8 * 8;
+            if to > self.from_square() { rank + 6 } else { rank + 2 }
+        }
+        pub fn is_drop(&self) -> bool {
+            ((self.0 >> 31) & 0b1) != 0
+        }
+        /// UCI notation. Castling is written as the king moving two squares,
+        /// or with `chess960` as the king taking its own rook. Drops look like `N@f3`.
+        pub fn to_uci(&self, chess960: bool) -> String {
+            if self.is_drop() {
+                let piece = b" PNBRQK"[self.moving_piece() as usize] as char;
+                return format!("{piece}@{}", square_name(self.to_square()));
+            }
+            let to = if chess960 { self.to_square() } else { self.destination() };
+            let mut uci = format!("{}{}", square_name(self.from_square()), square_name(to));
+            if self.is_promotion() {
+                uci.push(b"  nbrqk"[self.promoted_to() as usize] as char);
+            }
+            uci
+        }
         pub fn set_from_square(&mut self, square: usize) {
             self.0 |= (square as u32) & 0b111111;
         }
@@ -128,86 +177,645 @@ mod movegen {
         pub fn set_is_ep(&mut self) {
             self.0 |= 1 << 28;
         }
+        pub fn set_is_castling(&mut self) {
+            self.0 |= 1 << 29;
+        }
+        pub fn set_promotes_to_king(&mut self) {
+            self.0 |= 1 << 30;
+        }
+        pub fn set_is_drop(&mut self) {
+            self.0 |= 1 << 31;
+        }
+        /// The encoded move, e.g. for storing in the transposition table.
+        pub fn raw(&self) -> u32 {
+            self.0
+        }
+        pub fn from_raw(raw: u32) -> Self {
+            Self(raw)
+        }
+        pub fn is_null(&self) -> bool {
+            self.0 == 0
+        }
+        pub fn is_capture(&self) -> bool {
+            self.captured_piece() != NONE_PIECE_TYPE
+        }
     }
 
-    pub type Moves = tinyvec::ArrayVec<[Move; 218]>;
+    /// Room for any move list. Standard positions have at most 218 legal moves,
+    /// but Crazyhouse drops from a full pocket can add several hundred more.
+    pub const MAX_MOVES: usize = 512;
+    pub type Moves = tinyvec::ArrayVec<[Move; MAX_MOVES]>;
+
+    /// Which legal moves a generator call produces.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum GenType {
+        All,
+        /// Captures, including en passant, and every promotion.
+        Captures,
+        /// Everything that `Captures` leaves out, castling included.
+        Quiets,
+        /// The quiet moves that give check.
+        QuietChecks,
+        /// Every move that gives check.
+        Checks,
+        /// Every legal move if the side to move is in check, else nothing.
+        Evasions,
+    }
+
+    impl GenType {
+        pub(crate) fn captures(self) -> bool {
+            matches!(self, GenType::All | GenType::Captures | GenType::Evasions | GenType::Checks)
+        }
+        pub(crate) fn quiets(self) -> bool {
+            !matches!(self, GenType::Captures)
+        }
+        /// Whether only moves that give check are wanted. The generator skips
+        /// most others; `generate` drops the rest.
+        pub(crate) fn checks(self) -> bool {
+            matches!(self, GenType::QuietChecks | GenType::Checks)
+        }
+    }
+
+    impl fmt::Display for Move {
+        /// UCI notation, e.g. `e2e4`, `e7e8q` or `e1g1`; see `to_uci`.
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.to_uci(false))
+        }
+    }
 
     impl Board {
+        /// Appends every legal move for `side`, which must be the side to move.
         pub fn generate_moves(&self, moves: &mut Moves, side: Side) {
-            todo!();
-        }
-        pub fn king_moves(&self, moves: &mut Moves, side: Side) {
-            let king = self.kings[side];
-            let king = lsb1(king);
-            let move_bb = self.king_attack_table[king];
-            let opponent_attacks = self.attacks(!side);
-            // King can move to squares where
-            // 1. It can reach it
-            // 2. It is not under attack by opponent pieces
-            // 3. It is not occupied by friendly pieces
-            let mut allowed_bb = move_bb & !opponent_attacks & self.pieces[side ];
-            while allowed_bb != 0 {
-                let lsb = lsb1(allowed_bb);
-                allowed_bb &= !(1 << lsb);
-                let mut mv = Move::new();
-                mv.set_from_square(king);
-                mv.set_to_square(lsb);
-                mv.set_moving_piece(KING_PIECE_TYPE);
-                if (self.pieces[!side] & (1 << lsb)) != 0 {
-                    mv.set_captured_piece(self.arr[lsb]);
+            self.generate(moves, side, GenType::All);
+        }
+        /// Appends the legal moves of kind `kind` for `side`, which must be the side to move.
+        pub fn generate(&self, moves: &mut Moves, side: Side, kind: GenType) {
+            if self.is_variant_over() {
+                return;
+            }
+            match kind {
+                GenType::QuietChecks | GenType::Checks => {
+                    let mut candidates = Moves::new();
+                    if self.is_custom_legality() {
+                        let all = if kind == GenType::Checks { GenType::All } else { GenType::Quiets };
+                        self.generate_variant(&mut candidates, side, all);
+                    } else {
+                        self.generate_with(&mut candidates, side, kind, true);
+                    }
+                    moves.extend(candidates.into_iter().filter(|mv| self.gives_check(mv)));
+                    return;
                 }
-                mv.set_prev_ep(self.ep);
-                mv.set_castling_rights(self.castling);
-                moves.push(mv);
+                GenType::Evasions if !self.is_check() => return,
+                _ => {}
+            }
+            if self.is_custom_legality() {
+                self.generate_variant(moves, side, kind);
+            } else {
+                self.generate_with(moves, side, kind, true);
+            }
+        }
+        /// Appends every move for `side` that follows the piece movement rules,
+        /// including ones that leave the king in check. Pair with `is_legal`.
+        /// Castling moves are always fully checked. Variants whose legality
+        /// differs from standard chess get their legal moves.
+        pub fn generate_pseudo_legal(&self, moves: &mut Moves, side: Side) {
+            if self.is_custom_legality() {
+                self.generate(moves, side, GenType::All);
+            } else {
+                self.generate_with(moves, side, GenType::All, false);
             }
         }
-        pub fn other_moves(&self, moves: &mut Moves, side: Side) {
+        pub(crate) fn generate_with(&self, moves: &mut Moves, side: Side, kind: GenType, legal: bool) {
+            self.king_moves(moves, side, kind, legal);
+            let checkers = self.checkers(side);
+            // In double check only the king can move.
+            if legal && checkers.count_ones() > 1 {
+                return;
+            }
+            if checkers == 0 && kind.quiets() {
+                self.castling_moves(moves, side);
+            }
+            self.other_moves(moves, side, kind, legal);
+        }
+        /// Antichess promotes pawns to kings, so `side` may have several.
+        pub(crate) fn king_moves(&self, moves: &mut Moves, side: Side, kind: GenType, legal: bool) {
+            // A king only gives check by uncovering one.
+            if kind.checks() && self.check_squares(side).1 & self.kings[side] == 0 {
+                return;
+            }
+            let mut kings = self.kings[side];
+            while kings != 0 {
+                let king = lsb1(kings);
+                kings &= kings - 1;
+                let move_bb = self.king_attack_table[king];
+                // Sliders attack through the square the king is leaving.
+                let opponent_attacks = if legal {
+                    self.attacks_with(!side, self.occupied & !(1 << king))
+                } else {
+                    0
+                };
+                // King can move to squares where
+                // 1. It can reach it
+                // 2. It is not under attack by opponent pieces
+                // 3. It is not occupied by friendly pieces
+                let mut allowed_bb = move_bb & !opponent_attacks & self.targets(side, kind);
+                while allowed_bb != 0 {
+                    let lsb = lsb1(allowed_bb);
+                    allowed_bb &= !(1 << lsb);
+                    self.push_move(moves, king, lsb, KING_PIECE_TYPE);
+                }
+            }
+        }
+        /// Destination squares allowed for piece moves of kind `kind`.
+        fn targets(&self, side: Side, kind: GenType) -> u64 {
+            match (kind.captures(), kind.quiets()) {
+                (true, true) => !self.pieces[side],
+                (true, false) => self.pieces[!side],
+                (false, true) => self.empty,
+                (false, false) => 0,
+            }
+        }
+        pub(crate) fn castling_moves(&self, moves: &mut Moves, side: Side) {
             let king = lsb1(self.kings[side]);
-            let king_bishop_attacks = self.bishop_table.lookup(king, self.occupied);
-            let king_rook_attacks = self.rook_table.lookup(king, self.occupied);
-            let mut pawns = self.pawns[side];
+            let rank = if side == WHITE { 0 } else { 56 };
+            if self.kings[side] == 0 || king / 8 * 8 != rank {
+                return;
+            }
+            for kingside in [true, false] {
+                let rook = self.castling.rook_square(side, kingside);
+                if !self.castling.has(side, kingside) || self.rooks[side] & (1 << rook) == 0 {
+                    continue;
+                }
+                let (king_to, rook_to) = if kingside { (rank + 6, rank + 5) } else { (rank + 2, rank + 3) };
+                // Apart from the king and rook themselves, every square either of them
+                // crosses or lands on must be empty.
+                let others = self.occupied & !(1 << king) & !(1 << rook);
+                let king_path = self.between(king, king_to) | (1 << king_to);
+                let rook_path = self.between(rook, rook_to) | (1 << rook_to);
+                if (king_path | rook_path) & others != 0 {
+                    continue;
+                }
+                // The king may not pass through or land on an attacked square, including
+                // along lines the rook was blocking.
+                // In Atomic the enemy king attacks nothing, and nothing attacks a
+                // square next to it.
+                let mut path = king_path;
+                let mut safe = true;
+                while path != 0 && safe {
+                    let square = lsb1(path);
+                    let mut attackers = self.attackers_to(square, !side, others);
+                    if self.variant == Variant::Atomic {
+                        attackers &= !self.kings[!side];
+                        if self.king_attack_table[square] & self.kings[!side] != 0 {
+                            attackers = 0;
+                        }
+                    }
+                    safe = attackers == 0;
+                    path &= path - 1;
+                }
+                if safe {
+                    let mut mv = Move::new();
+                    mv.set_from_square(king);
+                    mv.set_to_square(rook);
+                    mv.set_moving_piece(KING_PIECE_TYPE);
+                    mv.set_prev_ep(self.ep);
+                    mv.set_castling_rights(self.castling);
+                    mv.set_is_castling();
+                    moves.push(mv);
+                }
+            }
+        }
+        pub(crate) fn other_moves(&self, moves: &mut Moves, side: Side, kind: GenType, legal: bool) {
+            let checkers = if legal { self.checkers(side) } else { 0 };
+            // Non-king moves must capture the checker or block the check.
+            let check_mask = if checkers == 0 {
+                !0
+            } else {
+                checkers | self.between(lsb1(self.kings[side]), lsb1(checkers))
+            };
+            let pin_rays = if legal { self.pin_rays(side) } else { [!0; 64] };
+            let targets = self.targets(side, kind) & check_mask;
+            let (direct, discoverers) = if kind.checks() { self.check_squares(side) } else { ([!0; 6], 0) };
+            // Squares a piece on `from` may go to if only checks are wanted.
+            let checking = |piece: u8, from: usize| {
+                if discoverers & (1 << from) != 0 { !0 } else { direct[piece as usize - 1] }
+            };
+
             let mut knights = self.knights[side];
-            let mut bishops = self.bishops[side];
-            let mut rooks = self.rooks[side];
-            let mut queens = self.queens[side];
-
-            todo!()
-        }
-        // pub fn diagonal_moves(&self, moves: &mut Moves, side: Side) {
-        //     let king = lsb1(self.kings[side]);
-        //     let king_bishop_attacks = self.bishop_table.lookup(king, self.occupied);
-            
-        // }
-        // pub fn straight_moves(&self, moves: &mut Moves, side: Side) {
-        //     todo!()
-        // }
-        // pub fn pinned(&self, side: Side) -> u64 {
-        //     let king = lsb1(self.kings[side]);
-        //     let king_bishop_attacks = self.bishop_table.lookup(king, self.occupied);
-        //     let king_rook_attacks = self.rook_table.lookup(king, self.occupied);
-            
-        //     let removed_king_bishop_attacks = self.bishop_table.lookup(king, self.occupied & !king_bishop_attacks);
-        //     let removed_king_rook_attacks = self.rook_table.lookup(king, self.occupied & !king_rook_attacks);
-        //     pinned
-        // }
+            while knights != 0 {
+                let from = lsb1(knights);
+                knights &= !(1 << from);
+                let attacks = self.knight_attack_table[from] & targets & pin_rays[from];
+                let attacks = attacks & checking(KNIGHT_PIECE_TYPE, from);
+                self.push_moves(moves, from, attacks, KNIGHT_PIECE_TYPE);
+            }
+            let sliders = [
+                (BISHOP_PIECE_TYPE, self.bishops[side]),
+                (ROOK_PIECE_TYPE, self.rooks[side]),
+                (QUEEN_PIECE_TYPE, self.queens[side]),
+            ];
+            for (piece, mut bb) in sliders {
+                while bb != 0 {
+                    let from = lsb1(bb);
+                    bb &= !(1 << from);
+                    let attacks = match piece {
+                        BISHOP_PIECE_TYPE => self.bishop_table.lookup(from, self.occupied),
+                        ROOK_PIECE_TYPE => self.rook_table.lookup(from, self.occupied),
+                        _ => {
+                            self.bishop_table.lookup(from, self.occupied)
+                                | self.rook_table.lookup(from, self.occupied)
+                        }
+                    };
+                    self.push_moves(moves, from, attacks & targets & pin_rays[from] & checking(piece, from), piece);
+                }
+            }
+
+            let mut pawns = self.pawns[side];
+            let (push, promo_rank) = match side {
+                WHITE => (8i32, RANK8),
+                BLACK => (-8, RANK1),
+            };
+            let start_rank = self.double_push_ranks(side);
+            while pawns != 0 {
+                let from = lsb1(pawns);
+                pawns &= !(1 << from);
+                let allowed = check_mask & pin_rays[from] & (checking(PAWN_PIECE_TYPE, from) | promo_rank);
+                let mut to_bb = self.pawn_attack_table[side][from] & self.pieces[!side];
+                let single = (from as i32 + push) as usize;
+                if self.empty & (1 << single) != 0 {
+                    to_bb |= 1 << single;
+                    let double = (single as i32 + push) as usize;
+                    if start_rank & (1 << from) != 0 && self.empty & (1 << double) != 0 {
+                        to_bb |= 1 << double;
+                    }
+                }
+                to_bb &= allowed;
+                while to_bb != 0 {
+                    let to = lsb1(to_bb);
+                    to_bb &= !(1 << to);
+                    // Promotions count as captures, even without taking anything.
+                    if promo_rank & (1 << to) != 0 {
+                        if kind.captures() {
+                            for promo in [PROMO_QUEEN, PROMO_ROOK, PROMO_BISHOP, PROMO_KNIGHT] {
+                                let mut mv = self.new_move(from, to, PAWN_PIECE_TYPE);
+                                mv.set_promotion_piece(promo);
+                                moves.push(mv);
+                            }
+                        }
+                    } else if self.pieces[!side] & (1 << to) != 0 {
+                        if kind.captures() {
+                            self.push_move(moves, from, to, PAWN_PIECE_TYPE);
+                        }
+                    } else if kind.quiets() {
+                        self.push_move(moves, from, to, PAWN_PIECE_TYPE);
+                    }
+                }
+            }
+            if kind.captures() {
+                self.ep_moves(moves, side, legal);
+            }
+            if kind.quiets() && self.variant == Variant::Crazyhouse {
+                // A drop can block a check but not take the checker.
+                let checking = direct.iter().fold(0, |squares, piece| squares | piece);
+                self.drop_moves(moves, side, self.empty & check_mask & checking);
+            }
+        }
+        fn ep_moves(&self, moves: &mut Moves, side: Side, legal: bool) {
+            let Some(file) = self.ep else {
+                return;
+            };
+            if side != self.side_to_move {
+                return;
+            }
+            let (target, captured) = match side {
+                WHITE => (40 + file as usize, 32 + file as usize),
+                BLACK => (16 + file as usize, 24 + file as usize),
+            };
+            let king = lsb1(self.kings[side]);
+            let mut from_bb = self.pawn_attack_table[!side][target] & self.pawns[side];
+            while from_bb != 0 {
+                let from = lsb1(from_bb);
+                from_bb &= !(1 << from);
+                // Two pawns leave the board at once, which pin masks do not capture,
+                // so play it out on the occupancy and look for attacks on the king.
+                if legal && self.kings[side] != 0 {
+                    let occupied = (self.occupied & !(1 << from) & !(1 << captured)) | (1 << target);
+                    if self.attackers_to(king, !side, occupied) & !(1 << captured) != 0 {
+                        continue;
+                    }
+                }
+                let mut mv = self.new_move(from, target, PAWN_PIECE_TYPE);
+                mv.set_captured_piece(PAWN_PIECE_TYPE);
+                mv.set_is_ep();
+                moves.push(mv);
+            }
+        }
+        fn new_move(&self, from: usize, to: usize, piece: u8) -> Move {
+            let mut mv = Move::new();
+            mv.set_from_square(from);
+            mv.set_to_square(to);
+            mv.set_moving_piece(piece);
+            mv.set_captured_piece(self.arr[to]);
+            mv.set_prev_ep(self.ep);
+            mv.set_castling_rights(self.castling);
+            mv
+        }
+        fn push_move(&self, moves: &mut Moves, from: usize, to: usize, piece: u8) {
+            moves.push(self.new_move(from, to, piece));
+        }
+        fn push_moves(&self, moves: &mut Moves, from: usize, mut to_bb: u64, piece: u8) {
+            while to_bb != 0 {
+                let to = lsb1(to_bb);
+                to_bb &= !(1 << to);
+                self.push_move(moves, from, to, piece);
+            }
+        }
+        /// Squares strictly between `a` and `b` if they share a line, else empty.
+        pub fn between(&self, a: usize, b: usize) -> u64 {
+            let (a_bb, b_bb) = (1u64 << a, 1u64 << b);
+            let rook = self.rook_table.lookup(a, b_bb);
+            if rook & b_bb != 0 {
+                return rook & self.rook_table.lookup(b, a_bb);
+            }
+            let bishop = self.bishop_table.lookup(a, b_bb);
+            if bishop & b_bb != 0 {
+                return bishop & self.bishop_table.lookup(b, a_bb);
+            }
+            0
+        }
+        /// For each square, where the piece on it may move without exposing `side`'s king:
+        /// the line to the pinning piece for pinned pieces, everywhere otherwise.
+        pub fn pin_rays(&self, side: Side) -> [u64; 64] {
+            let mut rays = [!0u64; 64];
+            if self.kings[side] == 0 {
+                return rays;
+            }
+            let king = lsb1(self.kings[side]);
+            let rook_like = self.rooks[!side] | self.queens[!side];
+            let bishop_like = self.bishops[!side] | self.queens[!side];
+            let mut snipers = (self.rook_table.lookup(king, 0) & rook_like)
+                | (self.bishop_table.lookup(king, 0) & bishop_like);
+            while snipers != 0 {
+                let sniper = lsb1(snipers);
+                snipers &= !(1 << sniper);
+                let between = self.between(king, sniper);
+                let blockers = between & self.occupied;
+                if blockers.count_ones() == 1 && blockers & self.pieces[side] != 0 {
+                    rays[lsb1(blockers)] = between | (1 << sniper);
+                }
+            }
+            rays
+        }
+        /// The squares each piece type of `side` would check the enemy king from,
+        /// indexed by `piece - 1`, and the pieces of `side` that may uncover a
+        /// check by moving off the line between a slider and that king.
+        fn check_squares(&self, side: Side) -> ([u64; 6], u64) {
+            if self.kings[!side] == 0 {
+                return ([0; 6], 0);
+            }
+            let king = lsb1(self.kings[!side]);
+            let bishop = self.bishop_table.lookup(king, self.occupied);
+            let rook = self.rook_table.lookup(king, self.occupied);
+            let direct = [
+                self.pawn_attack_table[!side][king],
+                self.knight_attack_table[king],
+                bishop,
+                rook,
+                bishop | rook,
+                0,
+            ];
+
+            let rook_like = self.rooks[side] | self.queens[side];
+            let bishop_like = self.bishops[side] | self.queens[side];
+            let mut snipers = (self.rook_table.lookup(king, 0) & rook_like)
+                | (self.bishop_table.lookup(king, 0) & bishop_like);
+            let mut discoverers = 0;
+            while snipers != 0 {
+                let sniper = lsb1(snipers);
+                snipers &= !(1 << sniper);
+                let blockers = self.between(king, sniper) & self.occupied;
+                if blockers.count_ones() == 1 {
+                    discoverers |= blockers & self.pieces[side];
+                }
+            }
+            (direct, discoverers)
+        }
+        /// Pieces of `side` attacking `square`, with sliders seeing through `occupied`.
+        pub fn attackers_to(&self, square: usize, side: Side, occupied: u64) -> u64 {
+            let rook_like = self.rooks[side] | self.queens[side];
+            let bishop_like = self.bishops[side] | self.queens[side];
+            ((self.pawn_attack_table[!side][square] & self.pawns[side])
+                | (self.knight_attack_table[square] & self.knights[side])
+                | (self.king_attack_table[square] & self.kings[side])
+                | (self.rook_table.lookup(square, occupied) & rook_like)
+                | (self.bishop_table.lookup(square, occupied) & bishop_like))
+                & occupied
+        }
+        pub fn is_check(&self) -> bool {
+            match self.variant {
+                Variant::Antichess => return false,
+                Variant::Atomic => return self.atomic_in_check(),
+                _ => {}
+            }
+            self.checkers(self.side_to_move) != 0
+        }
+        /// Pieces giving check to `side`'s king; none if `side` has no king.
+        fn checkers(&self, side: Side) -> u64 {
+            match self.kings[side] {
+                0 => 0,
+                kings => self.attackers_to(lsb1(kings), !side, self.occupied),
+            }
+        }
+        /// Appends the legal moves for `side` that give check.
+        pub fn generate_checks(&self, moves: &mut Moves, side: Side) {
+            self.generate(moves, side, GenType::Checks);
+        }
+        /// Whether the legal move `mv` checks the opponent, directly or by
+        /// uncovering a slider.
+        pub fn gives_check(&self, mv: &Move) -> bool {
+            if self.is_custom_legality() {
+                let mut after = self.clone();
+                after.make_move(mv);
+                return after.is_check();
+            }
+            let side = self.side_to_move;
+            if self.kings[!side] == 0 {
+                return false;
+            }
+            let king = lsb1(self.kings[!side]);
+            let (from, to) = (mv.from_square(), mv.destination());
+            let mut occupied = (self.occupied & !(1 << from)) | (1 << to);
+            let mut diagonal = (self.bishops[side] | self.queens[side]) & !(1 << from);
+            let mut straight = (self.rooks[side] | self.queens[side]) & !(1 << from);
+            if mv.is_ep() {
+                occupied &= !(1 << Board::capture_square(mv, side));
+            }
+            if mv.is_castling() {
+                let (rook_from, rook_to) = Board::castling_rook_squares(mv);
+                occupied = (occupied & !(1 << rook_from)) | (1 << rook_to) | (1 << to);
+                straight = (straight & !(1 << rook_from)) | (1 << rook_to);
+            }
+            let piece = if mv.is_promotion() { mv.promoted_to() } else { mv.moving_piece() };
+            match piece {
+                PAWN_PIECE_TYPE if self.pawn_attack_table[side][to] & (1 << king) != 0 => return true,
+                KNIGHT_PIECE_TYPE if self.knight_attack_table[to] & (1 << king) != 0 => return true,
+                BISHOP_PIECE_TYPE => diagonal |= 1 << to,
+                ROOK_PIECE_TYPE => straight |= 1 << to,
+                QUEEN_PIECE_TYPE => {
+                    diagonal |= 1 << to;
+                    straight |= 1 << to;
+                }
+                _ => {}
+            }
+            self.bishop_table.lookup(king, occupied) & diagonal != 0
+                || self.rook_table.lookup(king, occupied) & straight != 0
+        }
+        /// Whether the pseudo-legal move `mv` leaves the mover's king safe.
+        pub fn is_legal(&self, mv: &Move) -> bool {
+            // `generate_pseudo_legal` already checked these.
+            if self.is_custom_legality() {
+                return true;
+            }
+            let side = self.side_to_move;
+            let (from, to) = (mv.from_square(), mv.to_square());
+            if mv.moving_piece() == KING_PIECE_TYPE {
+                // Castling was checked when it was generated.
+                return mv.is_castling()
+                    || self.attackers_to(to, !side, self.occupied & !(1 << from)) == 0;
+            }
+            if self.kings[side] == 0 {
+                return true;
+            }
+            let king = lsb1(self.kings[side]);
+            let captured = Board::capture_square(mv, side);
+            let occupied = (self.occupied & !(1 << from) & !(1 << captured)) | (1 << to);
+            self.attackers_to(king, !side, occupied) & !(1 << captured) == 0
+        }
+        /// Whether `mv`, typically from the transposition table or a killer slot,
+        /// is a pseudo-legal move in this position. Moves recorded in another
+        /// position with the same squares are rejected too, since the captured
+        /// piece and saved state they carry would corrupt `unmake_move`.
+        pub fn is_pseudo_legal(&self, mv: &Move) -> bool {
+            let side = self.side_to_move;
+            let (from, to, piece) = (mv.from_square(), mv.to_square(), mv.moving_piece());
+            if mv.is_drop() {
+                let mut drops = Moves::new();
+                self.drop_moves(&mut drops, side, self.empty);
+                return drops.contains(mv);
+            }
+            if mv.is_null() || self.side_at(from) != Some(side) || self.arr[from] != piece {
+                return false;
+            }
+            if self.is_custom_legality() {
+                let mut moves = Moves::new();
+                self.generate(&mut moves, side, GenType::All);
+                return moves.contains(mv);
+            }
+            if mv.is_castling() {
+                let mut castles = Moves::new();
+                if !self.is_check() {
+                    self.castling_moves(&mut castles, side);
+                }
+                return castles.contains(mv);
+            }
+
+            let mut expected = self.new_move(from, to, piece);
+            if mv.is_promotion() {
+                expected.set_promotion_piece(mv.promotion_piece());
+            }
+            if mv.is_ep() {
+                expected.set_captured_piece(PAWN_PIECE_TYPE);
+                expected.set_is_ep();
+            }
+            if expected != *mv {
+                return false;
+            }
+
+            let to_bb = 1u64 << to;
+            let targets = !self.pieces[side];
+            match piece {
+                PAWN_PIECE_TYPE => {
+                    if mv.is_ep() {
+                        let target = match side {
+                            WHITE => 40,
+                            BLACK => 16,
+                        };
+                        return self.ep.is_some_and(|file| to == target + file as usize)
+                            && self.pawn_attack_table[side][from] & to_bb != 0;
+                    }
+                    let push: i32 = match side {
+                        WHITE => 8,
+                        BLACK => -8,
+                    };
+                    let single = (from as i32 + push) as usize;
+                    let start_rank = self.double_push_ranks(side);
+                    if self.pieces[!side] & to_bb != 0 {
+                        self.pawn_attack_table[side][from] & to_bb != 0
+                    } else if to == single {
+                        self.empty & to_bb != 0
+                    } else {
+                        (single as i32 + push) as usize == to
+                            && start_rank & (1 << from) != 0
+                            && self.empty & (1 << single) != 0
+                            && self.empty & to_bb != 0
+                    }
+                }
+                KNIGHT_PIECE_TYPE => self.knight_attack_table[from] & targets & to_bb != 0,
+                BISHOP_PIECE_TYPE => self.bishop_table.lookup(from, self.occupied) & targets & to_bb != 0,
+                ROOK_PIECE_TYPE => self.rook_table.lookup(from, self.occupied) & targets & to_bb != 0,
+                QUEEN_PIECE_TYPE => {
+                    let attacks = self.bishop_table.lookup(from, self.occupied)
+                        | self.rook_table.lookup(from, self.occupied);
+                    attacks & targets & to_bb != 0
+                }
+                KING_PIECE_TYPE => self.king_attack_table[from] & targets & to_bb != 0,
+                _ => false,
+            }
+        }
+        /// Number of leaf nodes `depth` plies from this position.
+        pub fn perft(&mut self, depth: u32) -> u64 {
+            let mut moves = Moves::new();
+            self.generate_moves(&mut moves, self.side_to_move);
+            if depth <= 1 {
+                return if depth == 0 { 1 } else { moves.len() as u64 };
+            }
+            let mut nodes = 0;
+            for mv in moves {
+                self.make_move(&mv);
+                nodes += self.perft(depth - 1);
+                self.unmake_move(&mv);
+            }
+            nodes
+        }
+        /// Finds the legal move written in UCI notation. Castling may be written
+        /// either way `Move::to_uci` does.
+        pub fn parse_uci_move(&self, s: &str) -> Option<Move> {
+            let mut moves = Moves::new();
+            self.generate_moves(&mut moves, self.side_to_move);
+            moves.into_iter().find(|mv| mv.to_string() == s || (mv.is_castling() && mv.to_uci(true) == s))
+        }
         pub fn attacks(&self, side: Side) -> u64 {
-            let pawn_attacks = if side == WHITE{
+            self.attacks_with(side, self.occupied)
+        }
+        /// Squares attacked by `side` with sliders blocked by `occupied`.
+        pub fn attacks_with(&self, side: Side, occupied: u64) -> u64 {
+            let pawn_attacks = if side == WHITE {
                 self.w_pawn_attacks()
             } else {
                 self.b_pawn_attacks()
             };
-            let king = self.kings[side];
             pawn_attacks
-                & self.knight_attacks(side)
-                & self.bishop_attacks(side)
-                & self.rook_attacks(side)
-                & self.queen_attacks(side)
-                & self.king_attack_table[lsb1(king)]
+                | self.knight_attacks(side)
+                | self.bishop_attacks(side, occupied)
+                | self.rook_attacks(side, occupied)
+                | self.queen_attacks(side, occupied)
+                | self.attacks_of_kings(side)
         }
     }
 }
 
 #[derive(Clone, Debug, Hash, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
     WHITE = 0,
     BLACK = 1,
@@ -228,17 +836,17 @@ impl<T> Index<Side> for [T; 2] {
     type Output = T;
 
     fn index(&self, index: Side) -> &Self::Output {
-        &self[index]
+        &self[index as usize]
     }
 }
 impl<T> IndexMut<Side> for [T; 2] {
     fn index_mut(&mut self, index: Side) -> &mut Self::Output {
-        &mut self[index]
+        &mut self[index as usize]
     }
 }
 
-
-struct Board {
+#[derive(Clone)]
+pub struct Board {
     pawns: [u64; 2],
     knights: [u64; 2],
     bishops: [u64; 2],
@@ -256,28 +864,144 @@ struct Board {
     ep: Option<u8>,
     castling: CastlingRights,
     side_to_move: Side,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+
+    variant: Variant,
+    /// Checks given by each side, counted in Three-check only.
+    checks: [u8; 2],
+    /// Crazyhouse pieces in hand by side, indexed by piece type - 1 from pawn to queen.
+    pockets: [[u8; 5]; 2],
+    /// Crazyhouse pieces that were pawns, which go back into a pocket as pawns when captured.
+    promoted: u64,
+
+    history: Vec<History>,
 
     pawn_attack_table: [[u64; 64]; 2],
     knight_attack_table: [u64; 64],
     king_attack_table: [u64; 64],
 
-    rook_table: RookTable,
-    bishop_table: BishopTable,
+    // Shared by every board; see `slider_tables`.
+    rook_table: Arc<RookTable>,
+    bishop_table: Arc<BishopTable>,
+
+    weights: Arc<EvalWeights>,
+    psqt: Psqt,
+
+    hash: u64,
+    pawn_hash: u64,
+
+    nnue: Option<Box<NnueState>>,
+}
+
+/// State that `Move` has no room for, pushed by `make_move` and popped by `unmake_move`.
+#[derive(Clone, Copy, Debug)]
+struct History {
+    halfmove_clock: u16,
+    hash: u64,
+    pawn_hash: u64,
+    checks: [u8; 2],
+    /// Squares of the pieces an Atomic capture blew up besides the two taking
+    /// part, and those pieces in square order as `side << 3 | piece`.
+    blast: u64,
+    exploded: [u8; 8],
+    pockets: [[u8; 5]; 2],
+    promoted: u64,
 }
 
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Clone, Debug, Copy)]
-struct CastlingRights {
-    white_kingside: bool,
-    white_queenside: bool,
-    black_kingside: bool,
-    black_queenside: bool,
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+    /// Files of the castling rooks by side, kingside first. Only Chess960
+    /// positions have them anywhere but on h and a.
+    pub rook_files: [[u8; 2]; 2],
 }
 
+impl Default for CastlingRights {
+    fn default() -> Self {
+        Self {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+            rook_files: [[7, 0]; 2],
+        }
+    }
+}
+
+impl CastlingRights {
+    /// Whether `side` may still castle on the king- or queenside.
+    pub fn has(&self, side: Side, kingside: bool) -> bool {
+        match (side, kingside) {
+            (WHITE, true) => self.white_kingside,
+            (WHITE, false) => self.white_queenside,
+            (BLACK, true) => self.black_kingside,
+            (BLACK, false) => self.black_queenside,
+        }
+    }
+    pub fn set(&mut self, side: Side, kingside: bool, allowed: bool) {
+        match (side, kingside) {
+            (WHITE, true) => self.white_kingside = allowed,
+            (WHITE, false) => self.white_queenside = allowed,
+            (BLACK, true) => self.black_kingside = allowed,
+            (BLACK, false) => self.black_queenside = allowed,
+        }
+    }
+    /// Whether any castling right is left.
+    pub fn any(&self) -> bool {
+        self.white_kingside || self.white_queenside || self.black_kingside || self.black_queenside
+    }
+    /// Where the rook that castles on the given wing starts.
+    pub fn rook_square(&self, side: Side, kingside: bool) -> usize {
+        let rank = if side == WHITE { 0 } else { 56 };
+        rank + self.rook_files[side][!kingside as usize] as usize
+    }
+}
+
+pub mod analysis;
+pub mod book;
+pub mod endgame;
+pub mod engine;
+pub mod eval;
+mod fen;
+mod make_move;
+mod mate;
+pub mod nnue;
+pub mod packed;
+pub mod pgn;
+pub mod puzzle;
+mod san;
+pub mod search;
+pub mod selfplay;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod syzygy;
 mod sliding_attacks;
+mod tt;
+pub mod tournament;
+pub mod tuner;
+pub mod uci;
+pub mod variant;
+pub mod zobrist;
+pub use eval::{EvalWeights, Psqt};
+pub use fen::{chess960_fen, FenError, STARTPOS};
+pub use make_move::Outcome;
+pub use mate::MateResult;
+pub use movegen::*;
+use nnue::NnueState;
 pub use sliding_attacks::{BishopTable, RookTable};
+pub use variant::Variant;
+use std::sync::{Arc, OnceLock};
 use utils::lsb1;
 
+pub const RANKS: [u64; 8] = [RANK1, RANK2, RANK3, RANK4, RANK5, RANK6, RANK7, RANK8];
+pub const FILES: [u64; 8] = [FILE_A, FILE_B, FILE_C, FILE_D, FILE_E, FILE_F, FILE_G, FILE_H];
+
 const RANK8: u64 = 0xFF00_0000_0000_0000;
 const RANK7: u64 = 0x00FF_0000_0000_0000;
 const RANK6: u64 = 0x0000_FF00_0000_0000;
@@ -296,6 +1020,124 @@ const FILE_F: u64 = 0x2020_2020_2020_2020;
 const FILE_G: u64 = 0x4040_4040_4040_4040;
 const FILE_H: u64 = 0x8080_8080_8080_8080;
 
+impl Board {
+    fn empty() -> Self {
+        Self {
+            pawns: [0; 2],
+            knights: [0; 2],
+            bishops: [0; 2],
+            rooks: [0; 2],
+            queens: [0; 2],
+            kings: [0; 2],
+            pieces: [0; 2],
+            arr: [NONE_PIECE_TYPE; 64],
+            occupied: 0,
+            empty: !0,
+            ep: None,
+            castling: CastlingRights::default(),
+            side_to_move: WHITE,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            variant: Variant::Standard,
+            checks: [0; 2],
+            pockets: [[0; 5]; 2],
+            promoted: 0,
+            history: Vec::new(),
+            pawn_attack_table: pawn_attack_table(),
+            knight_attack_table: knight_attack_table(),
+            king_attack_table: king_attack_table(),
+            rook_table: slider_tables().0.clone(),
+            bishop_table: slider_tables().1.clone(),
+            weights: Arc::default(),
+            psqt: Psqt::default(),
+            hash: 0,
+            pawn_hash: 0,
+            nnue: None,
+        }
+    }
+    /// The standard starting position.
+    pub fn new() -> Self {
+        Self::from_fen(STARTPOS).expect("start position is valid")
+    }
+    pub fn side_to_move(&self) -> Side {
+        self.side_to_move
+    }
+    pub fn castling(&self) -> CastlingRights {
+        self.castling
+    }
+    /// File of the square a pawn can currently be captured en passant on, if any.
+    pub fn ep(&self) -> Option<u8> {
+        self.ep
+    }
+    pub fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+    pub fn fullmove_number(&self) -> u16 {
+        self.fullmove_number
+    }
+    /// Zobrist key of the full position.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+    /// Zobrist key of the pawns only, used by the pawn hash table.
+    pub fn pawn_hash(&self) -> u64 {
+        self.pawn_hash
+    }
+    /// Computes the Zobrist keys `(hash, pawn_hash)` from scratch.
+    pub fn compute_hashes(&self) -> (u64, u64) {
+        let mut hash = 0;
+        let mut pawn_hash = 0;
+        for square in 0..64 {
+            let Some(side) = self.side_at(square) else {
+                continue;
+            };
+            let key = zobrist::piece_key(side, self.arr[square], square);
+            hash ^= key;
+            if self.arr[square] == PAWN_PIECE_TYPE {
+                pawn_hash ^= key;
+            }
+        }
+        hash ^= zobrist::castling_key(self.castling)
+            ^ zobrist::ep_key(self.ep)
+            ^ zobrist::side_key(self.side_to_move)
+            ^ zobrist::check_key(WHITE, self.checks[WHITE])
+            ^ zobrist::check_key(BLACK, self.checks[BLACK]);
+        for side in [WHITE, BLACK] {
+            for piece in PAWN_PIECE_TYPE..=QUEEN_PIECE_TYPE {
+                hash ^= zobrist::pocket_key(side, piece, self.pockets[side][piece as usize - 1]);
+            }
+        }
+        (hash, pawn_hash)
+    }
+    /// Piece type on `square`, `NONE_PIECE_TYPE` if empty.
+    pub fn piece_at(&self, square: usize) -> u8 {
+        self.arr[square]
+    }
+    /// Side owning the piece on `square`, if any.
+    pub fn side_at(&self, square: usize) -> Option<Side> {
+        if self.pieces[WHITE] & (1 << square) != 0 {
+            Some(WHITE)
+        } else if self.pieces[BLACK] & (1 << square) != 0 {
+            Some(BLACK)
+        } else {
+            None
+        }
+    }
+}
+
+/// The magic lookup tables are large and slow to generate, so they are built once
+/// per process and shared between all boards, e.g. one per search thread.
+fn slider_tables() -> &'static (Arc<RookTable>, Arc<BishopTable>) {
+    static TABLES: OnceLock<(Arc<RookTable>, Arc<BishopTable>)> = OnceLock::new();
+    TABLES.get_or_init(|| (Arc::new(RookTable::new()), Arc::new(BishopTable::new())))
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Board {
     fn w_pawn_attacks(&self) -> u64 {
         let pawns = self.pawns[WHITE];
@@ -315,34 +1157,46 @@ impl Board {
         }
         attacks
     }
-    fn bishop_attacks(&self, side: Side) -> u64 {
+    /// Squares next to `side`'s kings, of which Antichess may have several and
+    /// Horde none.
+    fn attacks_of_kings(&self, side: Side) -> u64 {
+        let mut kings = self.kings[side];
+        let mut attacks = 0u64;
+        while kings != 0 {
+            let lsb = lsb1(kings);
+            kings &= kings - 1;
+            attacks |= self.king_attack_table[lsb];
+        }
+        attacks
+    }
+    fn bishop_attacks(&self, side: Side, occupied: u64) -> u64 {
         let mut bishops = self.bishops[side];
         let mut attacks = 0u64;
         while bishops != 0 {
             let lsb = lsb1(bishops);
             bishops &= !(1 << lsb);
-            attacks |= self.bishop_table.lookup(lsb, self.occupied);
+            attacks |= self.bishop_table.lookup(lsb, occupied);
         }
         attacks
     }
-    fn rook_attacks(&self, side: Side) -> u64 {
+    fn rook_attacks(&self, side: Side, occupied: u64) -> u64 {
         let mut rooks = self.rooks[side];
         let mut attacks = 0u64;
         while rooks != 0 {
             let lsb = lsb1(rooks);
             rooks &= !(1 << lsb);
-            attacks |= self.rook_table.lookup(lsb, self.occupied);
+            attacks |= self.rook_table.lookup(lsb, occupied);
         }
         attacks
     }
-    fn queen_attacks(&self, side: Side) -> u64 {
+    fn queen_attacks(&self, side: Side, occupied: u64) -> u64 {
         let mut queens = self.queens[side];
         let mut attacks = 0u64;
         while queens != 0 {
             let lsb = lsb1(queens);
             queens &= !(1 << lsb);
-            attacks |= self.rook_table.lookup(lsb, self.occupied)
-                | self.bishop_table.lookup(lsb, self.occupied);
+            attacks |= self.rook_table.lookup(lsb, occupied)
+                | self.bishop_table.lookup(lsb, occupied);
         }
         attacks
     }
@@ -376,6 +1230,7 @@ impl Board {
     // }
 }
 
+#[allow(clippy::needless_range_loop)]
 pub fn pawn_attack_table() -> [[u64; 64]; 2] {
     let mut arr = [[0u64; 64]; 2];
     for i in 0..64 {
@@ -388,9 +1243,9 @@ pub fn pawn_attack_table() -> [[u64; 64]; 2] {
 
 pub fn knight_attack_table() -> [u64; 64] {
     let mut arr = [0u64; 64];
-    for i in 0..64 {
+    for (i, attacks) in arr.iter_mut().enumerate() {
         let bitboard = 1u64 << i;
-        arr[i] = (bitboard << 17) & !FILE_A // up right
+        *attacks = (bitboard << 17) & !FILE_A // up right
             | (bitboard << 15) & !FILE_H // up left
             | (bitboard << 10) & !(FILE_A | FILE_B) // left up
             | (bitboard << 6) & !(FILE_H | FILE_G) // right up
@@ -404,9 +1259,9 @@ pub fn knight_attack_table() -> [u64; 64] {
 
 pub fn king_attack_table() -> [u64; 64] {
     let mut arr = [0u64; 64];
-    for i in 0..64 {
+    for (i, attacks) in arr.iter_mut().enumerate() {
         let bitboard = 1u64 << i;
-        arr[i] = (bitboard << 8) // up
+        *attacks = (bitboard << 8) // up
             | (bitboard >> 8) // down
             | (bitboard << 1) & !FILE_A // right
             | (bitboard >> 1) & !FILE_H // left
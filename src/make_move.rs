@@ -0,0 +1,395 @@
+use std::fmt;
+
+use crate::movegen::*;
+use crate::pgn::GameResult;
+use crate::{zobrist, Board, CastlingRights, History, Side, Variant, BLACK, WHITE};
+
+impl Board {
+    fn piece_bb_mut(&mut self, piece: u8) -> &mut [u64; 2] {
+        match piece {
+            PAWN_PIECE_TYPE => &mut self.pawns,
+            KNIGHT_PIECE_TYPE => &mut self.knights,
+            BISHOP_PIECE_TYPE => &mut self.bishops,
+            ROOK_PIECE_TYPE => &mut self.rooks,
+            QUEEN_PIECE_TYPE => &mut self.queens,
+            KING_PIECE_TYPE => &mut self.kings,
+            _ => unreachable!("invalid piece type {piece}"),
+        }
+    }
+    /// Removes every piece and resets the state that goes with them.
+    pub(crate) fn clear(&mut self) {
+        self.pawns = [0; 2];
+        self.knights = [0; 2];
+        self.bishops = [0; 2];
+        self.rooks = [0; 2];
+        self.queens = [0; 2];
+        self.kings = [0; 2];
+        self.pieces = [0; 2];
+        self.arr = [NONE_PIECE_TYPE; 64];
+        self.occupied = 0;
+        self.empty = !0;
+        self.pockets = [[0; 5]; 2];
+        self.promoted = 0;
+        self.history.clear();
+        self.psqt = Default::default();
+        self.hash = 0;
+        self.pawn_hash = 0;
+    }
+    pub(crate) fn put_piece(&mut self, side: Side, piece: u8, square: usize) {
+        let bb = 1u64 << square;
+        self.piece_bb_mut(piece)[side] |= bb;
+        self.pieces[side] |= bb;
+        self.occupied |= bb;
+        self.empty &= !bb;
+        self.arr[square] = piece;
+        self.psqt.add(&self.weights, side, piece, square);
+        self.toggle_piece_hash(side, piece, square);
+    }
+    pub(crate) fn remove_piece(&mut self, side: Side, piece: u8, square: usize) {
+        let bb = 1u64 << square;
+        self.piece_bb_mut(piece)[side] &= !bb;
+        self.pieces[side] &= !bb;
+        self.occupied &= !bb;
+        self.empty |= bb;
+        self.arr[square] = NONE_PIECE_TYPE;
+        self.psqt.remove(&self.weights, side, piece, square);
+        self.toggle_piece_hash(side, piece, square);
+    }
+    fn toggle_piece_hash(&mut self, side: Side, piece: u8, square: usize) {
+        let key = zobrist::piece_key(side, piece, square);
+        self.hash ^= key;
+        if piece == PAWN_PIECE_TYPE {
+            self.pawn_hash ^= key;
+        }
+    }
+
+    /// Square of the piece captured by `mv`; differs from the target for en passant.
+    pub(crate) fn capture_square(mv: &Move, side: Side) -> usize {
+        match (mv.is_ep(), side) {
+            (false, _) => mv.to_square(),
+            (true, WHITE) => mv.to_square() - 8,
+            (true, BLACK) => mv.to_square() + 8,
+        }
+    }
+
+    /// Rook origin and destination for a castling king move.
+    pub(crate) fn castling_rook_squares(mv: &Move) -> (usize, usize) {
+        let rook = mv.to_square();
+        let rank = rook / 8 * 8;
+        if rook > mv.from_square() {
+            (rook, rank + 5)
+        } else {
+            (rook, rank + 3)
+        }
+    }
+
+    fn snapshot(&self) -> History {
+        History {
+            halfmove_clock: self.halfmove_clock,
+            hash: self.hash,
+            pawn_hash: self.pawn_hash,
+            checks: self.checks,
+            blast: 0,
+            exploded: [0; 8],
+            pockets: self.pockets,
+            promoted: self.promoted,
+        }
+    }
+
+    pub fn make_move(&mut self, mv: &Move) {
+        let side = self.side_to_move;
+        let from = mv.from_square();
+        let to = mv.to_square();
+        let piece = mv.moving_piece();
+        let captured = mv.captured_piece();
+
+        self.history.push(self.snapshot());
+        self.hash ^= zobrist::castling_key(self.castling) ^ zobrist::ep_key(self.ep);
+
+        if self.variant == Variant::Crazyhouse {
+            self.update_pockets(mv, side);
+        }
+        if captured != NONE_PIECE_TYPE {
+            self.remove_piece(!side, captured, Self::capture_square(mv, side));
+        }
+        if mv.is_drop() {
+            self.put_piece(side, piece, to);
+        } else if mv.is_castling() {
+            // The king may land where the rook stood, so both leave first.
+            let (rook_from, rook_to) = Self::castling_rook_squares(mv);
+            self.remove_piece(side, piece, from);
+            self.remove_piece(side, ROOK_PIECE_TYPE, rook_from);
+            self.put_piece(side, piece, mv.destination());
+            self.put_piece(side, ROOK_PIECE_TYPE, rook_to);
+        } else {
+            self.remove_piece(side, piece, from);
+            if mv.is_promotion() {
+                self.put_piece(side, mv.promoted_to(), to);
+            } else {
+                self.put_piece(side, piece, to);
+            }
+        }
+        let mut blast = 0;
+        if self.variant == Variant::Atomic && captured != NONE_PIECE_TYPE {
+            blast = self.explode(to);
+        }
+
+        // Moving the king or a rook, or capturing or blowing up a rook, loses the right for good.
+        if piece == KING_PIECE_TYPE {
+            match side {
+                WHITE => {
+                    self.castling.white_kingside = false;
+                    self.castling.white_queenside = false;
+                }
+                BLACK => {
+                    self.castling.black_kingside = false;
+                    self.castling.black_queenside = false;
+                }
+            }
+        }
+        if self.castling.any() {
+            for side in [WHITE, BLACK] {
+                for kingside in [true, false] {
+                    let rook = self.castling.rook_square(side, kingside);
+                    if rook == from || rook == to || blast & (1 << rook) != 0 {
+                        self.castling.set(side, kingside, false);
+                    }
+                }
+            }
+        }
+
+        // Horde pawns double-pushing from the first rank cannot be taken en passant.
+        self.ep = if piece == PAWN_PIECE_TYPE && from.abs_diff(to) == 16 && matches!(from / 8, 1 | 6) {
+            Some((from % 8) as u8)
+        } else {
+            None
+        };
+
+        if piece == PAWN_PIECE_TYPE || captured != NONE_PIECE_TYPE {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if side == BLACK {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = !side;
+        if self.variant == Variant::ThreeCheck && self.is_check() {
+            self.hash ^= zobrist::check_key(side, self.checks[side]);
+            self.checks[side] += 1;
+            self.hash ^= zobrist::check_key(side, self.checks[side]);
+        }
+        if let Some(mut nnue) = self.nnue.take() {
+            nnue.make_move(self, mv);
+            self.nnue = Some(nnue);
+        }
+        self.hash ^= zobrist::castling_key(self.castling)
+            ^ zobrist::ep_key(self.ep)
+            ^ zobrist::side_key(WHITE)
+            ^ zobrist::side_key(BLACK);
+    }
+
+    pub fn unmake_move(&mut self, mv: &Move) {
+        let side = !self.side_to_move;
+        let from = mv.from_square();
+        let to = mv.to_square();
+        let piece = mv.moving_piece();
+        let captured = mv.captured_piece();
+
+        if self.variant == Variant::Atomic && captured != NONE_PIECE_TYPE {
+            self.undo_explosion(mv, side);
+        }
+        if mv.is_drop() {
+            self.remove_piece(side, piece, to);
+        } else if mv.is_castling() {
+            let (rook_from, rook_to) = Self::castling_rook_squares(mv);
+            self.remove_piece(side, piece, mv.destination());
+            self.remove_piece(side, ROOK_PIECE_TYPE, rook_to);
+            self.put_piece(side, piece, from);
+            self.put_piece(side, ROOK_PIECE_TYPE, rook_from);
+        } else {
+            if mv.is_promotion() {
+                self.remove_piece(side, mv.promoted_to(), to);
+            } else {
+                self.remove_piece(side, piece, to);
+            }
+            self.put_piece(side, piece, from);
+        }
+        if captured != NONE_PIECE_TYPE {
+            self.put_piece(!side, captured, Self::capture_square(mv, side));
+        }
+
+        self.castling = CastlingRights {
+            rook_files: self.castling.rook_files,
+            ..mv.castling_rights()
+        };
+        self.ep = if mv.is_prev_ep() {
+            Some(mv.prev_ep_file())
+        } else {
+            None
+        };
+        let history = self.history.pop().expect("unmake_move without make_move");
+        self.halfmove_clock = history.halfmove_clock;
+        self.hash = history.hash;
+        self.pawn_hash = history.pawn_hash;
+        self.checks = history.checks;
+        self.pockets = history.pockets;
+        self.promoted = history.promoted;
+        if side == BLACK {
+            self.fullmove_number -= 1;
+        }
+        self.side_to_move = side;
+        if let Some(nnue) = &mut self.nnue {
+            nnue.unmake_move();
+        }
+    }
+
+    /// Passes the turn, for null move pruning. Returns the move to hand to
+    /// `unmake_null_move`, which remembers the en passant state.
+    pub fn make_null_move(&mut self) -> Move {
+        let mut mv = Move::new();
+        mv.set_prev_ep(self.ep);
+        mv.set_castling_rights(self.castling);
+        self.history.push(self.snapshot());
+        self.hash ^= zobrist::ep_key(self.ep)
+            ^ zobrist::side_key(WHITE)
+            ^ zobrist::side_key(BLACK);
+        self.ep = None;
+        self.halfmove_clock += 1;
+        self.side_to_move = !self.side_to_move;
+        if let Some(nnue) = &mut self.nnue {
+            nnue.push();
+        }
+        mv
+    }
+
+    pub fn unmake_null_move(&mut self, mv: &Move) {
+        self.side_to_move = !self.side_to_move;
+        self.ep = if mv.is_prev_ep() {
+            Some(mv.prev_ep_file())
+        } else {
+            None
+        };
+        let history = self.history.pop().expect("unmake_null_move without make_null_move");
+        self.halfmove_clock = history.halfmove_clock;
+        self.hash = history.hash;
+        if let Some(nnue) = &mut self.nnue {
+            nnue.unmake_move();
+        }
+    }
+
+    /// Whether the position occurred before since the last irreversible move.
+    pub fn is_repetition(&self) -> bool {
+        let len = self.history.len();
+        let reversible = (self.halfmove_clock as usize).min(len);
+        (2..=reversible)
+            .step_by(2)
+            .any(|back| self.history[len - back].hash == self.hash)
+    }
+
+    /// Draw by repetition, the fifty-move rule or a rule of the variant.
+    pub fn is_draw(&self) -> bool {
+        self.halfmove_clock >= 100 || self.is_repetition() || self.is_variant_draw()
+    }
+
+    /// Whether the position occurred twice before since the last irreversible move.
+    pub fn is_threefold_repetition(&self) -> bool {
+        let len = self.history.len();
+        let reversible = (self.halfmove_clock as usize).min(len);
+        (2..=reversible)
+            .step_by(2)
+            .filter(|&back| self.history[len - back].hash == self.hash)
+            .nth(1)
+            .is_some()
+    }
+
+    /// Whether neither side can mate: a lone king against at most one minor
+    /// piece, or bishops all on squares of one color.
+    pub fn is_insufficient_material(&self) -> bool {
+        const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+        let heavy = self.pawns[WHITE] | self.pawns[BLACK] | self.rooks[WHITE] | self.rooks[BLACK];
+        if heavy | self.queens[WHITE] | self.queens[BLACK] != 0 {
+            return false;
+        }
+        let knights = self.knights[WHITE] | self.knights[BLACK];
+        let bishops = self.bishops[WHITE] | self.bishops[BLACK];
+        (knights | bishops).count_ones() <= 1
+            || knights == 0 && (bishops & DARK_SQUARES == 0 || bishops & !DARK_SQUARES == 0)
+    }
+
+    /// How the game has ended by the rules, or `None` while it goes on. Draws
+    /// that need a claim, by threefold repetition and the fifty-move rule, count.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if let Some(winner) = self.variant_winner() {
+            return Some(Outcome::Variant(Some(winner)));
+        }
+        if self.is_variant_draw() {
+            return Some(Outcome::Variant(None));
+        }
+        let mut moves = Moves::new();
+        self.generate_moves(&mut moves, self.side_to_move);
+        if moves.is_empty() {
+            return Some(match self.variant {
+                // Running out of moves wins.
+                Variant::Antichess => Outcome::Variant(Some(self.side_to_move)),
+                _ if self.is_check() => Outcome::Checkmate(!self.side_to_move),
+                _ => Outcome::Stalemate,
+            });
+        }
+        if self.halfmove_clock >= 100 {
+            Some(Outcome::FiftyMoves)
+        } else if self.is_threefold_repetition() {
+            Some(Outcome::Repetition)
+        } else if self.variant == Variant::Standard && self.is_insufficient_material() {
+            Some(Outcome::InsufficientMaterial)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Won by the given side.
+    Checkmate(Side),
+    Stalemate,
+    Repetition,
+    FiftyMoves,
+    InsufficientMaterial,
+    /// Won by the given side or drawn (`None`) by a rule of the variant.
+    Variant(Option<Side>),
+}
+
+impl Outcome {
+    pub fn winner(self) -> Option<Side> {
+        match self {
+            Outcome::Checkmate(winner) => Some(winner),
+            Outcome::Variant(winner) => winner,
+            _ => None,
+        }
+    }
+
+    pub fn result(self) -> GameResult {
+        match self.winner() {
+            Some(WHITE) => GameResult::WhiteWins,
+            Some(BLACK) => GameResult::BlackWins,
+            None => GameResult::Draw,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |side: &Side| if *side == WHITE { "white" } else { "black" };
+        match self {
+            Outcome::Checkmate(winner) => write!(f, "{} mates", name(winner)),
+            Outcome::Stalemate => f.write_str("stalemate"),
+            Outcome::Repetition => f.write_str("threefold repetition"),
+            Outcome::FiftyMoves => f.write_str("fifty-move rule"),
+            Outcome::InsufficientMaterial => f.write_str("insufficient material"),
+            Outcome::Variant(Some(winner)) => write!(f, "{} wins by the variant's rules", name(winner)),
+            Outcome::Variant(None) => f.write_str("draw by the variant's rules"),
+        }
+    }
+}
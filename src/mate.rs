@@ -0,0 +1,94 @@
+use crate::{Board, Move, Moves};
+
+/// Outcome of `Board::find_mate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MateResult {
+    /// A forced mate: the attacker's moves alternate with the defender's
+    /// longest resistance, ending in checkmate.
+    Mate(Vec<Move>),
+    /// Every line was searched and the side to move cannot force mate within
+    /// the limit.
+    NoMate,
+}
+
+impl Board {
+    /// Looks for a forced mate in at most `max_moves` moves for the side to
+    /// move, trying the shortest mates first.
+    ///
+    /// The attacker's tree is complete: the mating move itself is taken from
+    /// the checks-only generator, every earlier move is tried with checks
+    /// first, and the defender always gets every legal reply. `NoMate` is
+    /// therefore a proof. The fifty-move rule and repetitions are ignored.
+    pub fn find_mate(&self, max_moves: u32) -> MateResult {
+        self.solve(max_moves, false)
+    }
+
+    /// Like `find_mate`, but the attacker only ever plays checks. Much faster
+    /// for typical puzzles; `NoMate` then only rules out mates by checks.
+    pub fn find_mate_by_checks(&self, max_moves: u32) -> MateResult {
+        self.solve(max_moves, true)
+    }
+
+    fn solve(&self, max_moves: u32, checks_only: bool) -> MateResult {
+        let mut board = self.clone();
+        for moves in 1..=max_moves {
+            if let Some(line) = board.attack(moves, checks_only) {
+                return MateResult::Mate(line);
+            }
+        }
+        MateResult::NoMate
+    }
+
+    /// Mating line for the side to move within `moves_left` moves, if any.
+    fn attack(&mut self, moves_left: u32, checks_only: bool) -> Option<Vec<Move>> {
+        let side = self.side_to_move;
+        let mut checks = Moves::new();
+        self.generate_checks(&mut checks, side);
+        let mut candidates: Vec<Move> = checks.to_vec();
+        // Mate is always delivered with a check.
+        if moves_left > 1 && !checks_only {
+            let mut all = Moves::new();
+            self.generate_moves(&mut all, side);
+            candidates.extend(all.into_iter().filter(|mv| !checks.contains(mv)));
+        }
+
+        for mv in candidates {
+            self.make_move(&mv);
+            let defence = self.defend(moves_left, checks_only);
+            self.unmake_move(&mv);
+            if let Some(mut line) = defence {
+                line.insert(0, mv);
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    /// With the defender to move, the line showing every reply loses within
+    /// `moves_left` attacker moves (counting the one just played), or `None`
+    /// if some reply escapes.
+    fn defend(&mut self, moves_left: u32, checks_only: bool) -> Option<Vec<Move>> {
+        let mut replies = Moves::new();
+        self.generate_moves(&mut replies, self.side_to_move);
+        if replies.is_empty() {
+            return self.is_check().then(Vec::new);
+        }
+        if moves_left == 1 {
+            return None;
+        }
+
+        let mut longest: Option<Vec<Move>> = None;
+        for reply in replies {
+            self.make_move(&reply);
+            // Shortest mate against this reply, so the line shows best play.
+            let line = (1..moves_left).find_map(|moves| self.attack(moves, checks_only));
+            self.unmake_move(&reply);
+            let mut line = line?;
+            line.insert(0, reply);
+            if longest.as_ref().is_none_or(|longest| line.len() > longest.len()) {
+                longest = Some(line);
+            }
+        }
+        longest
+    }
+}
@@ -0,0 +1,311 @@
+//! NNUE evaluation.
+//!
+//! The network has HalfKA inputs: for each perspective, one feature per
+//! (own king square, piece colour, piece type, square), with squares flipped
+//! vertically for black so both perspectives share weights. The feature
+//! transformer is quantized to `i16` and the output layer to `i8`, and all
+//! arithmetic is integer so the SIMD paths match the scalar one bit for bit.
+//!
+//! File layout, all little-endian:
+//!
+//! ```text
+//! magic        b"CNUE"
+//! version      u32 = 1
+//! hidden       u32, a multiple of 16
+//! ft_weights   i16 * FEATURES * hidden, one row of `hidden` per feature
+//! ft_biases    i16 * hidden
+//! out_weights  i8  * 2 * hidden, side to move's half first
+//! out_bias     i32
+//! ```
+
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::movegen::*;
+use crate::utils::lsb1;
+use crate::{Board, Side, Variant, BLACK, WHITE};
+
+mod simd;
+pub use simd::SimdBackend;
+
+pub const FEATURES: usize = 64 * 12 * 64;
+const MAGIC: &[u8; 4] = b"CNUE";
+const VERSION: u32 = 1;
+
+/// Accumulator values are clipped to `0..=QA` before the output layer.
+pub const QA: i32 = 127;
+/// Scale of the output layer weights.
+pub const QB: i32 = 64;
+/// Converts the network output to centipawns.
+pub const SCALE: i32 = 400;
+
+#[derive(Clone, Debug)]
+pub struct Network {
+    hidden: usize,
+    ft_weights: Vec<i16>,
+    ft_biases: Vec<i16>,
+    // Stored widened to i16 so the output layer can use the 16-bit multiply-add.
+    out_weights: Vec<i16>,
+    out_bias: i32,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Network {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        bytes.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not an NNUE file"));
+        }
+        let mut word = [0u8; 4];
+        bytes.read_exact(&mut word)?;
+        if u32::from_le_bytes(word) != VERSION {
+            return Err(invalid("unsupported NNUE version"));
+        }
+        bytes.read_exact(&mut word)?;
+        let hidden = u32::from_le_bytes(word) as usize;
+        if hidden == 0 || !hidden.is_multiple_of(16) {
+            return Err(invalid("hidden layer size must be a non-zero multiple of 16"));
+        }
+
+        let expected = FEATURES * hidden * 2 + hidden * 2 + 2 * hidden + 4;
+        if bytes.len() != expected {
+            return Err(invalid("NNUE file has the wrong size for its layer sizes"));
+        }
+        let (ft_weights, rest) = bytes.split_at(FEATURES * hidden * 2);
+        let (ft_biases, rest) = rest.split_at(hidden * 2);
+        let (out_weights, out_bias) = rest.split_at(2 * hidden);
+
+        let read_i16 = |b: &[u8]| {
+            b.chunks_exact(2)
+                .map(|c| i16::from_le_bytes([c[0], c[1]]))
+                .collect::<Vec<_>>()
+        };
+        Ok(Self {
+            hidden,
+            ft_weights: read_i16(ft_weights),
+            ft_biases: read_i16(ft_biases),
+            out_weights: out_weights.iter().map(|&w| w as i8 as i16).collect(),
+            out_bias: i32::from_le_bytes(out_bias.try_into().expect("4 bytes left")),
+        })
+    }
+
+    /// Serializes the network in the format `from_bytes` reads.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + (self.ft_weights.len() + self.hidden) * 2);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.hidden as u32).to_le_bytes());
+        for w in self.ft_weights.iter().chain(&self.ft_biases) {
+            bytes.extend_from_slice(&w.to_le_bytes());
+        }
+        bytes.extend(self.out_weights.iter().map(|&w| w as i8 as u8));
+        bytes.extend_from_slice(&self.out_bias.to_le_bytes());
+        bytes
+    }
+
+    /// A network with random weights, for testing the inference code without a trained file.
+    pub fn random(hidden: usize, seed: u64) -> Self {
+        assert!(hidden > 0 && hidden.is_multiple_of(16));
+        let mut rng = fastrand::Rng::with_seed(seed);
+        Self {
+            hidden,
+            ft_weights: (0..FEATURES * hidden).map(|_| rng.i16(-64..64)).collect(),
+            ft_biases: (0..hidden).map(|_| rng.i16(-64..64)).collect(),
+            out_weights: (0..2 * hidden).map(|_| rng.i8(..) as i16).collect(),
+            out_bias: rng.i32(-1000..1000),
+        }
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.hidden
+    }
+
+    fn row(&self, feature: usize) -> &[i16] {
+        &self.ft_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    /// Output for the side whose accumulator is `us`, in centipawns.
+    pub fn output(&self, us: &[i16], them: &[i16], backend: SimdBackend) -> i32 {
+        let (w_us, w_them) = self.out_weights.split_at(self.hidden);
+        let sum = backend.dot_clipped(us, w_us) + backend.dot_clipped(them, w_them);
+        // The scaled sum can exceed i32 for wide hidden layers.
+        ((sum as i64 + self.out_bias as i64) * SCALE as i64 / (QA * QB) as i64) as i32
+    }
+}
+
+/// Index of a piece's feature from `perspective`, whose king stands on `king`.
+pub fn feature(perspective: Side, king: usize, side: Side, piece: u8, square: usize) -> usize {
+    let (king, square) = match perspective {
+        WHITE => (king, square),
+        BLACK => (king ^ 56, square ^ 56),
+    };
+    let colour = (side != perspective) as usize;
+    king * 768 + (colour * 6 + piece as usize - 1) * 64 + square
+}
+
+/// Feature transformer outputs for both perspectives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accumulator {
+    pub values: [Vec<i16>; 2],
+}
+
+/// Incremental NNUE state kept by `Board`: one accumulator per ply, so that
+/// unmaking a move is just a pop.
+#[derive(Clone, Debug)]
+pub struct NnueState {
+    net: Arc<Network>,
+    backend: SimdBackend,
+    stack: Vec<Accumulator>,
+    top: usize,
+}
+
+impl NnueState {
+    pub fn new(net: Arc<Network>, board: &Board) -> Self {
+        let hidden = net.hidden;
+        let mut state = Self {
+            net,
+            backend: SimdBackend::detect(),
+            stack: vec![Accumulator {
+                values: [vec![0; hidden], vec![0; hidden]],
+            }],
+            top: 0,
+        };
+        state.reset(board);
+        state
+    }
+
+    /// Drops the accumulator history and recomputes the current one for `board`.
+    pub fn reset(&mut self, board: &Board) {
+        self.top = 0;
+        self.refresh(board, WHITE);
+        self.refresh(board, BLACK);
+    }
+
+    pub fn network(&self) -> &Arc<Network> {
+        &self.net
+    }
+    pub fn backend(&self) -> SimdBackend {
+        self.backend
+    }
+    /// Switches the SIMD implementation, e.g. to compare it against `SimdBackend::Scalar`.
+    /// One the CPU lacks falls back as `SimdBackend` describes.
+    pub fn set_backend(&mut self, backend: SimdBackend) {
+        self.backend = backend;
+    }
+    pub fn accumulator(&self) -> &Accumulator {
+        &self.stack[self.top]
+    }
+
+    /// Recomputes one perspective of the current accumulator from scratch.
+    pub fn refresh(&mut self, board: &Board, perspective: Side) {
+        let net = &self.net;
+        let acc = &mut self.stack[self.top].values[perspective];
+        acc.copy_from_slice(&net.ft_biases);
+        // Antichess can lose the king; its evaluation does not use the network.
+        if board.kings[perspective] == 0 {
+            return;
+        }
+        let king = lsb1(board.kings[perspective]);
+        for side in [WHITE, BLACK] {
+            let mut pieces = board.pieces[side];
+            while pieces != 0 {
+                let sq = lsb1(pieces);
+                pieces &= !(1 << sq);
+                let f = feature(perspective, king, side, board.arr[sq], sq);
+                self.backend.add(acc, net.row(f));
+            }
+        }
+    }
+
+    /// Pushes a copy of the current accumulator.
+    pub fn push(&mut self) {
+        if self.top + 1 == self.stack.len() {
+            let copy = self.stack[self.top].clone();
+            self.stack.push(copy);
+        } else {
+            let (done, rest) = self.stack.split_at_mut(self.top + 1);
+            for perspective in [WHITE, BLACK] {
+                rest[0].values[perspective].copy_from_slice(&done[self.top].values[perspective]);
+            }
+        }
+        self.top += 1;
+    }
+
+    /// Pushes a new accumulator for `mv`. `board` is the position after the move.
+    pub fn make_move(&mut self, board: &Board, mv: &Move) {
+        let side = !board.side_to_move;
+        self.push();
+
+        let from = mv.from_square();
+        let to = mv.destination();
+        let piece = mv.moving_piece();
+        let promoted = if mv.is_promotion() { mv.promoted_to() } else { piece };
+        // A move removes at most two pieces and adds at most two; castling never captures.
+        let rook = mv.is_castling().then(|| Board::castling_rook_squares(mv));
+        let captured = (mv.captured_piece() != NONE_PIECE_TYPE)
+            .then(|| (!side, mv.captured_piece(), Board::capture_square(mv, side)));
+        let removed = [
+            (!mv.is_drop()).then_some((side, piece, from)),
+            captured.or(rook.map(|(rook_from, _)| (side, ROOK_PIECE_TYPE, rook_from))),
+        ];
+        let added = [
+            Some((side, promoted, to)),
+            rook.map(|(_, rook_to)| (side, ROOK_PIECE_TYPE, rook_to)),
+        ];
+
+        for perspective in [WHITE, BLACK] {
+            // Every feature depends on the own king square, so a king move starts over,
+            // as does an Atomic capture, which can take out many pieces at once.
+            if (piece == KING_PIECE_TYPE && perspective == side)
+                || (board.variant() == Variant::Atomic && captured.is_some())
+            {
+                self.refresh(board, perspective);
+                continue;
+            }
+            if board.kings[perspective] == 0 {
+                continue;
+            }
+            let king = lsb1(board.kings[perspective]);
+            let acc = &mut self.stack[self.top].values[perspective];
+            for (s, p, sq) in removed.into_iter().flatten() {
+                self.backend.sub(acc, self.net.row(feature(perspective, king, s, p, sq)));
+            }
+            for (s, p, sq) in added.into_iter().flatten() {
+                self.backend.add(acc, self.net.row(feature(perspective, king, s, p, sq)));
+            }
+        }
+    }
+
+    pub fn unmake_move(&mut self) {
+        self.top -= 1;
+    }
+
+    /// Evaluation in centipawns from `side`'s point of view.
+    pub fn evaluate(&self, side: Side) -> i32 {
+        let acc = &self.stack[self.top];
+        self.net.output(&acc.values[side], &acc.values[!side], self.backend)
+    }
+}
+
+impl Board {
+    /// Switches the evaluation to `net`, or back to the hand-written one with `None`.
+    pub fn set_network(&mut self, net: Option<Arc<Network>>) {
+        self.nnue = net.map(|net| Box::new(NnueState::new(net, self)));
+    }
+    pub fn nnue(&self) -> Option<&NnueState> {
+        self.nnue.as_deref()
+    }
+    pub fn nnue_mut(&mut self) -> Option<&mut NnueState> {
+        self.nnue.as_deref_mut()
+    }
+}
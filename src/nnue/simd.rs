@@ -0,0 +1,199 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use super::QA;
+
+/// Implementation used for the accumulator updates and the output layer. All of
+/// them give identical results; adds wrap like the vector instructions do.
+/// `Avx2` runs as `Sse2` on CPUs without AVX2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimdBackend {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Sse2,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+}
+
+impl SimdBackend {
+    /// The fastest implementation the CPU supports.
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return SimdBackend::Avx2;
+            }
+            SimdBackend::Sse2
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        SimdBackend::Scalar
+    }
+
+    /// Whether the CPU runs this implementation itself rather than a fallback.
+    pub fn is_supported(self) -> bool {
+        match self {
+            SimdBackend::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Sse2 => true,
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Avx2 => is_x86_feature_detected!("avx2"),
+        }
+    }
+
+    /// Adds `row` to `acc`. Both must have the same length, a multiple of 16.
+    pub fn add(self, acc: &mut [i16], row: &[i16]) {
+        assert!(acc.len() == row.len() && acc.len().is_multiple_of(16));
+        match self {
+            SimdBackend::Scalar => {
+                for (a, r) in acc.iter_mut().zip(row) {
+                    *a = a.wrapping_add(*r);
+                }
+            }
+            // SAFETY: AVX2 was detected at runtime, and lengths are multiples of 16.
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Avx2 if is_x86_feature_detected!("avx2") => unsafe { avx2::add(acc, row) },
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Sse2 | SimdBackend::Avx2 => unsafe { sse2::add(acc, row) },
+        }
+    }
+
+    /// Subtracts `row` from `acc`, with the same requirements as `add`.
+    pub fn sub(self, acc: &mut [i16], row: &[i16]) {
+        assert!(acc.len() == row.len() && acc.len().is_multiple_of(16));
+        match self {
+            SimdBackend::Scalar => {
+                for (a, r) in acc.iter_mut().zip(row) {
+                    *a = a.wrapping_sub(*r);
+                }
+            }
+            // SAFETY: see `add`.
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Avx2 if is_x86_feature_detected!("avx2") => unsafe { avx2::sub(acc, row) },
+            // SAFETY: see `add`.
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Sse2 | SimdBackend::Avx2 => unsafe { sse2::sub(acc, row) },
+        }
+    }
+
+    /// Sum of `clamp(acc, 0, QA) * weights`. Products stay below 2^15, so pairwise
+    /// 16-bit multiply-adds cannot overflow. Lengths are as for `add`.
+    pub fn dot_clipped(self, acc: &[i16], weights: &[i16]) -> i32 {
+        assert!(acc.len() == weights.len() && acc.len().is_multiple_of(16));
+        match self {
+            SimdBackend::Scalar => acc
+                .iter()
+                .zip(weights)
+                .map(|(&a, &w)| (a as i32).clamp(0, QA) * w as i32)
+                .sum(),
+            // SAFETY: see `add`.
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Avx2 if is_x86_feature_detected!("avx2") => unsafe { avx2::dot_clipped(acc, weights) },
+            // SAFETY: see `add`.
+            #[cfg(target_arch = "x86_64")]
+            SimdBackend::Sse2 | SimdBackend::Avx2 => unsafe { sse2::dot_clipped(acc, weights) },
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod sse2 {
+    use super::*;
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn add(acc: &mut [i16], row: &[i16]) {
+        // SAFETY: the caller guarantees the target feature is available; every
+        // load and store is of a full chunk from `chunks_exact`.
+        unsafe {
+            for (a, r) in acc.chunks_exact_mut(8).zip(row.chunks_exact(8)) {
+                let a = a.as_mut_ptr() as *mut __m128i;
+                let r = r.as_ptr() as *const __m128i;
+                _mm_storeu_si128(a, _mm_add_epi16(_mm_loadu_si128(a), _mm_loadu_si128(r)));
+            }
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn sub(acc: &mut [i16], row: &[i16]) {
+        // SAFETY: the caller guarantees the target feature is available; every
+        // load and store is of a full chunk from `chunks_exact`.
+        unsafe {
+            for (a, r) in acc.chunks_exact_mut(8).zip(row.chunks_exact(8)) {
+                let a = a.as_mut_ptr() as *mut __m128i;
+                let r = r.as_ptr() as *const __m128i;
+                _mm_storeu_si128(a, _mm_sub_epi16(_mm_loadu_si128(a), _mm_loadu_si128(r)));
+            }
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn dot_clipped(acc: &[i16], weights: &[i16]) -> i32 {
+        // SAFETY: the caller guarantees the target feature is available; every
+        // load and store is of a full chunk from `chunks_exact`.
+        unsafe {
+            let zero = _mm_setzero_si128();
+            let qa = _mm_set1_epi16(QA as i16);
+            let mut sum = _mm_setzero_si128();
+            for (a, w) in acc.chunks_exact(8).zip(weights.chunks_exact(8)) {
+                let a = _mm_loadu_si128(a.as_ptr() as *const __m128i);
+                let w = _mm_loadu_si128(w.as_ptr() as *const __m128i);
+                let clipped = _mm_min_epi16(_mm_max_epi16(a, zero), qa);
+                sum = _mm_add_epi32(sum, _mm_madd_epi16(clipped, w));
+            }
+            let mut lanes = [0i32; 4];
+            _mm_storeu_si128(lanes.as_mut_ptr() as *mut __m128i, sum);
+            lanes.iter().sum()
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use super::*;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add(acc: &mut [i16], row: &[i16]) {
+        // SAFETY: the caller guarantees the target feature is available; every
+        // load and store is of a full chunk from `chunks_exact`.
+        unsafe {
+            for (a, r) in acc.chunks_exact_mut(16).zip(row.chunks_exact(16)) {
+                let a = a.as_mut_ptr() as *mut __m256i;
+                let r = r.as_ptr() as *const __m256i;
+                _mm256_storeu_si256(a, _mm256_add_epi16(_mm256_loadu_si256(a), _mm256_loadu_si256(r)));
+            }
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub(acc: &mut [i16], row: &[i16]) {
+        // SAFETY: the caller guarantees the target feature is available; every
+        // load and store is of a full chunk from `chunks_exact`.
+        unsafe {
+            for (a, r) in acc.chunks_exact_mut(16).zip(row.chunks_exact(16)) {
+                let a = a.as_mut_ptr() as *mut __m256i;
+                let r = r.as_ptr() as *const __m256i;
+                _mm256_storeu_si256(a, _mm256_sub_epi16(_mm256_loadu_si256(a), _mm256_loadu_si256(r)));
+            }
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn dot_clipped(acc: &[i16], weights: &[i16]) -> i32 {
+        // SAFETY: the caller guarantees the target feature is available; every
+        // load and store is of a full chunk from `chunks_exact`.
+        unsafe {
+            let zero = _mm256_setzero_si256();
+            let qa = _mm256_set1_epi16(QA as i16);
+            let mut sum = _mm256_setzero_si256();
+            for (a, w) in acc.chunks_exact(16).zip(weights.chunks_exact(16)) {
+                let a = _mm256_loadu_si256(a.as_ptr() as *const __m256i);
+                let w = _mm256_loadu_si256(w.as_ptr() as *const __m256i);
+                let clipped = _mm256_min_epi16(_mm256_max_epi16(a, zero), qa);
+                sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, w));
+            }
+            let mut lanes = [0i32; 8];
+            _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum);
+            lanes.iter().sum()
+        }
+    }
+}
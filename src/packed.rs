@@ -0,0 +1,237 @@
+//! Fixed-size position records for evaluation training, in the 32-byte layout
+//! known as marlinformat:
+//!
+//! ```text
+//! occupancy    u64, a1 = bit 0
+//! pieces       u128, one nibble per occupied square from a1 up: piece type from
+//!              0 (pawn) to 5 (king), 6 for a rook that may still castle, +8 for black
+//! stm_ep       u8, the en passant square or 64 for none, +128 if black is to move
+//! halfmove     u8
+//! fullmove     u16
+//! score        i16, centipawns from white's point of view
+//! result       u8, 0 for a black win, 1 for a draw, 2 for a white win
+//! extra        u8, unused
+//! ```
+//!
+//! Multi-byte fields are little-endian.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::fen::{placement, square_name};
+use crate::pgn::GameResult;
+use crate::{Board, FenError, Variant, BLACK, ROOK_PIECE_TYPE, WHITE};
+
+pub const RECORD_SIZE: usize = 32;
+
+const UNMOVED_ROOK: u8 = 6;
+const NO_EP: u8 = 64;
+
+/// One encoded position with its training labels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedPosition(pub [u8; RECORD_SIZE]);
+
+#[derive(Debug)]
+pub enum PackError {
+    /// Only standard chess and Chess960 positions can be packed.
+    Variant(Variant),
+    /// The format has room for 32 pieces.
+    TooManyPieces(u32),
+    InvalidPiece(u8),
+    Fen(FenError),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::Variant(variant) => write!(f, "cannot pack {variant} positions"),
+            PackError::TooManyPieces(count) => write!(f, "{count} pieces do not fit in a record"),
+            PackError::InvalidPiece(code) => write!(f, "invalid piece code {code}"),
+            PackError::Fen(err) => write!(f, "invalid position: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl PackedPosition {
+    /// Centipawn score from white's point of view.
+    pub fn score(&self) -> i16 {
+        i16::from_le_bytes([self.0[28], self.0[29]])
+    }
+    pub fn set_score(&mut self, score: i16) {
+        self.0[28..30].copy_from_slice(&score.to_le_bytes());
+    }
+    /// The game's result, `None` if the byte is not a valid result.
+    pub fn result(&self) -> Option<GameResult> {
+        match self.0[30] {
+            0 => Some(GameResult::BlackWins),
+            1 => Some(GameResult::Draw),
+            2 => Some(GameResult::WhiteWins),
+            _ => None,
+        }
+    }
+    pub fn set_result(&mut self, result: GameResult) {
+        self.0[30] = match result {
+            GameResult::BlackWins => 0,
+            GameResult::Draw => 1,
+            GameResult::WhiteWins => 2,
+        };
+    }
+    /// Plies played since the start of the game, from the move number.
+    pub fn ply(&self) -> u32 {
+        let fullmove = u16::from_le_bytes([self.0[26], self.0[27]]) as u32;
+        2 * fullmove.saturating_sub(1) + (self.0[24] >> 7) as u32
+    }
+}
+
+impl Board {
+    /// Packs the position with a score of 0 and a drawn result; fill those in
+    /// with `set_score` and `set_result`.
+    pub fn to_packed(&self) -> Result<PackedPosition, PackError> {
+        if self.variant != Variant::Standard {
+            return Err(PackError::Variant(self.variant));
+        }
+        let count = self.occupied.count_ones();
+        if count > 32 {
+            return Err(PackError::TooManyPieces(count));
+        }
+        let mut castling_rooks = 0u64;
+        for side in [WHITE, BLACK] {
+            for kingside in [true, false] {
+                if self.castling.has(side, kingside) {
+                    castling_rooks |= 1 << self.castling.rook_square(side, kingside);
+                }
+            }
+        }
+        let mut pieces = 0u128;
+        let mut occupied = self.occupied;
+        let mut shift = 0;
+        while occupied != 0 {
+            let square = occupied.trailing_zeros() as usize;
+            occupied &= occupied - 1;
+            let code = if castling_rooks & (1 << square) != 0 { UNMOVED_ROOK } else { self.arr[square] - 1 };
+            let color = if self.side_at(square) == Some(BLACK) { 8 } else { 0 };
+            pieces |= ((code | color) as u128) << shift;
+            shift += 4;
+        }
+        let ep = match self.ep {
+            Some(file) => file + if self.side_to_move == WHITE { 40 } else { 16 },
+            None => NO_EP,
+        };
+
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[..8].copy_from_slice(&self.occupied.to_le_bytes());
+        bytes[8..24].copy_from_slice(&pieces.to_le_bytes());
+        bytes[24] = ep | if self.side_to_move == BLACK { 0x80 } else { 0 };
+        bytes[25] = self.halfmove_clock.min(u8::MAX as u16) as u8;
+        bytes[26..28].copy_from_slice(&self.fullmove_number.to_le_bytes());
+        let mut packed = PackedPosition(bytes);
+        packed.set_result(GameResult::Draw);
+        Ok(packed)
+    }
+
+    pub fn from_packed(packed: &PackedPosition) -> Result<Self, PackError> {
+        let bytes = &packed.0;
+        let occupancy = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"));
+        let pieces = u128::from_le_bytes(bytes[8..24].try_into().expect("16 bytes"));
+        if occupancy.count_ones() > 32 {
+            return Err(PackError::TooManyPieces(occupancy.count_ones()));
+        }
+
+        let mut squares = [None; 64];
+        let mut unmoved_rooks = Vec::new();
+        for (i, square) in (0..64).filter(|&square| occupancy & (1 << square) != 0).enumerate() {
+            let code = (pieces >> (4 * i)) as u8 & 0xf;
+            let side = if code & 8 != 0 { BLACK } else { WHITE };
+            let piece = match code & 7 {
+                UNMOVED_ROOK => {
+                    unmoved_rooks.push((side, square));
+                    ROOK_PIECE_TYPE
+                }
+                piece @ 0..=5 => piece + 1,
+                _ => return Err(PackError::InvalidPiece(code)),
+            };
+            squares[square] = Some((side, piece));
+        }
+
+        // Shredder-FEN castling, naming the files of the rooks that may still
+        // castle. White's come first, as the squares go up from a1.
+        let mut castling: String = unmoved_rooks
+            .into_iter()
+            .map(|(side, square)| {
+                let file = (b'a' + (square % 8) as u8) as char;
+                if side == WHITE { file.to_ascii_uppercase() } else { file }
+            })
+            .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        let side = if bytes[24] & 0x80 != 0 { BLACK } else { WHITE };
+        let ep = match bytes[24] & 0x7f {
+            square @ 0..64 => square_name(square as usize),
+            _ => "-".to_string(),
+        };
+        let fen = format!(
+            "{} {} {castling} {ep} {} {}",
+            placement(&squares, 0),
+            if side == WHITE { 'w' } else { 'b' },
+            bytes[25],
+            u16::from_le_bytes([bytes[26], bytes[27]]).max(1),
+        );
+        Board::from_fen(&fen).map_err(PackError::Fen)
+    }
+}
+
+/// Writes records back to back.
+pub struct PackedWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> PackedWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write(&mut self, packed: &PackedPosition) -> io::Result<()> {
+        self.writer.write_all(&packed.0)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads records one after another. A trailing partial record is an error.
+pub struct PackedReader<R> {
+    reader: R,
+}
+
+impl<R: Read> PackedReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<PackedPosition>> {
+        let mut bytes = [0; RECORD_SIZE];
+        let mut filled = 0;
+        while filled < RECORD_SIZE {
+            match self.reader.read(&mut bytes[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record")),
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(Some(PackedPosition(bytes)))
+    }
+}
+
+impl<R: Read> Iterator for PackedReader<R> {
+    type Item = io::Result<PackedPosition>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
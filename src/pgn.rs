@@ -0,0 +1,379 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::{Board, FenError, Move, Side};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// Parses a PGN result token. `*` (unfinished) gives `None`.
+    pub fn from_pgn(s: &str) -> Option<Self> {
+        match s {
+            "1-0" => Some(Self::WhiteWins),
+            "0-1" => Some(Self::BlackWins),
+            "1/2-1/2" => Some(Self::Draw),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+        })
+    }
+}
+
+/// A game's tags and main line. Comments, NAGs and variations are dropped
+/// when reading; `annotations` are only written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    /// Moves in SAN as written in the file.
+    pub moves: Vec<String>,
+    /// Notes on the move with the same index. May be shorter than `moves`.
+    /// Missing in older records. Always written, as bincode has no way to skip it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub annotations: Vec<Annotation>,
+    pub result: Option<GameResult>,
+}
+
+/// What is written after a move: NAGs, a comment and alternative lines.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation {
+    /// Numeric annotation glyphs. 1 to 6 are written as `!`, `?`, `!!`, `??`,
+    /// `!?` and `?!` after the move, others as `$n`.
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    /// Lines in SAN played instead of the move, from the position before it.
+    pub variations: Vec<Vec<String>>,
+}
+
+/// Standard NAG numbers.
+pub const NAG_GOOD: u8 = 1;
+pub const NAG_MISTAKE: u8 = 2;
+pub const NAG_BRILLIANT: u8 = 3;
+pub const NAG_BLUNDER: u8 = 4;
+pub const NAG_SPECULATIVE: u8 = 5;
+pub const NAG_DUBIOUS: u8 = 6;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Fen(FenError),
+    /// The move at this index (0-based ply) is not legal or not valid SAN.
+    IllegalMove(usize, String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fen(err) => write!(f, "invalid FEN tag: {err}"),
+            Self::IllegalMove(ply, san) => write!(f, "illegal move {san} at ply {}", ply + 1),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Game {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Rating from the `WhiteElo` or `BlackElo` tag.
+    pub fn elo(&self, side: Side) -> Option<u32> {
+        let tag = match side {
+            Side::WHITE => "WhiteElo",
+            Side::BLACK => "BlackElo",
+        };
+        self.tag(tag)?.parse().ok()
+    }
+
+    /// The position the game starts from, honouring the `FEN` tag.
+    pub fn start(&self) -> Result<Board, FenError> {
+        match self.tag("FEN") {
+            Some(fen) => Board::from_fen(fen),
+            None => Ok(Board::new()),
+        }
+    }
+
+    /// Plays the game out, calling `visit` with the position before each move.
+    pub fn replay(&self, mut visit: impl FnMut(&Board, &Move)) -> Result<Board, ReplayError> {
+        let mut board = self.start().map_err(ReplayError::Fen)?;
+        for (ply, san) in self.moves.iter().enumerate() {
+            let mv = board
+                .parse_san(san)
+                .ok_or_else(|| ReplayError::IllegalMove(ply, san.clone()))?;
+            visit(&board, &mv);
+            board.make_move(&mv);
+        }
+        Ok(board)
+    }
+}
+
+/// Writes the game as PGN: the tags, then the moves wrapped at 80 columns and
+/// the result (`*` if unknown), followed by a blank line.
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{name} \"{}\"]", value.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+
+        let start = self.start().ok();
+        let black_first = start.as_ref().is_some_and(|board| board.side_to_move() == Side::BLACK);
+        let first_number = start.as_ref().map_or(1, |board| board.fullmove_number() as usize);
+        let mut tokens = Vec::new();
+        // Black's moves get a number after anything that interrupts the line.
+        let mut interrupted = true;
+        for (index, san) in self.moves.iter().enumerate() {
+            let ply = index + black_first as usize;
+            let annotation = self.annotations.get(index);
+            let mut san = san.clone();
+            for &nag in annotation.map_or(&[][..], |annotation| &annotation.nags) {
+                match nag_symbol(nag) {
+                    Some(symbol) => san.push_str(symbol),
+                    None => san.push_str(&format!(" ${nag}")),
+                }
+            }
+            tokens.push(numbered(first_number, ply, &san, interrupted));
+            interrupted = false;
+            let Some(annotation) = annotation else { continue };
+            if let Some(comment) = &annotation.comment {
+                // A comment cannot contain its closing brace.
+                tokens.extend(comment_words(&format!("{{{}}}", comment.replace('}', ""))));
+                interrupted = true;
+            }
+            for variation in annotation.variations.iter().filter(|variation| !variation.is_empty()) {
+                let mut words: Vec<String> = variation
+                    .iter()
+                    .enumerate()
+                    .map(|(i, san)| numbered(first_number, ply + i, san, i == 0))
+                    .collect();
+                words[0].insert(0, '(');
+                words.last_mut().expect("not empty").push(')');
+                tokens.extend(words);
+                interrupted = true;
+            }
+        }
+        tokens.push(self.result.map_or("*".to_string(), |result| result.to_string()));
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+                writeln!(f, "{line}")?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{line}")?;
+        writeln!(f)
+    }
+}
+
+/// `san` with its move number before white's moves, and before black's if
+/// `black_number` is set.
+fn numbered(first_number: usize, ply: usize, san: &str, black_number: bool) -> String {
+    let number = first_number + ply / 2;
+    if ply.is_multiple_of(2) {
+        format!("{number}. {san}")
+    } else if black_number {
+        format!("{number}... {san}")
+    } else {
+        san.to_string()
+    }
+}
+
+/// Splits a comment into words for wrapping, keeping commands like
+/// `[%eval 0.25]` on one line.
+fn comment_words(comment: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut in_command = false;
+    for word in comment.split(' ') {
+        match words.last_mut() {
+            Some(last) if in_command => {
+                last.push(' ');
+                last.push_str(word);
+            }
+            _ => words.push(word.to_string()),
+        }
+        if word.contains('[') {
+            in_command = true;
+        }
+        if word.contains(']') {
+            in_command = false;
+        }
+    }
+    words
+}
+
+fn nag_symbol(nag: u8) -> Option<&'static str> {
+    match nag {
+        NAG_GOOD => Some("!"),
+        NAG_MISTAKE => Some("?"),
+        NAG_BRILLIANT => Some("!!"),
+        NAG_BLUNDER => Some("??"),
+        NAG_SPECULATIVE => Some("!?"),
+        NAG_DUBIOUS => Some("?!"),
+        _ => None,
+    }
+}
+
+/// Reads games one after another from PGN text.
+pub struct PgnReader<R> {
+    reader: R,
+    line: String,
+    /// A tag line read while finishing the previous game.
+    pending: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            pending: None,
+        }
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        if let Some(line) = self.pending.take() {
+            return Ok(Some(line));
+        }
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.line.trim_end().to_string()))
+    }
+
+    fn read_game(&mut self) -> io::Result<Option<Game>> {
+        let mut game = Game::default();
+        let mut movetext = String::new();
+        let mut started = false;
+        while let Some(line) = self.next_line()? {
+            let trimmed = line.trim();
+            if trimmed.starts_with('%') {
+                continue;
+            }
+            if trimmed.starts_with('[') && !in_comment(&movetext) {
+                if !movetext.trim().is_empty() {
+                    self.pending = Some(line);
+                    break;
+                }
+                if let Some(tag) = parse_tag(trimmed) {
+                    game.tags.push(tag);
+                }
+                started = true;
+            } else if !trimmed.is_empty() {
+                movetext.push_str(&line);
+                movetext.push('\n');
+                started = true;
+            }
+        }
+        if !started {
+            return Ok(None);
+        }
+        parse_movetext(&movetext, &mut game);
+        Ok(Some(game))
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = io::Result<Game>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
+
+/// Whether `text` ends inside an unclosed `{` comment.
+fn in_comment(text: &str) -> bool {
+    text.matches('{').count() > text.matches('}').count()
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.trim_end().strip_suffix(']')?;
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+/// Adds a finished movetext token to `game`, skipping NAGs, move numbers
+/// and anything inside a variation.
+fn flush(token: &mut String, depth: usize, game: &mut Game) {
+    if token.is_empty() {
+        return;
+    }
+    let word = std::mem::take(token);
+    if depth > 0 || word.starts_with('$') {
+        return;
+    }
+    if let Some(result) = GameResult::from_pgn(&word) {
+        game.result = Some(result);
+        return;
+    }
+    // Move numbers may be glued to the move, as in `1.e4`, but `0-0` is castling.
+    let san = match word.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(i) if word[..i].contains('.') => &word[i..],
+        Some(_) => &word,
+        None => return,
+    };
+    if san != "*" {
+        game.moves.push(san.to_string());
+    }
+}
+
+fn parse_movetext(text: &str, game: &mut Game) {
+    let mut chars = text.chars();
+    let mut depth = 0;
+    let mut token = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                flush(&mut token, depth, game);
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                flush(&mut token, depth, game);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                flush(&mut token, depth, game);
+                depth += 1;
+            }
+            ')' => {
+                flush(&mut token, depth, game);
+                depth = depth.saturating_sub(1);
+            }
+            c if c.is_whitespace() => flush(&mut token, depth, game),
+            c => token.push(c),
+        }
+    }
+    flush(&mut token, depth, game);
+}
@@ -0,0 +1,241 @@
+//! Puzzles mined from games.
+//!
+//! Every position of a game is searched with two lines. A puzzle starts where
+//! the side to move has exactly one winning move: the only move that mates,
+//! or the only one that keeps a winning score while the other lines fall
+//! short of it. The solution then follows the opponent's best replies and
+//! must stay unique at each of the solver's moves until the mate is on the
+//! board or the material is won.
+
+pub mod themes;
+
+use std::fmt;
+
+use crate::pgn::{Game, ReplayError};
+use crate::search::{mate_in, Limits, SearchInfo, SearchResult, Searcher};
+use crate::{Board, Move, Moves, Outcome, Side};
+use themes::Theme;
+
+#[derive(Clone, Debug)]
+pub struct PuzzleOptions {
+    /// Limits for every search, normally a depth.
+    pub limits: Limits,
+    /// Least score, in centipawns, that counts as winning.
+    pub win_score: i32,
+    /// Least material, in pawns, a material puzzle has to win.
+    pub min_gain: i32,
+    /// Most moves of the solver in a solution.
+    pub max_moves: usize,
+}
+
+impl Default for PuzzleOptions {
+    fn default() -> Self {
+        Self {
+            limits: Limits {
+                depth: Some(10),
+                ..Limits::default()
+            },
+            win_score: 200,
+            min_gain: 2,
+            max_moves: 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    /// Mate in this many moves.
+    Mate(u32),
+    /// Material won, in pawns.
+    Material(i32),
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goal::Mate(moves) => write!(f, "mateIn{moves}"),
+            Goal::Material(_) => write!(f, "material"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Puzzle {
+    pub board: Board,
+    /// The solver's moves and the replies in between, starting and ending
+    /// with a move of the side to move.
+    pub solution: Vec<Move>,
+    pub goal: Goal,
+    pub themes: Vec<Theme>,
+    /// A rough difficulty on the usual Elo scale; see `estimate_rating`.
+    pub rating: u32,
+}
+
+/// The header of the puzzle file `Puzzle`'s `Display` writes lines for.
+pub const CSV_HEADER: &str = "FEN,Moves,Rating,Themes";
+
+/// One CSV line: the FEN, the solution in UCI notation, the rating, and the
+/// goal followed by the themes.
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let moves: Vec<String> = self.solution.iter().map(|mv| mv.to_uci(false)).collect();
+        let themes: Vec<String> = std::iter::once(self.goal.to_string())
+            .chain(self.themes.iter().map(Theme::to_string))
+            .collect();
+        write!(f, "{},{},{},{}", self.board.to_fen(), moves.join(" "), self.rating, themes.join(" "))
+    }
+}
+
+/// Finds the puzzles in `game`, in game order. Positions inside a puzzle's
+/// solution are not tried again.
+pub fn find_puzzles(
+    game: &Game,
+    searcher: &mut Searcher,
+    options: &PuzzleOptions,
+) -> Result<Vec<Puzzle>, ReplayError> {
+    searcher.clear();
+    let mut positions = vec![];
+    let last = game.replay(|board, _| positions.push(board.clone()))?;
+    positions.push(last);
+
+    let mut puzzles = Vec::new();
+    let mut index = 1;
+    while index < positions.len() {
+        let board = &positions[index];
+        // Material is counted from before the opponent's move, so taking back
+        // a piece it has just taken does not count as winning one.
+        let baseline = material(&positions[index - 1], board.side_to_move());
+        match find_puzzle(board, baseline, searcher, options) {
+            Some(puzzle) => {
+                index += puzzle.solution.len();
+                puzzles.push(puzzle);
+            }
+            None => index += 1,
+        }
+    }
+    Ok(puzzles)
+}
+
+/// The puzzle starting at `board`, if there is one. `baseline` is the side to
+/// move's material balance any gain is counted from.
+pub fn find_puzzle(
+    board: &Board,
+    baseline: i32,
+    searcher: &mut Searcher,
+    options: &PuzzleOptions,
+) -> Option<Puzzle> {
+    if board.outcome().is_some() || legal_moves(board) < 2 {
+        return None;
+    }
+    let limits = Limits {
+        multipv: 2,
+        ..options.limits.clone()
+    };
+    let solver = board.side_to_move();
+    let mut position = board.clone();
+    let mut solution = Vec::new();
+    let mut first_depth = 0;
+    let mut goal = None;
+    for step in 0..options.max_moves {
+        // The depth from which the search kept choosing its final move.
+        let mut settled = (0, Move::default());
+        let result = searcher.search(&position, &limits, |info: &SearchInfo| {
+            if let Some(&mv) = info.pv.first()
+                && info.multipv == 1
+                && mv != settled.1
+            {
+                settled = (info.depth, mv);
+            }
+        });
+        if step == 0 {
+            first_depth = settled.0;
+        }
+        let (best, second) = (result.lines.first()?, result.lines.get(1));
+        let mates = |score: i32| mate_in(score).is_some_and(|moves| moves > 0);
+        let mating = *goal.get_or_insert(mates(best.score));
+        // Without a second line there is only one legal move, which needs no
+        // finding after the first step. Once material is won the score may
+        // drop below winning, so later moves only have to stand out.
+        let unique = if mating {
+            mates(best.score) && !second.is_some_and(|line| mates(line.score))
+        } else {
+            (step > 0 || best.score >= options.win_score)
+                && !second.is_some_and(|line| {
+                    line.score >= options.win_score || best.score - line.score < options.win_score
+                })
+        };
+        if !unique || (step == 0 && mating && mate_in(best.score)? as usize > options.max_moves) {
+            return None;
+        }
+
+        position.make_move(&result.best_move);
+        solution.push(result.best_move);
+        match position.outcome() {
+            Some(Outcome::Checkmate(_)) => {
+                let goal = Goal::Mate(solution.len().div_ceil(2) as u32);
+                return Some(make_puzzle(board, solution, goal, first_depth));
+            }
+            Some(_) => return None,
+            None => {}
+        }
+        let reply = reply(&position, &result, searcher, &options.limits)?;
+        position.make_move(&reply);
+        // The gain counts once the opponent's best reply, often a recapture,
+        // has been played, but the solution ends with the solver's move.
+        let gain = material(&position, solver) - baseline;
+        if !mating && gain >= options.min_gain {
+            return Some(make_puzzle(board, solution, Goal::Material(gain), first_depth));
+        }
+        solution.push(reply);
+    }
+    None
+}
+
+/// The opponent's best reply after the solver's move: the second move of the
+/// solver's line, or a search of its own if the line stops short.
+fn reply(board: &Board, result: &SearchResult, searcher: &mut Searcher, limits: &Limits) -> Option<Move> {
+    if let Some(&reply) = result.lines.first().and_then(|line| line.pv.get(1)) {
+        return Some(reply);
+    }
+    let reply = searcher.search(board, limits, |_| {}).best_move;
+    (!reply.is_null()).then_some(reply)
+}
+
+fn make_puzzle(board: &Board, solution: Vec<Move>, goal: Goal, settled_depth: u32) -> Puzzle {
+    Puzzle {
+        themes: themes::detect(board, &solution),
+        rating: estimate_rating(board, &solution, settled_depth),
+        board: board.clone(),
+        solution,
+        goal,
+    }
+}
+
+/// A rough rating: each further move of the solver adds 250, a first move
+/// that is not a check 100 and one that is also not a capture or promotion
+/// 300 more, and each ply of depth the search needed to settle on it 50.
+pub fn estimate_rating(board: &Board, solution: &[Move], settled_depth: u32) -> u32 {
+    let first = &solution[0];
+    let check = board.gives_check(first);
+    let quiet = !check && !first.is_capture() && !first.is_promotion();
+    let solver_moves = solution.len().div_ceil(2) as u32;
+    let rating = 700 + 250 * (solver_moves - 1) + 100 * !check as u32 + 300 * quiet as u32 + 50 * settled_depth;
+    rating.clamp(600, 3000)
+}
+
+/// `side`'s material minus its opponent's, in pawns.
+fn material(board: &Board, side: Side) -> i32 {
+    let count = |side: Side| {
+        (board.pawns[side].count_ones()
+            + 3 * (board.knights[side] | board.bishops[side]).count_ones()
+            + 5 * board.rooks[side].count_ones()
+            + 9 * board.queens[side].count_ones()) as i32
+    };
+    count(side) - count(!side)
+}
+
+fn legal_moves(board: &Board) -> usize {
+    let mut moves = Moves::new();
+    board.generate_moves(&mut moves, board.side_to_move());
+    moves.len()
+}
@@ -0,0 +1,229 @@
+//! Tactical themes, found from attack tables in the positions around each of
+//! the solver's moves.
+
+use std::fmt;
+
+use crate::utils::lsb1;
+use crate::{
+    Board, Move, Outcome, Side, BISHOP_PIECE_TYPE, KING_PIECE_TYPE, KNIGHT_PIECE_TYPE, PAWN_PIECE_TYPE,
+    QUEEN_PIECE_TYPE, ROOK_PIECE_TYPE,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Theme {
+    /// A move attacks two pieces worth more than the attacker, or undefended.
+    Fork,
+    /// A move pins a piece to a more valuable one, or attacks a piece pinned
+    /// to its king.
+    Pin,
+    /// A move attacks a valuable piece that has to move away from a piece
+    /// behind it.
+    Skewer,
+    /// A piece moving out of the way uncovers an attack by another.
+    DiscoveredAttack,
+    /// Mate by a rook or queen on the king's first rank, its escape squares
+    /// blocked by its own pieces.
+    BackRankMate,
+    Promotion,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 6] = [
+        Theme::Fork,
+        Theme::Pin,
+        Theme::Skewer,
+        Theme::DiscoveredAttack,
+        Theme::BackRankMate,
+        Theme::Promotion,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Fork => "fork",
+            Theme::Pin => "pin",
+            Theme::Skewer => "skewer",
+            Theme::DiscoveredAttack => "discoveredAttack",
+            Theme::BackRankMate => "backRankMate",
+            Theme::Promotion => "promotion",
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The themes of `solution` played from `start`, in `Theme::ALL` order. The
+/// solver's moves are the even-numbered ones.
+pub fn detect(start: &Board, solution: &[Move]) -> Vec<Theme> {
+    let mut found = Vec::new();
+    let mut board = start.clone();
+    for (ply, mv) in solution.iter().enumerate() {
+        let before = board.clone();
+        board.make_move(mv);
+        if ply % 2 == 1 {
+            continue;
+        }
+        let side = before.side_to_move();
+        if is_fork(&board, mv, side) {
+            found.push(Theme::Fork);
+        }
+        if is_pin(&board, mv, side) {
+            found.push(Theme::Pin);
+        }
+        if is_skewer(&board, mv, side) {
+            found.push(Theme::Skewer);
+        }
+        if is_discovered_attack(&before, &board, mv, side) {
+            found.push(Theme::DiscoveredAttack);
+        }
+        if mv.is_promotion() {
+            found.push(Theme::Promotion);
+        }
+        if ply + 1 == solution.len() && is_back_rank_mate(&board, side) {
+            found.push(Theme::BackRankMate);
+        }
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// Piece values in pawns, with the king worth more than everything else.
+fn value(piece: u8) -> u32 {
+    match piece {
+        PAWN_PIECE_TYPE => 1,
+        KNIGHT_PIECE_TYPE | BISHOP_PIECE_TYPE => 3,
+        ROOK_PIECE_TYPE => 5,
+        QUEEN_PIECE_TYPE => 9,
+        KING_PIECE_TYPE => 100,
+        _ => 0,
+    }
+}
+
+/// Squares a `side` piece of type `piece` on `square` attacks.
+fn attacks_from(board: &Board, square: usize, piece: u8, side: Side, occupied: u64) -> u64 {
+    match piece {
+        PAWN_PIECE_TYPE => board.pawn_attack_table[side][square],
+        KNIGHT_PIECE_TYPE => board.knight_attack_table[square],
+        BISHOP_PIECE_TYPE => board.bishop_table.lookup(square, occupied),
+        ROOK_PIECE_TYPE => board.rook_table.lookup(square, occupied),
+        QUEEN_PIECE_TYPE => board.bishop_table.lookup(square, occupied) | board.rook_table.lookup(square, occupied),
+        KING_PIECE_TYPE => board.king_attack_table[square],
+        _ => 0,
+    }
+}
+
+fn squares(mut bitboard: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (bitboard != 0).then(|| {
+            let square = lsb1(bitboard);
+            bitboard &= bitboard - 1;
+            square
+        })
+    })
+}
+
+fn is_defended(board: &Board, square: usize, side: Side) -> bool {
+    board.attackers_to(square, side, board.occupied) != 0
+}
+
+fn is_fork(after: &Board, mv: &Move, side: Side) -> bool {
+    let to = mv.destination();
+    let piece = after.piece_at(to);
+    // A fork by a piece that is simply taken back wins nothing.
+    if !is_defended(after, to, side) && is_defended(after, to, !side) {
+        return false;
+    }
+    let targets = attacks_from(after, to, piece, side, after.occupied) & after.pieces[!side];
+    let worthwhile = squares(targets)
+        .filter(|&target| {
+            let target_piece = after.piece_at(target);
+            target_piece != PAWN_PIECE_TYPE
+                && (value(target_piece) > value(piece) || !is_defended(after, target, !side))
+        })
+        .count();
+    worthwhile >= 2
+}
+
+/// Opponent pieces on a line from the slider on `from`, each paired with the
+/// opponent piece right behind it.
+fn lined_up(board: &Board, from: usize, side: Side) -> Vec<(usize, usize)> {
+    let piece = board.piece_at(from);
+    if !matches!(piece, BISHOP_PIECE_TYPE | ROOK_PIECE_TYPE | QUEEN_PIECE_TYPE) {
+        return Vec::new();
+    }
+    let attacks = attacks_from(board, from, piece, side, board.occupied);
+    let mut pairs = Vec::new();
+    for front in squares(attacks & board.pieces[!side]) {
+        let xray = attacks_from(board, from, piece, side, board.occupied & !(1 << front));
+        for back in squares(xray & !attacks & board.pieces[!side]) {
+            if board.between(from, back) & (1 << front) != 0 {
+                pairs.push((front, back));
+            }
+        }
+    }
+    pairs
+}
+
+fn is_pin(after: &Board, mv: &Move, side: Side) -> bool {
+    let to = mv.destination();
+    let attacker = value(after.piece_at(to));
+    let creates_pin = lined_up(after, to, side).into_iter().any(|(front, back)| {
+        let (front, back) = (value(after.piece_at(front)), value(after.piece_at(back)));
+        back > front && back > attacker
+    });
+    // Or the move piles up on a piece that cannot leave its king's line.
+    let rays = after.pin_rays(!side);
+    let attacks = attacks_from(after, to, after.piece_at(to), side, after.occupied);
+    creates_pin || squares(attacks & after.pieces[!side]).any(|square| rays[square] != !0)
+}
+
+fn is_skewer(after: &Board, mv: &Move, side: Side) -> bool {
+    let to = mv.destination();
+    let attacker = value(after.piece_at(to));
+    lined_up(after, to, side).into_iter().any(|(front, back)| {
+        let (front_piece, back_piece) = (after.piece_at(front), after.piece_at(back));
+        value(front_piece) > value(back_piece)
+            && value(front_piece) > attacker
+            && (value(back_piece) >= 3 || !is_defended(after, back, !side))
+    })
+}
+
+fn is_discovered_attack(before: &Board, after: &Board, mv: &Move, side: Side) -> bool {
+    let from = mv.from_square();
+    if mv.is_drop() {
+        return false;
+    }
+    let sliders = (after.bishops[side] | after.rooks[side] | after.queens[side]) & !(1 << mv.destination());
+    squares(sliders).any(|slider| {
+        let piece = after.piece_at(slider);
+        let uncovered = attacks_from(after, slider, piece, side, after.occupied)
+            & !attacks_from(before, slider, piece, side, before.occupied)
+            & after.pieces[!side];
+        squares(uncovered).any(|target| {
+            let target_piece = after.piece_at(target);
+            after.between(slider, target) & (1 << from) != 0
+                && (value(target_piece) > value(piece) || !is_defended(after, target, !side))
+        })
+    })
+}
+
+fn is_back_rank_mate(after: &Board, side: Side) -> bool {
+    if !matches!(after.outcome(), Some(Outcome::Checkmate(winner)) if winner == side) {
+        return false;
+    }
+    let king = lsb1(after.kings[!side]);
+    let back_rank = if side == Side::WHITE { 7 } else { 0 };
+    if king / 8 != back_rank {
+        return false;
+    }
+    let rank = 0xFFu64 << (8 * back_rank);
+    let checkers = after.attackers_to(king, side, after.occupied);
+    let on_rank = checkers & rank & (after.rooks[side] | after.queens[side]);
+    let forward = if side == Side::WHITE { rank >> 8 } else { rank << 8 };
+    let escapes = after.king_attack_table[king] & forward;
+    on_rank != 0 && escapes & !after.pieces[!side] == 0
+}
@@ -0,0 +1,158 @@
+use crate::fen::{parse_square, square_name};
+use crate::{
+    Board, Move, Moves, BISHOP_PIECE_TYPE, KING_PIECE_TYPE, KNIGHT_PIECE_TYPE, PAWN_PIECE_TYPE,
+    QUEEN_PIECE_TYPE, ROOK_PIECE_TYPE,
+};
+
+fn piece_letter(piece: u8) -> Option<char> {
+    match piece {
+        KNIGHT_PIECE_TYPE => Some('N'),
+        BISHOP_PIECE_TYPE => Some('B'),
+        ROOK_PIECE_TYPE => Some('R'),
+        QUEEN_PIECE_TYPE => Some('Q'),
+        KING_PIECE_TYPE => Some('K'),
+        _ => None,
+    }
+}
+
+fn piece_from_letter(c: char) -> Option<u8> {
+    match c {
+        'N' => Some(KNIGHT_PIECE_TYPE),
+        'B' => Some(BISHOP_PIECE_TYPE),
+        'R' => Some(ROOK_PIECE_TYPE),
+        'Q' => Some(QUEEN_PIECE_TYPE),
+        'K' => Some(KING_PIECE_TYPE),
+        _ => None,
+    }
+}
+
+impl Board {
+    /// Finds the legal move written in standard algebraic notation, e.g. `Nbd7`,
+    /// `exd6`, `e8=Q+`, `O-O` or the drop `N@f3`. Check and annotation suffixes are ignored.
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let mut moves = Moves::new();
+        self.generate_moves(&mut moves, self.side_to_move);
+
+        let castle = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castle {
+            return moves
+                .into_iter()
+                .find(|mv| mv.is_castling() && (mv.to_square() > mv.from_square()) == kingside);
+        }
+        if let Some((letter, square)) = san.split_once('@') {
+            let piece = match letter {
+                "" | "P" => PAWN_PIECE_TYPE,
+                letter => piece_from_letter(letter.parse().ok()?)?,
+            };
+            let to = parse_square(square)?;
+            return moves.into_iter().find(|mv| mv.is_drop() && mv.moving_piece() == piece && mv.to_square() == to);
+        }
+
+        let mut chars: Vec<char> = san.chars().filter(|&c| c != 'x' && c != '=').collect();
+        let piece = match chars.first().copied().and_then(piece_from_letter) {
+            Some(piece) => {
+                chars.remove(0);
+                piece
+            }
+            None => PAWN_PIECE_TYPE,
+        };
+        let promotion = match chars.last() {
+            Some(&c) if piece == PAWN_PIECE_TYPE && !c.is_ascii_digit() => {
+                chars.pop();
+                Some(piece_from_letter(c.to_ascii_uppercase())?)
+            }
+            _ => None,
+        };
+        if chars.len() < 2 {
+            return None;
+        }
+        let target: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = parse_square(&target)?;
+        // Whatever is left disambiguates the origin.
+        let file = chars.iter().find(|c| ('a'..='h').contains(c)).map(|&c| c as usize - 'a' as usize);
+        let rank = chars.iter().find(|c| ('1'..='8').contains(c)).map(|&c| c as usize - '1' as usize);
+
+        let mut candidates = moves.into_iter().filter(|mv| {
+            mv.moving_piece() == piece
+                && mv.to_square() == to
+                && !mv.is_castling()
+                && !mv.is_drop()
+                && file.is_none_or(|file| mv.from_square() % 8 == file)
+                && rank.is_none_or(|rank| mv.from_square() / 8 == rank)
+                && match promotion {
+                    Some(promo) => mv.is_promotion() && mv.promoted_to() == promo,
+                    None => !mv.is_promotion(),
+                }
+        });
+        let mv = candidates.next()?;
+        // Ambiguous SAN is invalid.
+        candidates.next().is_none().then_some(mv)
+    }
+
+    /// Standard algebraic notation for the legal move `mv`, with `+` or `#`.
+    pub fn san(&self, mv: &Move) -> String {
+        let mut san = String::new();
+        let (from, to) = (mv.from_square(), mv.to_square());
+        if mv.is_castling() {
+            san.push_str(if to > from { "O-O" } else { "O-O-O" });
+        } else if mv.is_drop() {
+            // Pawn drops go without a letter, as pawn moves do.
+            san.extend(piece_letter(mv.moving_piece()));
+            san.push('@');
+            san.push_str(&square_name(to));
+        } else if let Some(letter) = piece_letter(mv.moving_piece()) {
+            san.push(letter);
+            let mut moves = Moves::new();
+            self.generate_moves(&mut moves, self.side_to_move);
+            let rivals: Vec<Move> = moves
+                .into_iter()
+                .filter(|other| {
+                    other.moving_piece() == mv.moving_piece()
+                        && !other.is_drop()
+                        && other.to_square() == to
+                        && other.from_square() != from
+                })
+                .collect();
+            if !rivals.is_empty() {
+                let name = square_name(from);
+                let same_file = rivals.iter().any(|other| other.from_square() % 8 == from % 8);
+                let same_rank = rivals.iter().any(|other| other.from_square() / 8 == from / 8);
+                if !same_file {
+                    san.push_str(&name[..1]);
+                } else if !same_rank {
+                    san.push_str(&name[1..]);
+                } else {
+                    san.push_str(&name);
+                }
+            }
+            if mv.is_capture() {
+                san.push('x');
+            }
+            san.push_str(&square_name(to));
+        } else {
+            if mv.is_capture() {
+                san.push_str(&square_name(from)[..1]);
+                san.push('x');
+            }
+            san.push_str(&square_name(to));
+            if mv.is_promotion() {
+                san.push('=');
+                san.extend(piece_letter(mv.promoted_to()));
+            }
+        }
+
+        if self.gives_check(mv) {
+            let mut after = self.clone();
+            after.make_move(mv);
+            let mut replies = Moves::new();
+            after.generate_moves(&mut replies, after.side_to_move);
+            san.push(if replies.is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+}
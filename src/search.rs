@@ -0,0 +1,753 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::endgame::{kpk, Dtm, Endgames};
+use crate::eval::PawnTable;
+pub use crate::tt::Bound;
+use crate::tt::{TranspositionTable, TtEntry};
+use crate::{Board, GenType, Move, Moves, Variant, MAX_MOVES, PROMO_QUEEN};
+
+pub const INFINITY: i32 = 32_000;
+pub const MATE: i32 = 31_000;
+pub const MAX_PLY: usize = 128;
+/// Scores beyond this are mates found within the search horizon.
+const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+/// Nodes a thread searches between publishing its count and checking limits.
+const NODE_FLUSH: u64 = 1024;
+
+/// When to stop searching. With no limits set the search runs until stopped.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    /// Time left on the clock of the side to move.
+    pub time: Option<Duration>,
+    pub increment: Duration,
+    pub moves_to_go: Option<u32>,
+    pub infinite: bool,
+    /// Number of best lines to find; 0 is treated as 1.
+    pub multipv: usize,
+    /// Only consider these root moves. Empty means all legal moves.
+    pub searchmoves: Vec<Move>,
+}
+
+impl Limits {
+    /// Time to aim for and time never to exceed.
+    fn deadlines(&self) -> (Option<Duration>, Option<Duration>) {
+        if self.infinite {
+            return (None, None);
+        }
+        if let Some(movetime) = self.movetime {
+            return (Some(movetime), Some(movetime));
+        }
+        let Some(time) = self.time else {
+            return (None, None);
+        };
+        let overhead = Duration::from_millis(30);
+        let time = time.saturating_sub(overhead);
+        let moves = self.moves_to_go.unwrap_or(30).clamp(1, 30);
+        let optimum = (time / moves + self.increment * 3 / 4).min(time / 2);
+        let maximum = (optimum * 4).min(time * 3 / 4);
+        (Some(optimum), Some(maximum))
+    }
+}
+
+/// Progress report for one line of an iteration.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub seldepth: u32,
+    /// 1-based rank of the line among the MultiPV lines.
+    pub multipv: usize,
+    /// Centipawns from the side to move's point of view; see `mate_in`.
+    pub score: i32,
+    /// Whether `score` is exact or the search failed outside its window.
+    pub bound: Bound,
+    pub nodes: u64,
+    pub time: Duration,
+    pub pv: Vec<Move>,
+    /// Permille of the transposition table in use.
+    pub hashfull: usize,
+}
+
+impl SearchInfo {
+    pub fn nps(&self) -> u64 {
+        (self.nodes as u128 * 1000 / self.time.as_millis().max(1)) as u64
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    /// Null if the side to move has no legal moves.
+    pub best_move: Move,
+    pub ponder: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    /// Lines of the last completed iteration, best first.
+    pub lines: Vec<SearchInfo>,
+}
+
+/// Moves until mate for mate scores, negative when the side to move is mated.
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score >= MATE_BOUND {
+        Some((MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        Some(-(MATE + score) / 2)
+    } else {
+        None
+    }
+}
+
+/// Lazy SMP searcher. All threads search the same position and share the
+/// transposition table; everything else is per thread.
+pub struct Searcher {
+    tt: TranspositionTable,
+    threads: usize,
+    stop: Arc<AtomicBool>,
+    endgames: Option<Arc<Endgames>>,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new(1, 16)
+    }
+}
+
+impl Searcher {
+    pub fn new(threads: usize, hash_mb: usize) -> Self {
+        // Built here rather than in the middle of the first search to reach a KPK ending.
+        kpk::init();
+        Self {
+            tt: TranspositionTable::new(hash_mb),
+            threads: threads.max(1),
+            stop: Arc::new(AtomicBool::new(false)),
+            endgames: None,
+        }
+    }
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+    /// Replaces the transposition table, discarding its contents.
+    pub fn set_hash(&mut self, mb: usize) {
+        self.tt = TranspositionTable::new(mb);
+    }
+    /// Distance-to-mate tables to score positions they cover exactly.
+    pub fn set_endgames(&mut self, endgames: Option<Arc<Endgames>>) {
+        self.endgames = endgames;
+    }
+    /// Forgets everything learned from previous searches.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+    }
+    /// Setting the returned flag makes a running search return as soon as possible.
+    /// It is cleared again when the search returns.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Searches `board` until `limits` are reached or the search is stopped,
+    /// calling `on_info` after every iteration of the main thread.
+    pub fn search(
+        &mut self,
+        board: &Board,
+        limits: &Limits,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let (optimum, maximum) = limits.deadlines();
+        let (root_moves, restricted) = root_moves(board, &limits.searchmoves);
+        let shared = Shared {
+            tt: &self.tt,
+            stop: &self.stop,
+            nodes: AtomicU64::new(0),
+            start: Instant::now(),
+            maximum,
+            node_limit: limits.nodes,
+            root_moves,
+            restricted,
+            multipv: limits.multipv,
+            endgames: self.endgames.as_deref(),
+        };
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32 - 1).clamp(1, MAX_PLY as u32 - 1);
+
+        let result = std::thread::scope(|scope| {
+            for id in 1..self.threads {
+                let shared = &shared;
+                let board = board.clone();
+                scope.spawn(move || {
+                    let mut worker = Worker::new(id, board, shared);
+                    worker.iterate(max_depth, None, |_| {});
+                });
+            }
+            let mut main = Worker::new(0, board.clone(), &shared);
+            let result = main.iterate(max_depth, optimum, &mut on_info);
+            // Helpers run until the main thread is done.
+            self.stop.store(true, Ordering::Relaxed);
+            result
+        });
+        self.stop.store(false, Ordering::Relaxed);
+        result
+    }
+}
+
+/// State every search thread sees.
+struct Shared<'a> {
+    tt: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    nodes: AtomicU64,
+    start: Instant,
+    maximum: Option<Duration>,
+    node_limit: Option<u64>,
+    /// Legal root moves allowed by `searchmoves`.
+    root_moves: Moves,
+    /// Whether `root_moves` leaves out some legal moves.
+    restricted: bool,
+    multipv: usize,
+    endgames: Option<&'a Endgames>,
+}
+
+/// Legal moves that are in `searchmoves`, or all of them if none are.
+fn root_moves(board: &Board, searchmoves: &[Move]) -> (Moves, bool) {
+    let mut moves = Moves::new();
+    board.generate_moves(&mut moves, board.side_to_move());
+    let legal = moves.len();
+    if moves.iter().any(|mv| searchmoves.contains(mv)) {
+        moves.retain(|mv| searchmoves.contains(mv));
+    }
+    let restricted = moves.len() < legal;
+    (moves, restricted)
+}
+
+struct Worker<'a> {
+    id: usize,
+    board: Board,
+    shared: &'a Shared<'a>,
+    killers: [[Move; 2]; MAX_PLY],
+    /// Indexed by side, from square and to square.
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    pawn_table: PawnTable,
+    pv: Box<[[Move; MAX_PLY]; MAX_PLY]>,
+    pv_len: [usize; MAX_PLY],
+    /// Nodes not yet added to `shared.nodes`.
+    nodes: u64,
+    seldepth: usize,
+    stopped: bool,
+    /// Root moves of the MultiPV lines already found at this depth.
+    excluded: Moves,
+}
+
+impl<'a> Worker<'a> {
+    fn new(id: usize, board: Board, shared: &'a Shared<'a>) -> Self {
+        Self {
+            id,
+            board,
+            shared,
+            killers: [[Move::new(); 2]; MAX_PLY],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            pawn_table: PawnTable::default(),
+            pv: Box::new([[Move::new(); MAX_PLY]; MAX_PLY]),
+            pv_len: [0; MAX_PLY],
+            nodes: 0,
+            seldepth: 0,
+            stopped: false,
+            excluded: Moves::new(),
+        }
+    }
+
+    /// Iterative deepening with aspiration windows. With MultiPV each depth is
+    /// searched once per line, excluding the root moves of the lines before it.
+    fn iterate(
+        &mut self,
+        max_depth: u32,
+        optimum: Option<Duration>,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let root_moves = &self.shared.root_moves;
+        let mut result = SearchResult {
+            best_move: root_moves.first().copied().unwrap_or_default(),
+            ponder: None,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            lines: Vec::new(),
+        };
+        if root_moves.is_empty() {
+            return result;
+        }
+        let multipv = self.shared.multipv.clamp(1, root_moves.len());
+
+        // Odd helpers start one ply deeper so threads spread over depths.
+        let first = 1 + (self.id % 2) as u32;
+        'deepening: for depth in first..=max_depth {
+            let mut lines: Vec<SearchInfo> = Vec::with_capacity(multipv);
+            self.excluded.clear();
+            for index in 0..multipv {
+                self.seldepth = 0;
+                let previous = result.lines.get(index);
+                let score = self.aspiration(depth, index, previous, &mut on_info);
+                if self.stopped {
+                    break 'deepening;
+                }
+                let line = self.line(depth, index, score, Bound::Exact, self.pv[0][..self.pv_len[0]].to_vec());
+                self.excluded.push(line.pv[0]);
+                lines.push(line);
+            }
+            // A later line can come out ahead of an earlier one when the
+            // search is unstable, so number them by score.
+            lines.sort_by_key(|line| std::cmp::Reverse(line.score));
+            for (index, line) in lines.iter_mut().enumerate() {
+                line.multipv = index + 1;
+            }
+            let best = &lines[0];
+            result = SearchResult {
+                best_move: best.pv[0],
+                ponder: best.pv.get(1).copied(),
+                score: best.score,
+                depth,
+                nodes: 0,
+                lines,
+            };
+            if self.id == 0 {
+                let time = self.shared.start.elapsed();
+                for line in &mut result.lines {
+                    line.nodes = self.total_nodes();
+                    line.time = time;
+                    line.hashfull = self.shared.tt.hashfull();
+                    on_info(line);
+                }
+                // Another iteration would likely not finish in time.
+                if optimum.is_some_and(|optimum| time >= optimum / 2) {
+                    break;
+                }
+            }
+            if self.shared.stop.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        result.nodes = self.total_nodes();
+        result
+    }
+
+    /// Searches one MultiPV line, widening the window around the line's
+    /// previous score until the result falls inside it. The main thread
+    /// reports the bounds it finds on the way.
+    fn aspiration(
+        &mut self,
+        depth: u32,
+        index: usize,
+        previous: Option<&SearchInfo>,
+        on_info: &mut impl FnMut(&SearchInfo),
+    ) -> i32 {
+        let mut delta = 25;
+        let (mut alpha, mut beta) = match previous {
+            Some(line) if depth >= 5 && line.score.abs() < MATE_BOUND => {
+                (line.score - delta, line.score + delta)
+            }
+            _ => (-INFINITY, INFINITY),
+        };
+        loop {
+            let score = self.negamax(depth as i32, alpha, beta, 0, true);
+            if self.stopped {
+                return score;
+            }
+            let bound = if score <= alpha {
+                alpha = (score - delta).max(-INFINITY);
+                Bound::Upper
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+                Bound::Lower
+            } else {
+                return score;
+            };
+            if self.id == 0 {
+                // Nothing raised alpha on a fail low, so the old line is the best guess.
+                let pv = match (bound, previous) {
+                    (Bound::Upper, Some(line)) => line.pv.clone(),
+                    _ => self.pv[0][..self.pv_len[0]].to_vec(),
+                };
+                let mut line = self.line(depth, index, score, bound, pv);
+                line.nodes = self.total_nodes();
+                line.time = self.shared.start.elapsed();
+                line.hashfull = self.shared.tt.hashfull();
+                on_info(&line);
+            }
+            delta *= 2;
+        }
+    }
+
+    fn line(&self, depth: u32, index: usize, score: i32, bound: Bound, pv: Vec<Move>) -> SearchInfo {
+        SearchInfo {
+            depth,
+            seldepth: self.seldepth as u32,
+            multipv: index + 1,
+            score,
+            bound,
+            nodes: 0,
+            time: Duration::ZERO,
+            pv,
+            hashfull: 0,
+        }
+    }
+
+    fn total_nodes(&self) -> u64 {
+        self.shared.nodes.load(Ordering::Relaxed) + self.nodes
+    }
+
+    /// Counts a node and every so often checks whether the search should stop.
+    fn tick(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes >= NODE_FLUSH {
+            let total = self.shared.nodes.fetch_add(self.nodes, Ordering::Relaxed) + self.nodes;
+            self.nodes = 0;
+            let out_of_time = self
+                .shared
+                .maximum
+                .is_some_and(|maximum| self.shared.start.elapsed() >= maximum);
+            let out_of_nodes = self.shared.node_limit.is_some_and(|limit| total >= limit);
+            if out_of_time || out_of_nodes {
+                self.shared.stop.store(true, Ordering::Relaxed);
+            }
+            self.stopped = self.shared.stop.load(Ordering::Relaxed);
+        }
+        self.stopped
+    }
+
+    fn negamax(&mut self, mut depth: i32, mut alpha: i32, mut beta: i32, ply: usize, allow_null: bool) -> i32 {
+        self.pv_len[ply] = ply;
+        if self.tick() {
+            return 0;
+        }
+        self.seldepth = self.seldepth.max(ply);
+        let root = ply == 0;
+        let pv_node = beta - alpha > 1;
+
+        if !root {
+            if let Some(winner) = self.board.variant_winner() {
+                return if winner == self.board.side_to_move() { MATE - ply as i32 } else { -MATE + ply as i32 };
+            }
+            if self.board.is_draw() {
+                return 0;
+            }
+            if let Some(dtm) = self.shared.endgames.and_then(|endgames| endgames.probe(&self.board)) {
+                return match dtm {
+                    Dtm::Draw => 0,
+                    Dtm::Win(plies) => MATE - ply as i32 - plies as i32,
+                    Dtm::Loss(plies) => -MATE + ply as i32 + plies as i32,
+                };
+            }
+            if ply >= MAX_PLY - 1 {
+                return self.board.evaluate_cached(&mut self.pawn_table);
+            }
+            // No line from here can beat a shorter mate already found.
+            alpha = alpha.max(-MATE + ply as i32);
+            beta = beta.min(MATE - ply as i32 - 1);
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        let in_check = self.board.is_check();
+        if in_check {
+            depth += 1;
+        }
+        if depth <= 0 {
+            return self.qsearch(alpha, beta, ply);
+        }
+
+        let key = self.board.hash();
+        let entry = self.shared.tt.probe(key);
+        let tt_move = entry.map_or(Move::new(), |entry| entry.mv);
+        if let Some(entry) = entry
+            && !pv_node
+            && !root
+            && entry.depth as i32 >= depth
+        {
+            let score = score_from_tt(entry.score as i32, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff {
+                return score;
+            }
+        }
+
+        let static_eval = if in_check {
+            -INFINITY
+        } else {
+            self.board.evaluate_cached(&mut self.pawn_table)
+        };
+
+        if !pv_node && !in_check {
+            // Reverse futility pruning.
+            if depth <= 6 && static_eval - 80 * depth >= beta && static_eval.abs() < MATE_BOUND {
+                return static_eval;
+            }
+            // Null move pruning, unsafe in pawn endings because of zugzwang.
+            if allow_null && depth >= 3 && static_eval >= beta && self.has_non_pawn_material() {
+                let reduction = 3 + depth / 4;
+                let null = self.board.make_null_move();
+                let score = -self.negamax(depth - 1 - reduction, -beta, -beta + 1, ply + 1, false);
+                self.board.unmake_null_move(&null);
+                if self.stopped {
+                    return 0;
+                }
+                if score >= beta {
+                    return if score >= MATE_BOUND { beta } else { score };
+                }
+            }
+        }
+
+        let mut moves = Moves::new();
+        if root {
+            let excluded = &self.excluded;
+            moves.extend(self.shared.root_moves.iter().copied().filter(|mv| !excluded.contains(mv)));
+        } else {
+            // Legality is only checked for the moves actually tried.
+            self.board.generate_pseudo_legal(&mut moves, self.board.side_to_move());
+        }
+        let mut scores = [0; MAX_MOVES];
+        for (i, mv) in moves.iter().enumerate() {
+            scores[i] = self.order_score(mv, tt_move, ply);
+        }
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = Move::new();
+        let mut quiets_tried = Moves::new();
+        let mut searched = 0;
+        for next in 0..moves.len() {
+            let mv = pick_next(&mut moves, &mut scores, next);
+            if !root && !self.board.is_legal(&mv) {
+                continue;
+            }
+            let quiet = !mv.is_capture() && !mv.is_promotion();
+
+            self.board.make_move(&mv);
+            let gives_check = self.board.is_check();
+            let score = if searched == 0 {
+                -self.negamax(depth - 1, -beta, -alpha, ply + 1, true)
+            } else {
+                // Late move reductions for quiet moves ordered after the good ones.
+                let reduction = if depth >= 3 && searched >= 3 && quiet && !in_check && !gives_check {
+                    let r = ((depth as f32).ln() * (searched as f32).ln() / 2.0) as i32;
+                    r.clamp(0, depth - 2) - pv_node as i32
+                } else {
+                    0
+                }
+                .max(0);
+                let mut score = -self.negamax(depth - 1 - reduction, -alpha - 1, -alpha, ply + 1, true);
+                if score > alpha && reduction > 0 {
+                    score = -self.negamax(depth - 1, -alpha - 1, -alpha, ply + 1, true);
+                }
+                if score > alpha && score < beta {
+                    score = -self.negamax(depth - 1, -beta, -alpha, ply + 1, true);
+                }
+                score
+            };
+            self.board.unmake_move(&mv);
+            searched += 1;
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = mv;
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, mv);
+                    if score >= beta {
+                        if quiet {
+                            self.reward_quiet(mv, &quiets_tried, depth, ply);
+                        }
+                        break;
+                    }
+                }
+            }
+            if quiet {
+                quiets_tried.push(mv);
+            }
+        }
+        if searched == 0 {
+            return if in_check {
+                -MATE + ply as i32
+            } else if self.board.variant() == Variant::Antichess {
+                // Running out of moves wins Antichess.
+                MATE - ply as i32
+            } else {
+                0
+            };
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        // A root search over a subset of moves says nothing about the position.
+        if root && (self.shared.restricted || !self.excluded.is_empty()) {
+            return best_score;
+        }
+        self.shared.tt.store(
+            key,
+            TtEntry {
+                mv: if bound == Bound::Upper { Move::new() } else { best_move },
+                score: score_to_tt(best_score, ply) as i16,
+                depth: depth as u8,
+                bound,
+            },
+        );
+        best_score
+    }
+
+    /// Searches captures and promotions until the position is quiet. In check
+    /// every evasion is searched, so mates are still found.
+    fn qsearch(&mut self, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.pv_len[ply] = ply;
+        if self.tick() {
+            return 0;
+        }
+        self.seldepth = self.seldepth.max(ply);
+        if let Some(winner) = self.board.variant_winner() {
+            return if winner == self.board.side_to_move() { MATE - ply as i32 } else { -MATE + ply as i32 };
+        }
+        if ply >= MAX_PLY - 1 {
+            return self.board.evaluate_cached(&mut self.pawn_table);
+        }
+
+        let in_check = self.board.is_check();
+        let mut best_score = -INFINITY;
+        if !in_check {
+            best_score = self.board.evaluate_cached(&mut self.pawn_table);
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+        }
+
+        let mut moves = Moves::new();
+        let kind = if in_check { GenType::Evasions } else { GenType::Captures };
+        self.board.generate(&mut moves, self.board.side_to_move(), kind);
+        if in_check && moves.is_empty() {
+            return -MATE + ply as i32;
+        }
+        let mut scores = [0; MAX_MOVES];
+        for (i, mv) in moves.iter().enumerate() {
+            scores[i] = self.order_score(mv, Move::new(), ply);
+        }
+
+        for i in 0..moves.len() {
+            let mv = pick_next(&mut moves, &mut scores, i);
+            self.board.make_move(&mv);
+            let score = -self.qsearch(-beta, -alpha, ply + 1);
+            self.board.unmake_move(&mv);
+            if self.stopped {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, mv);
+                    if score >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best_score
+    }
+
+    fn order_score(&self, mv: &Move, tt_move: Move, ply: usize) -> i32 {
+        if *mv == tt_move {
+            return 1 << 30;
+        }
+        if mv.is_capture() || mv.is_promotion() {
+            // MVV-LVA, with queen promotions ahead of quiet captures.
+            let promo = if mv.is_promotion() && mv.promotion_piece() == PROMO_QUEEN { 64 } else { 0 };
+            return (1 << 24) + promo + 8 * mv.captured_piece() as i32 - mv.moving_piece() as i32;
+        }
+        if *mv == self.killers[ply][0] {
+            return (1 << 22) + 1;
+        }
+        if *mv == self.killers[ply][1] {
+            return 1 << 22;
+        }
+        self.history[self.board.side_to_move()][mv.from_square()][mv.to_square()]
+    }
+
+    /// Updates killers and history after `mv` failed high, penalising the
+    /// quiet moves tried before it.
+    fn reward_quiet(&mut self, mv: Move, tried: &Moves, depth: i32, ply: usize) {
+        if self.killers[ply][0] != mv {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = mv;
+        }
+        let side = self.board.side_to_move();
+        let bonus = (depth * depth).min(400);
+        let history = &mut self.history[side];
+        update_history(&mut history[mv.from_square()][mv.to_square()], bonus);
+        for other in tried {
+            update_history(&mut history[other.from_square()][other.to_square()], -bonus);
+        }
+    }
+
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        self.pv[ply][ply] = mv;
+        let (head, tail) = self.pv.split_at_mut(ply + 1);
+        let len = self.pv_len[ply + 1].max(ply + 1);
+        head[ply][ply + 1..len].copy_from_slice(&tail[0][ply + 1..len]);
+        self.pv_len[ply] = len;
+    }
+
+    fn has_non_pawn_material(&self) -> bool {
+        let side = self.board.side_to_move();
+        let board = &self.board;
+        board.knights[side] | board.bishops[side] | board.rooks[side] | board.queens[side] != 0
+    }
+}
+
+/// History scores saturate so they stay below killer and capture scores.
+fn update_history(entry: &mut i32, bonus: i32) {
+    *entry += bonus - *entry * bonus.abs() / 16_384;
+}
+
+/// Swaps the best remaining move into position `i` and returns it.
+fn pick_next(moves: &mut Moves, scores: &mut [i32; MAX_MOVES], i: usize) -> Move {
+    let best = (i..moves.len()).max_by_key(|&j| scores[j]).unwrap_or(i);
+    moves.swap(i, best);
+    scores.swap(i, best);
+    moves[i]
+}
+
+// Mate scores are stored relative to the node so they stay correct when the
+// position is reached at a different ply.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
@@ -0,0 +1,203 @@
+//! Self-play games for generating training data.
+//!
+//! Each game starts from a book line or a few random moves, then the engine
+//! plays both sides at fixed limits. Every quiet position it searched is kept
+//! with its score, and the game's result is filled in once it is known.
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use crate::book::Book;
+use crate::packed::{PackedPosition, PackedWriter};
+use crate::pgn::GameResult;
+use crate::search::{mate_in, Limits, Searcher};
+use crate::{Board, Move, Moves, BLACK, WHITE};
+
+#[derive(Clone, Debug)]
+pub struct SelfPlayOptions {
+    /// Search limits for every move, normally a depth or node count.
+    pub limits: Limits,
+    pub book: Option<Arc<Book>>,
+    /// Book moves to play at most before the search takes over.
+    pub book_plies: u32,
+    /// Uniformly random moves to play after the book.
+    pub random_plies: u32,
+    /// A side whose score has stayed at or below `-resign_score` for its
+    /// last `resign_moves` moves resigns.
+    pub resign_score: i32,
+    pub resign_moves: u32,
+    /// After `draw_after` plies, a game whose score has stayed within
+    /// `draw_score` for `draw_plies` plies is a draw.
+    pub draw_score: i32,
+    pub draw_plies: u32,
+    pub draw_after: u32,
+    /// Games still going after this many plies are drawn.
+    pub max_plies: u32,
+}
+
+impl Default for SelfPlayOptions {
+    fn default() -> Self {
+        Self {
+            limits: Limits {
+                depth: Some(8),
+                ..Limits::default()
+            },
+            book: None,
+            book_plies: 16,
+            random_plies: 8,
+            resign_score: 1000,
+            resign_moves: 3,
+            draw_score: 10,
+            draw_plies: 12,
+            draw_after: 80,
+            max_plies: 400,
+        }
+    }
+}
+
+/// A finished game's positions, ready to be written out.
+#[derive(Clone, Debug)]
+pub struct SelfPlayGame {
+    /// Positions with their scores and the game's result.
+    pub positions: Vec<PackedPosition>,
+    pub result: GameResult,
+    /// Every move played, from the start position.
+    pub moves: Vec<Move>,
+}
+
+/// Plays one game with a fresh transposition table. The same `seed` and
+/// options always give the same game.
+pub fn play_game(options: &SelfPlayOptions, searcher: &mut Searcher, seed: u64) -> SelfPlayGame {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    searcher.clear();
+    let mut board = Board::new();
+    let mut moves_played = Vec::new();
+    let opening = loop {
+        if let Some(opening) = play_opening(options, &mut board, &mut rng) {
+            break opening;
+        }
+        // The random moves ended the game; try again from the start.
+        board = Board::new();
+    };
+    moves_played.extend(opening);
+
+    let mut positions = Vec::new();
+    let (mut resign_count, mut draw_count) = ([0; 2], 0);
+    let result = loop {
+        if let Some(outcome) = board.outcome() {
+            break outcome.result();
+        }
+        if moves_played.len() as u32 >= options.max_plies {
+            break GameResult::Draw;
+        }
+        let search = searcher.search(&board, &options.limits, |_| {});
+        let side = board.side_to_move();
+        let score = search.score;
+
+        // Positions in check, with a winning capture or promotion, or a mate on
+        // the board are poor training targets for a static evaluation.
+        let tactical = search.best_move.is_capture() || search.best_move.is_promotion();
+        if !board.is_check()
+            && !tactical
+            && mate_in(score).is_none()
+            && let Ok(mut packed) = board.to_packed()
+        {
+            let white_score = if side == WHITE { score } else { -score };
+            packed.set_score(white_score.clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+            positions.push(packed);
+        }
+
+        if score <= -options.resign_score {
+            resign_count[side] += 1;
+            if resign_count[side] >= options.resign_moves {
+                break if side == WHITE { GameResult::BlackWins } else { GameResult::WhiteWins };
+            }
+        } else {
+            resign_count[side] = 0;
+        }
+        if moves_played.len() as u32 >= options.draw_after && score.abs() <= options.draw_score {
+            draw_count += 1;
+            if draw_count >= options.draw_plies {
+                break GameResult::Draw;
+            }
+        } else {
+            draw_count = 0;
+        }
+
+        board.make_move(&search.best_move);
+        moves_played.push(search.best_move);
+    };
+
+    for packed in &mut positions {
+        packed.set_result(result);
+    }
+    SelfPlayGame {
+        positions,
+        result,
+        moves: moves_played,
+    }
+}
+
+/// Plays the book and random moves, returning them, or `None` if the game
+/// ended on the way.
+fn play_opening(options: &SelfPlayOptions, board: &mut Board, rng: &mut fastrand::Rng) -> Option<Vec<Move>> {
+    let mut played = Vec::new();
+    if let Some(book) = &options.book {
+        while (played.len() as u32) < options.book_plies {
+            let Some(mv) = book.pick_move(board, rng) else {
+                break;
+            };
+            board.make_move(&mv);
+            played.push(mv);
+        }
+    }
+    for _ in 0..options.random_plies {
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move());
+        if moves.is_empty() {
+            return None;
+        }
+        let mv = moves[rng.usize(..moves.len())];
+        board.make_move(&mv);
+        played.push(mv);
+    }
+    board.outcome().is_none().then_some(played)
+}
+
+/// How positions are written out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 32-byte records; see `packed`.
+    Packed,
+    /// One EPD line per position with the score as `ce`, from the side to
+    /// move's point of view, and the result as `c9`.
+    Epd,
+}
+
+impl OutputFormat {
+    pub fn write_game(self, writer: &mut impl Write, game: &SelfPlayGame) -> io::Result<()> {
+        match self {
+            OutputFormat::Packed => {
+                let mut writer = PackedWriter::new(writer);
+                game.positions.iter().try_for_each(|packed| writer.write(packed))
+            }
+            OutputFormat::Epd => {
+                for packed in &game.positions {
+                    let board = Board::from_packed(packed).map_err(io::Error::other)?;
+                    let fen = board.to_fen();
+                    let fields: Vec<&str> = fen.split(' ').collect();
+                    let score = if board.side_to_move() == BLACK { -packed.score() } else { packed.score() };
+                    writeln!(
+                        writer,
+                        "{} hmvc {}; fmvn {}; ce {score}; c9 \"{}\";",
+                        fields[..4].join(" "),
+                        fields[4],
+                        fields[5],
+                        game.result
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Serde support, behind the `serde` feature.
+//!
+//! Human-readable formats such as JSON get a `Board` as its FEN, prefixed with
+//! `variant:` for anything but standard chess. Binary formats get packed bytes:
+//!
+//! ```text
+//! variant      u8, index into Variant::ALL
+//! occupancy    u64 little-endian
+//! pieces       one nibble per occupied square from a1 up, piece type + 8 for black
+//! crazyhouse   promoted u64 little-endian, then the pockets as ten counts, white first
+//! rest         the FEN fields after the placement: side, castling, ep, checks, clocks
+//! ```
+//!
+//! A `Move` is its raw `u32` in every format: the UCI string alone does not say
+//! which piece moved or what it took, so it could not be read back without the
+//! position.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::fen::{placement, pocket};
+use crate::{Board, Move, Variant, BLACK, WHITE};
+
+impl Board {
+    fn to_bytes(&self) -> Vec<u8> {
+        let variant = Variant::ALL.iter().position(|&v| v == self.variant).expect("every variant is listed");
+        let mut bytes = vec![variant as u8];
+        bytes.extend(self.occupied.to_le_bytes());
+        let mut nibbles = Vec::new();
+        for square in (0..64).filter(|&square| self.occupied & (1 << square) != 0) {
+            nibbles.push(self.arr[square] | if self.side_at(square) == Some(BLACK) { 8 } else { 0 });
+        }
+        bytes.extend(nibbles.chunks(2).map(|pair| pair[0] | pair.get(1).unwrap_or(&0) << 4));
+        if self.variant == Variant::Crazyhouse {
+            bytes.extend(self.promoted.to_le_bytes());
+            bytes.extend(self.pockets.as_flattened());
+        }
+        let fen = self.to_fen();
+        let (_, rest) = fen.split_once(' ').expect("FEN has several fields");
+        bytes.extend(rest.as_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let truncated = || "truncated position".to_string();
+        let (&variant, bytes) = bytes.split_first().ok_or_else(truncated)?;
+        let variant = *Variant::ALL.get(variant as usize).ok_or(format!("unknown variant {variant}"))?;
+        let (occupancy, bytes) = bytes.split_first_chunk::<8>().ok_or_else(truncated)?;
+        let occupancy = u64::from_le_bytes(*occupancy);
+        let count = occupancy.count_ones() as usize;
+        let (nibbles, mut bytes) = bytes.split_at_checked(count.div_ceil(2)).ok_or_else(truncated)?;
+        let mut promoted = 0;
+        let mut pockets = [[0; 5]; 2];
+        if variant == Variant::Crazyhouse {
+            let (mask, rest) = bytes.split_first_chunk::<8>().ok_or_else(truncated)?;
+            let (white, rest) = rest.split_first_chunk::<5>().ok_or_else(truncated)?;
+            let (black, rest) = rest.split_first_chunk::<5>().ok_or_else(truncated)?;
+            (promoted, pockets, bytes) = (u64::from_le_bytes(*mask), [*white, *black], rest);
+        }
+        let rest = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+
+        let mut codes = nibbles.iter().flat_map(|&byte| [byte & 0xf, byte >> 4]);
+        let mut squares = [None; 64];
+        for square in (0..64).filter(|&square| occupancy & (1 << square) != 0) {
+            let code = codes.next().expect("one nibble per occupied square");
+            let side = if code & 8 != 0 { BLACK } else { WHITE };
+            let piece = code & 7;
+            if piece == 0 || piece > 6 {
+                return Err(format!("invalid piece code {code}"));
+            }
+            squares[square] = Some((side, piece));
+        }
+
+        let mut fen = placement(&squares, promoted);
+        if variant == Variant::Crazyhouse {
+            fen.push_str(&pocket(&pockets));
+        }
+        fen.push(' ');
+        fen.push_str(rest);
+        Board::from_variant_fen(&fen, variant).map_err(|err| err.to_string())
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(&self.to_bytes());
+        }
+        match self.variant {
+            Variant::Standard => serializer.serialize_str(&self.to_fen()),
+            variant => serializer.serialize_str(&format!("{variant}:{}", self.to_fen())),
+        }
+    }
+}
+
+struct BoardVisitor;
+
+impl<'de> Visitor<'de> for BoardVisitor {
+    type Value = Board;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a FEN string or a packed position")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Board, E> {
+        let (variant, fen) = match s.split_once(':') {
+            Some((variant, fen)) => (variant.parse().map_err(E::custom)?, fen),
+            None => (Variant::Standard, s),
+        };
+        Board::from_variant_fen(fen, variant).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Board, E> {
+        Board::from_bytes(bytes).map_err(E::custom)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Board, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BoardVisitor)
+        } else {
+            deserializer.deserialize_bytes(BoardVisitor)
+        }
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.raw())
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        u32::deserialize(deserializer).map(Move::from_raw)
+    }
+}
+
+impl Serialize for Variant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Variant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Variant, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
@@ -20,6 +20,12 @@ impl RookTable {
     }
 }
 
+impl Default for RookTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BishopTable {
     pub table: Vec<u64>,
@@ -42,6 +48,12 @@ impl BishopTable {
     }
 }
 
+impl Default for BishopTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 fn gen_subsets(mask: u64) -> Vec<u64> {
     let mut subsets = Vec::new();
     let mut subset = mask;
@@ -83,7 +95,7 @@ fn gen_empty_slide_bishop_inner(square: usize) -> u64 {
         attacks |= 1 << (r * 8 + f);
     }
     // Up Left
-    for (r, f) in (rank + 1..7).zip(1..file) {
+    for (r, f) in (rank + 1..7).zip((1..file).rev()) {
         attacks |= 1 << (r * 8 + f);
     }
     // Down Right
@@ -91,7 +103,7 @@ fn gen_empty_slide_bishop_inner(square: usize) -> u64 {
         attacks |= 1 << (r * 8 + f);
     }
     // Down Left
-    for (r, f) in (1..rank).rev().zip(1..file) {
+    for (r, f) in (1..rank).rev().zip((1..file).rev()) {
         attacks |= 1 << (r * 8 + f);
     }
     attacks
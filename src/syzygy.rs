@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io;
+use std::ops::Neg;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{Board, Move, Moves, Side, Variant, PAWN_PIECE_TYPE};
+
+mod table;
+use table::{Kind, Material, Table, MAX_PIECES};
+
+/// Ranks root moves above any real distance to zeroing.
+const MAX_DTZ: i32 = 1 << 18;
+
+/// Tablebase result for the side to move. Cursed wins and blessed losses
+/// are decided on the board but drawn by the fifty-move rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Self {
+        match value {
+            ..=-2 => Self::Loss,
+            -1 => Self::BlessedLoss,
+            0 => Self::Draw,
+            1 => Self::CursedWin,
+            2.. => Self::Win,
+        }
+    }
+
+    fn signum(self) -> i32 {
+        (self as i32).signum()
+    }
+
+    /// Distance to zeroing, in plies, of a position whose best move is a
+    /// capture or pawn move with this result.
+    fn dtz_before_zeroing(self) -> i32 {
+        match self {
+            Self::Loss => -1,
+            Self::BlessedLoss => -101,
+            Self::Draw => 0,
+            Self::CursedWin => 101,
+            Self::Win => 1,
+        }
+    }
+}
+
+impl Neg for Wdl {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::from_value(-(self as i32))
+    }
+}
+
+/// A table file, mapped on first use.
+struct LazyTable {
+    path: PathBuf,
+    table: OnceLock<Option<Table>>,
+}
+
+impl LazyTable {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            table: OnceLock::new(),
+        }
+    }
+
+    fn get(&self, kind: Kind, material: &Material) -> Option<&Table> {
+        self.table
+            .get_or_init(|| Table::open(&self.path, kind, material).ok())
+            .as_ref()
+    }
+}
+
+struct TableFiles {
+    material: Material,
+    wdl: Option<LazyTable>,
+    dtz: Option<LazyTable>,
+}
+
+/// Syzygy endgame tablebases read from `.rtbw` and `.rtbz` files.
+///
+/// Files are found by name, e.g. `KRvK.rtbw`, and memory-mapped the first
+/// time a position needs them. Positions with castling rights are never in
+/// the tables. WDL tables are required; DTZ tables are optional.
+#[derive(Default)]
+pub struct Tablebase {
+    /// Keyed by file name without extension.
+    tables: HashMap<String, TableFiles>,
+    max_pieces: usize,
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tablebases from every directory in `paths`, separated like `PATH`.
+    pub fn open(paths: impl AsRef<OsStr>) -> io::Result<Self> {
+        let mut tablebase = Self::new();
+        for dir in std::env::split_paths(&paths) {
+            tablebase.add_directory(dir)?;
+        }
+        Ok(tablebase)
+    }
+
+    /// Adds the table files found in `dir`, returning how many there were.
+    pub fn add_directory(&mut self, dir: impl AsRef<Path>) -> io::Result<usize> {
+        let mut added = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let kind = match path.extension().and_then(OsStr::to_str) {
+                Some("rtbw") => Kind::Wdl,
+                Some("rtbz") => Kind::Dtz,
+                _ => continue,
+            };
+            let Some(name) = path.file_stem().and_then(OsStr::to_str).map(str::to_string) else {
+                continue;
+            };
+            let Some(material) = Material::from_name(&name) else {
+                continue;
+            };
+            if kind == Kind::Wdl {
+                self.max_pieces = self.max_pieces.max(material.piece_count());
+            }
+            let files = self.tables.entry(name).or_insert_with(|| TableFiles {
+                material,
+                wdl: None,
+                dtz: None,
+            });
+            let slot = match kind {
+                Kind::Wdl => &mut files.wdl,
+                Kind::Dtz => &mut files.dtz,
+            };
+            *slot = Some(LazyTable::new(path));
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// Most pieces, kings included, of any WDL table; 0 if there are none.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Whether `board` may be found in the tables.
+    fn covers(&self, board: &Board) -> bool {
+        board.occupied.count_ones() as usize <= self.max_pieces.min(MAX_PIECES)
+            && !board.castling.any()
+            && board.variant == Variant::Standard
+    }
+
+    /// Win, draw or loss for the side to move, or `None` if a needed table
+    /// is missing.
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.covers(board) {
+            return None;
+        }
+        let mut board = board.clone();
+        self.search(&mut board, false).map(|(wdl, _)| wdl)
+    }
+
+    /// Distance to zeroing the fifty-move counter with best play, in plies:
+    /// positive if the side to move wins, negative if it loses and 0 for a
+    /// draw. Cursed wins and blessed losses are offset by 100. Values may be
+    /// off by one, as the tables sometimes store full moves.
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.covers(board) {
+            return None;
+        }
+        let mut board = board.clone();
+        self.dtz(&mut board)
+    }
+
+    /// The legal moves that keep the best result for the side to move,
+    /// counting the fifty-move rule. With DTZ tables a winning side only
+    /// keeps the moves that zero the counter soonest, so that playing any
+    /// of them makes progress, and a losing side the ones that resist
+    /// longest. Without them every move with the best WDL result is kept.
+    pub fn root_moves(&self, board: &Board) -> Option<Vec<Move>> {
+        if !self.covers(board) {
+            return None;
+        }
+        let mut board = board.clone();
+        let ranked = self.rank_by_dtz(&mut board).or_else(|| self.rank_by_wdl(&mut board))?;
+        let best = ranked.iter().map(|&(_, rank)| rank).max()?;
+        Some(ranked.into_iter().filter(|&(_, rank)| rank == best).map(|(mv, _)| mv).collect())
+    }
+
+    fn rank_by_dtz(&self, board: &mut Board) -> Option<Vec<(Move, i32)>> {
+        let halfmove_clock = board.halfmove_clock as i32;
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move);
+        let mut ranked = Vec::with_capacity(moves.len());
+        for mv in moves {
+            board.make_move(&mv);
+            let dtz = if board.halfmove_clock == 0 {
+                self.search(board, false).map(|(wdl, _)| (-wdl).dtz_before_zeroing())
+            } else if board.is_draw() {
+                Some(0)
+            } else {
+                self.dtz(board).map(|dtz| -dtz - dtz.signum())
+            };
+            let mates = board.is_check() && !has_legal_moves(board);
+            board.unmake_move(&mv);
+            let dtz = if mates { 1 } else { dtz? };
+            let rank = match dtz {
+                0 => 0,
+                1.. => MAX_DTZ - (dtz + halfmove_clock),
+                _ => -MAX_DTZ + (-dtz + halfmove_clock),
+            };
+            ranked.push((mv, rank));
+        }
+        Some(ranked)
+    }
+
+    fn rank_by_wdl(&self, board: &mut Board) -> Option<Vec<(Move, i32)>> {
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move);
+        let mut ranked = Vec::with_capacity(moves.len());
+        for mv in moves {
+            board.make_move(&mv);
+            let wdl = if board.is_draw() {
+                Some(Wdl::Draw)
+            } else {
+                self.search(board, false).map(|(wdl, _)| -wdl)
+            };
+            board.unmake_move(&mv);
+            ranked.push((mv, wdl? as i32));
+        }
+        Some(ranked)
+    }
+
+    /// The table file for the material on `board`, and whether the colours
+    /// are swapped relative to its name.
+    fn find(&self, board: &Board) -> Option<(&TableFiles, bool)> {
+        let white = material_name(board, Side::WHITE);
+        let black = material_name(board, Side::BLACK);
+        if let Some(files) = self.tables.get(&format!("{white}v{black}")) {
+            return Some((files, false));
+        }
+        self.tables.get(&format!("{black}v{white}")).map(|files| (files, true))
+    }
+
+    fn table_wdl(&self, board: &Board) -> Option<Wdl> {
+        if board.occupied.count_ones() == 2 {
+            return Some(Wdl::Draw);
+        }
+        let (files, swapped) = self.find(board)?;
+        let table = files.wdl.as_ref()?.get(Kind::Wdl, &files.material)?;
+        table.probe(board, swapped, 0).map(Wdl::from_value)
+    }
+
+    /// The stored DTZ, or `Some(None)` if the table only has the other side to move.
+    fn table_dtz(&self, board: &Board, wdl: Wdl) -> Option<Option<i32>> {
+        let (files, swapped) = self.find(board)?;
+        let table = files.dtz.as_ref()?.get(Kind::Dtz, &files.material)?;
+        Some(table.probe(board, swapped, wdl as i32))
+    }
+
+    /// WDL of `board` and whether the best move is a capture, or with
+    /// `zeroing` a pawn move. Tables may store any value for positions won
+    /// by a capture, so those are searched first and the best result kept.
+    fn search(&self, board: &mut Board, zeroing: bool) -> Option<(Wdl, bool)> {
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move);
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for mv in &moves {
+            let zeroing_move = mv.is_capture() || (zeroing && mv.moving_piece() == PAWN_PIECE_TYPE);
+            if !zeroing_move {
+                continue;
+            }
+            searched += 1;
+            board.make_move(mv);
+            let result = self.search(board, false);
+            board.unmake_move(mv);
+            let wdl = -result?.0;
+            if wdl > best {
+                best = wdl;
+                if wdl == Wdl::Win {
+                    return Some((wdl, true));
+                }
+            }
+        }
+
+        // Tables know nothing of en passant, so only trust them if some
+        // move was left unsearched.
+        let all_searched = searched > 0 && searched == moves.len();
+        let stored = if all_searched { best } else { self.table_wdl(board)? };
+        if best >= stored {
+            Some((best, best > Wdl::Draw || all_searched))
+        } else {
+            Some((stored, false))
+        }
+    }
+
+    fn dtz(&self, board: &mut Board) -> Option<i32> {
+        let (wdl, zeroing_best) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if zeroing_best {
+            return Some(wdl.dtz_before_zeroing());
+        }
+        if let Some(dtz) = self.table_dtz(board, wdl)? {
+            let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+            return Some((dtz + if cursed { 100 } else { 0 }) * wdl.signum());
+        }
+
+        // Only the other side to move is stored: take the best reply.
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move);
+        let mut best = None;
+        for mv in moves {
+            let zeroing = mv.is_capture() || mv.moving_piece() == PAWN_PIECE_TYPE;
+            board.make_move(&mv);
+            let dtz = if zeroing {
+                self.search(board, false).map(|(wdl, _)| -wdl.dtz_before_zeroing())
+            } else {
+                self.dtz(board).map(|dtz| -dtz - dtz.signum())
+            };
+            let mates = board.is_check() && !has_legal_moves(board);
+            board.unmake_move(&mv);
+            let dtz = if mates { 1 } else { dtz? };
+            if dtz.signum() == wdl.signum() && best.is_none_or(|best| dtz < best) {
+                best = Some(dtz);
+            }
+        }
+        // With no legal moves the side to move is mated.
+        Some(best.unwrap_or(-1))
+    }
+}
+
+/// Pieces of `side` as they appear in table names, e.g. `KRP`.
+fn material_name(board: &Board, side: Side) -> String {
+    let mut name = String::from("K");
+    for (letter, pieces) in [
+        ('Q', board.queens[side]),
+        ('R', board.rooks[side]),
+        ('B', board.bishops[side]),
+        ('N', board.knights[side]),
+        ('P', board.pawns[side]),
+    ] {
+        for _ in 0..pieces.count_ones() {
+            name.push(letter);
+        }
+    }
+    name
+}
+
+fn has_legal_moves(board: &Board) -> bool {
+    let mut moves = Moves::new();
+    board.generate_moves(&mut moves, board.side_to_move);
+    !moves.is_empty()
+}
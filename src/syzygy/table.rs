@@ -0,0 +1,807 @@
+// Reading of Syzygy `.rtbw` (win/draw/loss) and `.rtbz` (distance to zeroing)
+// files. Positions are mapped to an index by the same canonical encoding the
+// generator used, and the value at that index is decompressed from the
+// file's Huffman-coded, recursively paired blocks.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use memmap2::Mmap;
+
+use crate::{
+    king_attack_table, Board, Side, BISHOP_PIECE_TYPE, KING_PIECE_TYPE, KNIGHT_PIECE_TYPE,
+    PAWN_PIECE_TYPE, QUEEN_PIECE_TYPE, ROOK_PIECE_TYPE,
+};
+
+pub(super) const MAX_PIECES: usize = 7;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+// Per-table flags.
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Kind {
+    Wdl,
+    Dtz,
+}
+
+/// Piece counts of a table such as `KRPvKR`, white being the side before the `v`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Material {
+    /// Indexed by side and piece type.
+    counts: [[u8; 7]; 2],
+}
+
+impl Material {
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0; 7]; 2];
+        for (side, pieces) in [white, black].into_iter().enumerate() {
+            for c in pieces.chars() {
+                let piece = match c {
+                    'P' => PAWN_PIECE_TYPE,
+                    'N' => KNIGHT_PIECE_TYPE,
+                    'B' => BISHOP_PIECE_TYPE,
+                    'R' => ROOK_PIECE_TYPE,
+                    'Q' => QUEEN_PIECE_TYPE,
+                    'K' => KING_PIECE_TYPE,
+                    _ => return None,
+                };
+                counts[side][piece as usize] += 1;
+            }
+            if counts[side][KING_PIECE_TYPE as usize] != 1 {
+                return None;
+            }
+        }
+        let material = Self { counts };
+        (material.piece_count() <= MAX_PIECES).then_some(material)
+    }
+
+    pub(super) fn piece_count(&self) -> usize {
+        self.counts.iter().flatten().map(|&n| n as usize).sum()
+    }
+
+    fn pawns(&self, side: usize) -> usize {
+        self.counts[side][PAWN_PIECE_TYPE as usize] as usize
+    }
+
+    fn has_pawns(&self) -> bool {
+        self.pawns(0) + self.pawns(1) > 0
+    }
+
+    /// Whether some side has exactly one piece of a kind other than the king.
+    fn has_unique_pieces(&self) -> bool {
+        self.counts
+            .iter()
+            .any(|counts| counts[PAWN_PIECE_TYPE as usize..KING_PIECE_TYPE as usize].contains(&1))
+    }
+
+    fn is_symmetric(&self) -> bool {
+        self.counts[0] == self.counts[1]
+    }
+
+    /// Pawn counts of the leading side and the other side. The side with
+    /// fewer pawns leads when both have some.
+    fn pawn_counts(&self) -> [usize; 2] {
+        let (white, black) = (self.pawns(0), self.pawns(1));
+        if black == 0 || (white > 0 && black >= white) {
+            [white, black]
+        } else {
+            [black, white]
+        }
+    }
+}
+
+/// Index tables shared by every table file.
+struct Consts {
+    /// Squares a2-h7 to 0..48, higher toward the edge files and lower ranks.
+    map_pawns: [usize; 64],
+    /// Squares below the a1-h8 diagonal to 0..28.
+    map_b1h1h7: [usize; 64],
+    /// Squares of the a1-d1-d4 triangle to 0..10, the diagonal last.
+    map_a1d1d4: [usize; 64],
+    /// The 462 placements of two kings with the first in the a1-d1-d4 triangle.
+    map_kk: [[usize; 64]; 10],
+    /// `binomial[k][n]` ways to choose k of n.
+    binomial: [[u64; 64]; MAX_PIECES],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+fn off_a1h8(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+impl Consts {
+    fn new() -> Self {
+        let mut map_b1h1h7 = [0; 64];
+        let below = (0..64).filter(|&s| off_a1h8(s) < 0);
+        for (code, square) in below.enumerate() {
+            map_b1h1h7[square] = code;
+        }
+
+        let mut map_a1d1d4 = [0; 64];
+        let triangle = (0..=27).filter(|&s| s % 8 <= 3 && off_a1h8(s) < 0);
+        let diagonal = (0..=27).filter(|&s| s % 8 <= 3 && off_a1h8(s) == 0);
+        for (code, square) in triangle.chain(diagonal).enumerate() {
+            map_a1d1d4[square] = code;
+        }
+
+        let king_attacks = king_attack_table();
+        let mut map_kk = [[0; 64]; 10];
+        let mut both_on_diagonal = Vec::new();
+        let mut code = 0;
+        for (idx, row) in map_kk.iter_mut().enumerate() {
+            for s1 in (0..=27).filter(|&s| map_a1d1d4[s] == idx && (idx != 0 || s == 1)) {
+                for (s2, slot) in row.iter_mut().enumerate() {
+                    if (king_attacks[s1] | 1 << s1) & 1 << s2 != 0
+                        || (off_a1h8(s1) == 0 && off_a1h8(s2) > 0)
+                    {
+                        continue;
+                    }
+                    if off_a1h8(s1) == 0 && off_a1h8(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        *slot = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            map_kk[idx][s2] = code;
+            code += 1;
+        }
+
+        let mut binomial = [[0; 64]; MAX_PIECES];
+        binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..MAX_PIECES.min(n + 1) {
+                binomial[k][n] = if k > 0 { binomial[k - 1][n - 1] } else { 0 }
+                    + if k < n { binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut map_pawns = [0; 64];
+        let mut lead_pawn_idx = [[0; 64]; 6];
+        let mut lead_pawns_size = [[0; 4]; 6];
+        let mut available = 48;
+        for lead_pawns in 1..=5 {
+            for (file, size) in lead_pawns_size[lead_pawns].iter_mut().enumerate() {
+                let mut idx = 0;
+                for rank in 1..=6 {
+                    let square = 8 * rank + file;
+                    if lead_pawns == 1 {
+                        map_pawns[square] = available - 1;
+                        map_pawns[square ^ 7] = available - 2;
+                        available -= 2;
+                    }
+                    lead_pawn_idx[lead_pawns][square] = idx;
+                    idx += binomial[lead_pawns - 1][map_pawns[square]];
+                }
+                *size = idx;
+            }
+        }
+
+        Self {
+            map_pawns,
+            map_b1h1h7,
+            map_a1d1d4,
+            map_kk,
+            binomial,
+            lead_pawn_idx,
+            lead_pawns_size,
+        }
+    }
+}
+
+fn consts() -> &'static Consts {
+    static CONSTS: OnceLock<Consts> = OnceLock::new();
+    CONSTS.get_or_init(Consts::new)
+}
+
+/// Decoding information for one side to move and, with pawns, one file of the
+/// leading pawn. Fields ending in `_at` are byte offsets into the file.
+#[derive(Clone, Debug, Default)]
+struct PairsData {
+    flags: u8,
+    min_sym_len: u8,
+    num_blocks: u32,
+    block_size: usize,
+    /// Every `span` values there is a sparse index entry.
+    span: usize,
+    lowest_sym_at: usize,
+    btree_at: usize,
+    block_length_at: usize,
+    block_length_size: usize,
+    sparse_index_at: usize,
+    sparse_index_size: usize,
+    data_at: usize,
+    /// `base64[l]` is the lowest symbol of length `min_sym_len + l`, padded to 64 bits.
+    base64: Vec<u64>,
+    /// Number of values, minus one, that each symbol expands to.
+    symlen: Vec<u8>,
+    /// Piece codes (type, plus 8 for black) in encoding order.
+    pieces: [u8; MAX_PIECES],
+    group_idx: [u64; MAX_PIECES + 1],
+    /// Sizes of the groups of pieces encoded together, zero-terminated.
+    group_len: [usize; MAX_PIECES + 1],
+    /// Where the DTZ value maps for wins, losses, cursed wins and blessed losses start.
+    map_idx: [usize; 4],
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// A memory-mapped table file.
+pub(super) struct Table {
+    mmap: Mmap,
+    kind: Kind,
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    symmetric: bool,
+    pawn_counts: [usize; 2],
+    /// Indexed by side to move (WDL tables of asymmetric material only) and file.
+    items: [[PairsData; 4]; 2],
+    /// Start of the DTZ value maps.
+    map_at: usize,
+}
+
+impl Table {
+    pub(super) fn open(path: &Path, kind: Kind, material: &Material) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: table files are never written while an engine has them open.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let magic = match kind {
+            Kind::Wdl => WDL_MAGIC,
+            Kind::Dtz => DTZ_MAGIC,
+        };
+        if mmap.get(..4) != Some(&magic[..]) {
+            return Err(invalid("not a Syzygy table"));
+        }
+        let mut table = Self {
+            mmap,
+            kind,
+            piece_count: material.piece_count(),
+            has_pawns: material.has_pawns(),
+            has_unique_pieces: material.has_unique_pieces(),
+            symmetric: material.is_symmetric(),
+            pawn_counts: material.pawn_counts(),
+            items: Default::default(),
+            map_at: 0,
+        };
+        table.parse()?;
+        Ok(table)
+    }
+
+    fn byte(&self, at: usize) -> u8 {
+        self.mmap[at]
+    }
+
+    fn u16_le(&self, at: usize) -> u16 {
+        u16::from_le_bytes([self.mmap[at], self.mmap[at + 1]])
+    }
+
+    fn u32_le(&self, at: usize) -> u32 {
+        u32::from_le_bytes(self.mmap[at..at + 4].try_into().expect("4 bytes"))
+    }
+
+    /// Compressed data may be read a little past the end of the last block.
+    fn u32_be(&self, at: usize) -> u32 {
+        match self.mmap.get(at..at + 4) {
+            Some(bytes) => u32::from_be_bytes(bytes.try_into().expect("4 bytes")),
+            None => 0,
+        }
+    }
+
+    fn sides(&self) -> usize {
+        if self.kind == Kind::Wdl && !self.symmetric { 2 } else { 1 }
+    }
+
+    fn files(&self) -> usize {
+        if self.has_pawns { 4 } else { 1 }
+    }
+
+    fn item(&self, stm: usize, file: usize) -> &PairsData {
+        &self.items[stm % self.sides()][if self.has_pawns { file } else { 0 }]
+    }
+
+    fn parse(&mut self) -> io::Result<()> {
+        let len = self.mmap.len();
+        let mut at = 4;
+        if len < at + 2 + self.piece_count {
+            return Err(invalid("truncated Syzygy table"));
+        }
+        let flags = self.byte(at);
+        if (flags & 2 != 0) != self.has_pawns || (flags & 1 != 0) == self.symmetric {
+            return Err(invalid("Syzygy table does not match its file name"));
+        }
+        at += 1;
+
+        let (sides, files) = (self.sides(), self.files());
+        let both_pawns = self.has_pawns && self.pawn_counts[1] > 0;
+        for file in 0..files {
+            let byte = self.byte(at);
+            let next = if both_pawns { self.byte(at + 1) } else { 0xFF };
+            let order = [[byte & 0xF, next & 0xF], [byte >> 4, next >> 4]];
+            at += 1 + both_pawns as usize;
+            for k in 0..self.piece_count {
+                let byte = self.byte(at);
+                self.items[0][file].pieces[k] = byte & 0xF;
+                self.items[1][file].pieces[k] = byte >> 4;
+                at += 1;
+            }
+            for (side, order) in order.iter().enumerate().take(sides) {
+                self.set_groups(side, file, order);
+            }
+        }
+        at += at & 1;
+
+        for file in 0..files {
+            for side in 0..sides {
+                at = self.set_sizes(side, file, at)?;
+            }
+        }
+        if self.kind == Kind::Dtz {
+            at = self.set_dtz_map(at)?;
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut self.items[side][file];
+                d.sparse_index_at = at;
+                at += d.sparse_index_size * 6;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut self.items[side][file];
+                d.block_length_at = at;
+                at += d.block_length_size * 2;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut self.items[side][file];
+                at = (at + 0x3F) & !0x3F;
+                d.data_at = at;
+                at += d.num_blocks as usize * d.block_size;
+            }
+        }
+        if at > len {
+            return Err(invalid("truncated Syzygy table"));
+        }
+        Ok(())
+    }
+
+    /// Splits the pieces into groups and works out the factor of each group
+    /// in the index. The leading group is three unique pieces, the two kings
+    /// or, with pawns, the leading pawns.
+    fn set_groups(&mut self, side: usize, file: usize, order: &[u8; 2]) {
+        let c = consts();
+        let (piece_count, has_pawns, has_unique_pieces) =
+            (self.piece_count, self.has_pawns, self.has_unique_pieces);
+        let both_pawns = has_pawns && self.pawn_counts[1] > 0;
+        let d = &mut self.items[side][file];
+
+        let mut first_len: i32 = if has_pawns { 0 } else if has_unique_pieces { 3 } else { 2 };
+        let mut n = 0;
+        d.group_len[0] = 1;
+        for i in 1..piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        // Groups are encoded in the order the table gives, which need not be
+        // the order they appear in.
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+        let mut idx = 1;
+        let mut k = 0;
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                d.group_idx[0] = idx;
+                idx *= if has_pawns {
+                    c.lead_pawns_size[d.group_len[0]][file]
+                } else if has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                d.group_idx[1] = idx;
+                idx *= c.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= c.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        d.group_idx[n] = idx;
+    }
+
+    fn set_sizes(&mut self, side: usize, file: usize, mut at: usize) -> io::Result<usize> {
+        let flags = self.byte(at);
+        at += 1;
+        if flags & SINGLE_VALUE != 0 {
+            let d = &mut self.items[side][file];
+            d.flags = flags;
+            // The single value is kept in place of the symbol length.
+            d.min_sym_len = self.mmap[at];
+            return Ok(at + 1);
+        }
+
+        let d = &self.items[side][file];
+        let groups = d.group_len.iter().position(|&len| len == 0).unwrap_or(MAX_PIECES);
+        let tb_size = d.group_idx[groups];
+
+        let block_size = 1usize << self.byte(at);
+        let span = 1usize << self.byte(at + 1);
+        let padding = self.byte(at + 2) as usize;
+        let num_blocks = self.u32_le(at + 3);
+        let max_sym_len = self.byte(at + 7);
+        let min_sym_len = self.byte(at + 8);
+        at += 9;
+        if min_sym_len == 0 || max_sym_len < min_sym_len || max_sym_len > 32 {
+            return Err(invalid("bad Huffman code lengths in Syzygy table"));
+        }
+        let lowest_sym_at = at;
+
+        // Canonical Huffman: longer codes have lower values, so each length's
+        // lowest code padded to 64 bits marks where its codes begin.
+        let lengths = (max_sym_len - min_sym_len) as usize + 1;
+        let mut base64 = vec![0u64; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = self.u16_le(lowest_sym_at + 2 * i) as u64;
+            let next = self.u16_le(lowest_sym_at + 2 * (i + 1)) as u64;
+            base64[i] = (base64[i + 1] + lowest)
+                .checked_sub(next)
+                .ok_or_else(|| invalid("bad Huffman code in Syzygy table"))?
+                / 2;
+        }
+        for (i, base) in base64.iter_mut().enumerate() {
+            *base = base.checked_shl(64 - i as u32 - min_sym_len as u32).unwrap_or(0);
+        }
+        at += lengths * 2;
+
+        let symbols = self.u16_le(at) as usize;
+        at += 2;
+        let btree_at = at;
+        if btree_at + symbols * 3 > self.mmap.len() {
+            return Err(invalid("truncated Syzygy table"));
+        }
+        let mut symlen = vec![0u8; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                symlen[sym] = self.symlen(btree_at, sym, &mut symlen, &mut visited)?;
+            }
+        }
+        at += symbols * 3 + (symbols & 1);
+
+        let d = &mut self.items[side][file];
+        d.flags = flags;
+        d.min_sym_len = min_sym_len;
+        d.num_blocks = num_blocks;
+        d.block_size = block_size;
+        d.span = span;
+        d.lowest_sym_at = lowest_sym_at;
+        d.btree_at = btree_at;
+        // Padded so the sparse index never points past the end.
+        d.block_length_size = num_blocks as usize + padding;
+        d.sparse_index_size = (tb_size as usize).div_ceil(span);
+        d.base64 = base64;
+        d.symlen = symlen;
+        Ok(at)
+    }
+
+    /// Left and right halves of the pair that `sym` stands for.
+    fn pair(&self, btree_at: usize, sym: usize) -> (usize, usize) {
+        let at = btree_at + 3 * sym;
+        let (a, b, c) = (self.byte(at) as usize, self.byte(at + 1) as usize, self.byte(at + 2) as usize);
+        (((b & 0xF) << 8) | a, (c << 4) | (b >> 4))
+    }
+
+    /// Number of values, minus one, that `sym` expands to.
+    fn symlen(&self, btree_at: usize, sym: usize, symlen: &mut [u8], visited: &mut [bool]) -> io::Result<u8> {
+        visited[sym] = true;
+        let (left, right) = self.pair(btree_at, sym);
+        if right == 0xFFF {
+            return Ok(0);
+        }
+        if left >= symlen.len() || right >= symlen.len() {
+            return Err(invalid("bad symbol in Syzygy table"));
+        }
+        for half in [left, right] {
+            if !visited[half] {
+                symlen[half] = self.symlen(btree_at, half, symlen, visited)?;
+            }
+        }
+        Ok((symlen[left] as usize + symlen[right] as usize + 1) as u8)
+    }
+
+    fn set_dtz_map(&mut self, mut at: usize) -> io::Result<usize> {
+        self.map_at = at;
+        for file in 0..self.files() {
+            let flags = self.items[0][file].flags;
+            if flags & MAPPED == 0 {
+                continue;
+            }
+            let mut map_idx = [0; 4];
+            if flags & WIDE != 0 {
+                at += at & 1;
+                for idx in &mut map_idx {
+                    *idx = (at - self.map_at) / 2 + 1;
+                    at += 2 * self.u16_le(at) as usize + 2;
+                }
+            } else {
+                for idx in &mut map_idx {
+                    *idx = at - self.map_at + 1;
+                    at += self.byte(at) as usize + 1;
+                }
+            }
+            self.items[0][file].map_idx = map_idx;
+        }
+        Ok(at + (at & 1))
+    }
+
+    /// The value stored at `idx`.
+    fn decompress(&self, d: &PairsData, idx: u64) -> usize {
+        if d.flags & SINGLE_VALUE != 0 {
+            return d.min_sym_len as usize;
+        }
+
+        // Sparse entry k points at the value with index k * span + span / 2;
+        // walk from there to the block holding `idx`.
+        let k = (idx / d.span as u64) as usize;
+        let entry = d.sparse_index_at + 6 * k;
+        let mut block = self.u32_le(entry) as usize;
+        let mut offset = self.u16_le(entry + 4) as i64;
+        offset += (idx % d.span as u64) as i64 - (d.span / 2) as i64;
+        let block_length = |block: usize| self.u16_le(d.block_length_at + 2 * block) as i64;
+        while offset < 0 {
+            block -= 1;
+            offset += block_length(block) + 1;
+        }
+        while offset > block_length(block) {
+            offset -= block_length(block) + 1;
+            block += 1;
+        }
+
+        // Read symbols until reaching the one that covers `offset`.
+        let mut at = d.data_at + block * d.block_size;
+        let mut buf = (self.u32_be(at) as u64) << 32 | self.u32_be(at + 4) as u64;
+        at += 8;
+        let mut buf_size = 64;
+        let min_sym_len = d.min_sym_len as usize;
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buf < d.base64[len] {
+                len += 1;
+            }
+            sym = ((buf - d.base64[len]) >> (64 - len - min_sym_len)) as usize;
+            sym += self.u16_le(d.lowest_sym_at + 2 * len) as usize;
+            let count = d.symlen[sym] as i64 + 1;
+            if offset < count {
+                break;
+            }
+            offset -= count;
+            let len = len + min_sym_len;
+            buf <<= len;
+            buf_size -= len;
+            if buf_size <= 32 {
+                buf_size += 32;
+                buf |= (self.u32_be(at) as u64) << (64 - buf_size);
+                at += 4;
+            }
+        }
+
+        // Expand the pairs down to the single value at `offset`.
+        while d.symlen[sym] != 0 {
+            let (left, right) = self.pair(d.btree_at, sym);
+            let count = d.symlen[left] as i64 + 1;
+            if offset < count {
+                sym = left;
+            } else {
+                offset -= count;
+                sym = right;
+            }
+        }
+        self.pair(d.btree_at, sym).0
+    }
+
+    /// Looks up `board`, whose material matches this table with the colours
+    /// swapped if `swapped` is set. WDL tables give the score from -2 to 2;
+    /// DTZ tables give the distance in plies, or `None` if only the other
+    /// side to move is stored.
+    pub(super) fn probe(&self, board: &Board, swapped: bool, wdl: i32) -> Option<i32> {
+        let c = consts();
+        // Symmetric material is only stored with white to move.
+        let flip = swapped || (self.symmetric && board.side_to_move == Side::BLACK);
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let stm = flip as usize ^ board.side_to_move as usize;
+
+        let code = |square: usize| {
+            let black = board.side_at(square) == Some(Side::BLACK);
+            (board.arr[square] | if black { 8 } else { 0 }) ^ flip_color
+        };
+        let mut squares = [0; MAX_PIECES];
+        let mut pieces = [0; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut file = 0;
+        if self.has_pawns {
+            // The leading pawns come first, and the one nearest the edge on
+            // the lowest rank decides the file.
+            let lead = self.items[0][0].pieces[0] ^ flip_color;
+            let side = if lead & 8 != 0 { Side::BLACK } else { Side::WHITE };
+            lead_pawns = board.pawns[side];
+            let mut bits = lead_pawns;
+            while bits != 0 {
+                squares[size] = bits.trailing_zeros() as usize ^ flip_squares;
+                size += 1;
+                bits &= bits - 1;
+            }
+            let best = (0..size).rev().max_by_key(|&i| c.map_pawns[squares[i]]).expect("a leading pawn");
+            squares.swap(0, best);
+            file = (squares[0] % 8).min(7 - squares[0] % 8);
+        }
+        let lead_count = size;
+
+        // DTZ tables hold one side to move, except for symmetric pawnless material.
+        if self.kind == Kind::Dtz
+            && (self.has_pawns || !self.symmetric)
+            && (self.item(stm, file).flags & STM) as usize != stm
+        {
+            return None;
+        }
+
+        let mut bits = board.occupied ^ lead_pawns;
+        while bits != 0 {
+            let square = bits.trailing_zeros() as usize;
+            squares[size] = square ^ flip_squares;
+            pieces[size] = code(square);
+            size += 1;
+            bits &= bits - 1;
+        }
+
+        let d = self.item(stm, file);
+        for i in lead_count..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|&j| d.pieces[i] == pieces[j]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+
+        if squares[0] % 8 > 3 {
+            for square in &mut squares[..size] {
+                *square ^= 7;
+            }
+        }
+
+        let mut idx;
+        if self.has_pawns {
+            idx = c.lead_pawn_idx[lead_count][squares[0]];
+            squares[1..lead_count].sort_by_key(|&square| c.map_pawns[square]);
+            for (i, &square) in squares.iter().enumerate().take(lead_count).skip(1) {
+                idx += c.binomial[i][c.map_pawns[square]];
+            }
+        } else {
+            if squares[0] / 8 > 3 {
+                for square in &mut squares[..size] {
+                    *square ^= 56;
+                }
+            }
+            // Mirror along the a1-h8 diagonal so that the first leading piece
+            // off the diagonal is below it.
+            if let Some(i) = (0..d.group_len[0]).find(|&i| off_a1h8(squares[i]) != 0)
+                && off_a1h8(squares[i]) > 0
+            {
+                for square in &mut squares[i..size] {
+                    *square = ((*square >> 3) | (*square << 3)) & 63;
+                }
+            }
+            idx = if self.has_unique_pieces {
+                self.unique_pieces_index(&squares)
+            } else {
+                c.map_kk[c.map_a1d1d4[squares[0]]][squares[1]] as u64
+            };
+        }
+
+        idx *= d.group_idx[0];
+        let mut start = d.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_counts[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let len = d.group_len[next];
+            squares[start..start + len].sort_unstable();
+            let mut n = 0;
+            for i in 0..len {
+                let square = squares[start + i];
+                // Skip the squares taken by earlier groups.
+                let adjust = squares[..start].iter().filter(|&&s| square > s).count();
+                n += c.binomial[i + 1][square - adjust - if remaining_pawns { 8 } else { 0 }];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            start += len;
+            next += 1;
+        }
+
+        let value = self.decompress(d, idx) as i32;
+        Some(match self.kind {
+            Kind::Wdl => value - 2,
+            Kind::Dtz => self.map_dtz(file, value, wdl),
+        })
+    }
+
+    /// Index of three unique leading pieces, the first in the a1-d1-d4 triangle.
+    fn unique_pieces_index(&self, squares: &[usize; MAX_PIECES]) -> u64 {
+        let c = consts();
+        let rank = |square: usize| (square / 8) as u64;
+        let adjust1 = (squares[1] > squares[0]) as usize;
+        let adjust2 = (squares[2] > squares[0]) as usize + (squares[2] > squares[1]) as usize;
+        if off_a1h8(squares[0]) != 0 {
+            ((c.map_a1d1d4[squares[0]] * 63 + squares[1] - adjust1) * 62 + squares[2] - adjust2) as u64
+        } else if off_a1h8(squares[1]) != 0 {
+            (6 * 63 + rank(squares[0]) * 28 + c.map_b1h1h7[squares[1]] as u64) * 62
+                + (squares[2] - adjust2) as u64
+        } else if off_a1h8(squares[2]) != 0 {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + rank(squares[0]) * 7 * 28
+                + (rank(squares[1]) - adjust1 as u64) * 28
+                + c.map_b1h1h7[squares[2]] as u64
+        } else {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + 4 * 7 * 28
+                + rank(squares[0]) * 7 * 6
+                + (rank(squares[1]) - adjust1 as u64) * 6
+                + (rank(squares[2]) - adjust2 as u64)
+        }
+    }
+
+    /// Turns a stored DTZ value into plies. Values are stored per result,
+    /// possibly remapped by frequency and possibly in full moves.
+    fn map_dtz(&self, file: usize, mut value: i32, wdl: i32) -> i32 {
+        let d = self.item(0, file);
+        if d.flags & MAPPED != 0 {
+            let idx = d.map_idx[[1, 3, 0, 2, 0][(wdl + 2) as usize]] + value as usize;
+            value = if d.flags & WIDE != 0 {
+                self.u16_le(self.map_at + 2 * idx) as i32
+            } else {
+                self.byte(self.map_at + idx) as i32
+            };
+        }
+        let in_moves = match wdl {
+            2 => d.flags & WIN_PLIES == 0,
+            -2 => d.flags & LOSS_PLIES == 0,
+            _ => true,
+        };
+        if in_moves {
+            value *= 2;
+        }
+        value + 1
+    }
+}
@@ -0,0 +1,291 @@
+//! Matches between two UCI engines running as local processes.
+//!
+//! Every opening is played twice with colors swapped. Games end by the rules
+//! (see `Board::outcome`), on time, or when an engine sends an illegal move or
+//! stops responding, which loses the game.
+
+pub mod sprt;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::engine::{BestMove, EngineError, UciEngine};
+use crate::pgn::{Game, GameResult, PgnReader};
+use crate::search::Limits;
+use crate::{Board, Move, BLACK, STARTPOS, WHITE};
+use sprt::{MatchScore, Sprt, SprtStatus};
+
+#[derive(Clone, Debug, Default)]
+pub struct EngineConfig {
+    /// Name for the PGN; the engine's `id name` if `None`.
+    pub name: Option<String>,
+    pub command: String,
+    pub args: Vec<String>,
+    /// UCI options set after the handshake.
+    pub options: Vec<(String, String)>,
+}
+
+/// A clock of `base` plus `increment` per move, written like `10+0.1` in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (base, increment) = s.split_once('+').unwrap_or((s, "0"));
+        let seconds = |field: &str| {
+            field
+                .parse::<f64>()
+                .ok()
+                .filter(|&seconds| seconds >= 0.0)
+                .map(Duration::from_secs_f64)
+                .ok_or_else(|| format!("invalid time control '{s}'"))
+        };
+        Ok(Self {
+            base: seconds(base)?,
+            increment: seconds(increment)?,
+        })
+    }
+}
+
+impl std::fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}+{}", self.base.as_secs_f64(), self.increment.as_secs_f64())
+    }
+}
+
+/// A start position and the moves played from it before the engines take over.
+#[derive(Clone)]
+pub struct Opening {
+    pub start: Board,
+    pub moves: Vec<Move>,
+}
+
+/// Reads openings from a PGN file, taking each game's moves, or from an EPD
+/// or FEN file with one position per line.
+pub fn load_openings(path: impl AsRef<Path>) -> io::Result<Vec<Opening>> {
+    let path = path.as_ref();
+    let invalid = |err: String| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {err}", path.display()));
+    let mut openings = Vec::new();
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pgn")) {
+        for game in PgnReader::new(BufReader::new(File::open(path)?)) {
+            let game = game?;
+            let start = game.start().map_err(|err| invalid(err.to_string()))?;
+            let mut moves = Vec::new();
+            game.replay(|_, mv| moves.push(*mv)).map_err(|err| invalid(err.to_string()))?;
+            openings.push(Opening { start, moves });
+        }
+    } else {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 4 {
+                continue;
+            }
+            // EPD has operations instead of the clocks.
+            let clocks = fields.len() >= 6 && fields[4..6].iter().all(|f| f.parse::<u16>().is_ok());
+            let fen = if clocks { fields[..6].join(" ") } else { format!("{} 0 1", fields[..4].join(" ")) };
+            let start = Board::from_fen(&fen).map_err(|err| invalid(format!("{err} in '{line}'")))?;
+            openings.push(Opening {
+                start,
+                moves: Vec::new(),
+            });
+        }
+    }
+    Ok(openings)
+}
+
+/// An engine process and the name it plays under.
+struct Player {
+    name: String,
+    engine: UciEngine,
+}
+
+impl Player {
+    fn start(config: &EngineConfig) -> io::Result<Self> {
+        let mut engine = UciEngine::spawn(&config.command, &config.args).map_err(io::Error::other)?;
+        for (name, value) in &config.options {
+            engine.set_option(name, value).map_err(io::Error::other)?;
+        }
+        engine.is_ready().map_err(io::Error::other)?;
+        let name = config.name.clone().or_else(|| engine.name().map(String::from));
+        Ok(Self {
+            name: name.unwrap_or_else(|| config.command.clone()),
+            engine,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct MatchConfig {
+    pub engines: [EngineConfig; 2],
+    pub time_control: TimeControl,
+    /// Time an engine may exceed its clock by before it loses on time.
+    pub time_margin: Duration,
+    /// Played in order and reused from the start when they run out.
+    pub openings: Vec<Opening>,
+    /// Total games, rounded up to whole pairs.
+    pub games: usize,
+    /// Games played at the same time, each by its own pair of processes.
+    pub concurrency: usize,
+    /// Stops the match once the test has accepted a hypothesis.
+    pub sprt: Option<Sprt>,
+    pub event: String,
+}
+
+/// Plays the match, calling `on_game` with each finished game and the score
+/// so far, in the order the games finish.
+pub fn run_match(config: &MatchConfig, mut on_game: impl FnMut(&Game, &MatchScore)) -> io::Result<MatchScore> {
+    if config.openings.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "no openings"));
+    }
+    // Fail early if either engine does not start.
+    for engine in &config.engines {
+        Player::start(engine)?;
+    }
+
+    let pairs = config.games.div_ceil(2);
+    let next_pair = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    let mut score = MatchScore::default();
+    std::thread::scope(|scope| {
+        let mut workers = Vec::new();
+        for _ in 0..config.concurrency.clamp(1, pairs.max(1)) {
+            let (next_pair, stop, sender) = (&next_pair, &stop, sender.clone());
+            workers.push(scope.spawn(move || -> io::Result<()> {
+                let mut engines = [Player::start(&config.engines[0])?, Player::start(&config.engines[1])?];
+                loop {
+                    let pair = next_pair.fetch_add(1, Ordering::Relaxed);
+                    if pair >= pairs || stop.load(Ordering::Relaxed) {
+                        return Ok(());
+                    }
+                    let opening = &config.openings[pair % config.openings.len()];
+                    for (game, first) in [(2 * pair, WHITE), (2 * pair + 1, BLACK)] {
+                        // An engine that crashed or hung is replaced before the next game.
+                        for (engine, engine_config) in engines.iter_mut().zip(&config.engines) {
+                            if engine.engine.new_game().is_err() {
+                                *engine = Player::start(engine_config)?;
+                            }
+                        }
+                        let [a, b] = &mut engines;
+                        let players = if first == WHITE { [a, b] } else { [b, a] };
+                        let record = play_game(players, opening, config, game + 1);
+                        if sender.send((first, record)).is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+            }));
+        }
+        drop(sender);
+
+        for (first, game) in receiver {
+            match (game.result, first) {
+                (Some(GameResult::Draw), _) | (None, _) => score.draws += 1,
+                (Some(GameResult::WhiteWins), WHITE) | (Some(GameResult::BlackWins), BLACK) => score.wins += 1,
+                _ => score.losses += 1,
+            }
+            on_game(&game, &score);
+            if config.sprt.is_some_and(|sprt| sprt.status(&score) != SprtStatus::Continue) {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+        workers.into_iter().try_for_each(|worker| worker.join().expect("match worker panicked"))
+    })?;
+    Ok(score)
+}
+
+/// Plays one game between `players`, white first, and returns its record.
+fn play_game(players: [&mut Player; 2], opening: &Opening, config: &MatchConfig, round: usize) -> Game {
+    let mut board = opening.start.clone();
+    let start_fen = board.to_fen();
+    let mut sans = Vec::new();
+    for mv in &opening.moves {
+        sans.push(board.san(mv));
+        board.make_move(mv);
+    }
+    let mut moves = opening.moves.clone();
+
+    let TimeControl { base, increment } = config.time_control;
+    let mut clocks = [base; 2];
+    let (result, termination) = loop {
+        if let Some(outcome) = board.outcome() {
+            break (outcome.result(), "normal");
+        }
+        let side = board.side_to_move();
+        let loss = if side == WHITE { GameResult::BlackWins } else { GameResult::WhiteWins };
+        let engine = &mut players[side as usize].engine;
+        let limits = Limits {
+            time: Some(clocks[side]),
+            increment,
+            ..Limits::default()
+        };
+        let started = Instant::now();
+        let reply = engine
+            .set_position(&opening.start, &moves)
+            .and_then(|()| engine.go(&limits, Some(clocks[side] + config.time_margin), |_| {}));
+        let elapsed = started.elapsed();
+        let mv = match reply {
+            Ok(BestMove { best: Some(mv), .. }) => mv,
+            // No move when the game is not over is as bad as an illegal one.
+            Ok(_) | Err(EngineError::IllegalMove(_)) => break (loss, "rules infraction"),
+            Err(EngineError::Timeout) => break (loss, "time forfeit"),
+            Err(_) => break (loss, "abandoned"),
+        };
+        if elapsed > clocks[side] + config.time_margin {
+            break (loss, "time forfeit");
+        }
+        clocks[side] = clocks[side].saturating_sub(elapsed) + increment;
+        sans.push(board.san(&mv));
+        board.make_move(&mv);
+        moves.push(mv);
+    };
+
+    let mut tags = vec![
+        ("Event".to_string(), config.event.clone()),
+        ("Site".to_string(), "?".to_string()),
+        ("Date".to_string(), today()),
+        ("Round".to_string(), round.to_string()),
+        ("White".to_string(), players[WHITE].name.clone()),
+        ("Black".to_string(), players[BLACK].name.clone()),
+        ("Result".to_string(), result.to_string()),
+        ("TimeControl".to_string(), config.time_control.to_string()),
+        ("Termination".to_string(), termination.to_string()),
+    ];
+    if start_fen != STARTPOS {
+        tags.push(("SetUp".to_string(), "1".to_string()));
+        tags.push(("FEN".to_string(), start_fen));
+    }
+    Game {
+        tags,
+        moves: sans,
+        annotations: Vec::new(),
+        result: Some(result),
+    }
+}
+
+/// Today's date in PGN's `YYYY.MM.DD` form, in UTC.
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86_400) as i64;
+    // Days to civil date, after Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{year:04}.{month:02}.{day:02}")
+}
@@ -0,0 +1,126 @@
+//! Elo estimates and the sequential probability ratio test.
+//!
+//! The SPRT uses the usual normal approximation to the generalized SPRT with
+//! logistic Elo: with mean score `s`, per-game variance `var` over `n` games and
+//! `s0`, `s1` the expected scores at `elo0` and `elo1`,
+//! `LLR = n (s1 - s0) (2s - s0 - s1) / (2 var)`. The variance is taken to be
+//! at least `1 / (4n)`, about what one game with a different result would add,
+//! so that a run of identical results still ends the test.
+
+use std::fmt;
+
+/// Results from the first engine's point of view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Mean score per game and its variance, `None` before the first game.
+    fn mean_and_variance(&self) -> Option<(f64, f64)> {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return None;
+        }
+        let (w, d) = (self.wins as f64 / n, self.draws as f64 / n);
+        let mean = w + d / 2.0;
+        Some((mean, w + d / 4.0 - mean * mean))
+    }
+
+    /// Elo difference and the half-width of its 95% confidence interval, or
+    /// `None` while all games are won or all are lost.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let (mean, variance) = self.mean_and_variance()?;
+        if mean <= 0.0 || mean >= 1.0 {
+            return None;
+        }
+        let margin = 1.959964 * (variance / self.games() as f64).sqrt();
+        let low = elo_from_score((mean - margin).max(1e-6));
+        let high = elo_from_score((mean + margin).min(1.0 - 1e-6));
+        Some((elo_from_score(mean), (high - low) / 2.0))
+    }
+}
+
+impl fmt::Display for MatchScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} ={} -{}", self.wins, self.draws, self.losses)?;
+        match self.elo() {
+            Some((elo, margin)) => write!(f, ", Elo {elo:+.1} +/- {margin:.1}"),
+            None => Ok(()),
+        }
+    }
+}
+
+fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Chance of accepting H1 when H0 holds.
+    pub alpha: f64,
+    /// Chance of accepting H0 when H1 holds.
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtStatus {
+    Continue,
+    /// The Elo difference is more likely `elo0` than `elo1`.
+    AcceptH0,
+    /// The Elo difference is more likely `elo1` than `elo0`.
+    AcceptH1,
+}
+
+impl Sprt {
+    /// The LLR bounds at which H0 and H1 are accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    /// Log-likelihood ratio of H1 (Elo difference `elo1`) against H0 (`elo0`).
+    pub fn llr(&self, score: &MatchScore) -> f64 {
+        let Some((mean, variance)) = score.mean_and_variance() else {
+            return 0.0;
+        };
+        let n = score.games() as f64;
+        let variance = variance.max(0.25 / n);
+        let (s0, s1) = (score_from_elo(self.elo0), score_from_elo(self.elo1));
+        n * (s1 - s0) * (2.0 * mean - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn status(&self, score: &MatchScore) -> SprtStatus {
+        let (lower, upper) = self.bounds();
+        let llr = self.llr(score);
+        if llr >= upper {
+            SprtStatus::AcceptH1
+        } else if llr <= lower {
+            SprtStatus::AcceptH0
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
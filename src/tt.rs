@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::movegen::Move;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact = 0,
+    /// The score is at least this high (fail high).
+    Lower = 1,
+    /// The score is at most this high (fail low).
+    Upper = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TtEntry {
+    pub mv: Move,
+    pub score: i16,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+impl TtEntry {
+    // bits 0-31: move
+    // bits 32-47: score
+    // bits 48-55: depth
+    // bits 56-57: bound
+    fn pack(&self) -> u64 {
+        self.mv.raw() as u64
+            | (self.score as u16 as u64) << 32
+            | (self.depth as u64) << 48
+            | (self.bound as u64) << 56
+    }
+    fn unpack(data: u64) -> Self {
+        Self {
+            mv: Move::from_raw(data as u32),
+            score: (data >> 32) as u16 as i16,
+            depth: (data >> 48) as u8,
+            bound: match (data >> 56) & 0b11 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    // Stored as `key ^ data` so a slot torn by two threads writing at once is
+    // detected as a miss instead of returning another position's data.
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Transposition table shared by all search threads without locking.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    mask: usize,
+}
+
+impl TranspositionTable {
+    pub fn new(mb: usize) -> Self {
+        let bytes = mb.max(1) * 1024 * 1024;
+        // Round down to a power of two so indexing is a mask.
+        let len = 1 << (bytes / std::mem::size_of::<Slot>()).max(1).ilog2();
+        let mut slots = Vec::with_capacity(len);
+        slots.resize_with(len, Slot::default);
+        Self {
+            slots,
+            mask: len - 1,
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[key as usize & self.mask]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        let stored = slot.key.load(Ordering::Relaxed);
+        (stored ^ data == key && data != 0).then(|| TtEntry::unpack(data))
+    }
+
+    pub fn store(&self, key: u64, entry: TtEntry) {
+        let slot = self.slot(key);
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let same_position = slot.key.load(Ordering::Relaxed) ^ old_data == key;
+        // Prefer keeping deeper results for the same position, but always let a
+        // new position in: the old one is likely from an earlier search.
+        if same_position && entry.bound != Bound::Exact {
+            let old = TtEntry::unpack(old_data);
+            if old.depth > entry.depth + 2 {
+                return;
+            }
+        }
+        let mut entry = entry;
+        if entry.mv.is_null() && same_position {
+            entry.mv = TtEntry::unpack(old_data).mv;
+        }
+        let data = entry.pack();
+        slot.data.store(data, Ordering::Relaxed);
+        slot.key.store(key ^ data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Permille of the first thousand slots in use, as reported by UCI `hashfull`.
+    pub fn hashfull(&self) -> usize {
+        let sample = self.slots.len().min(1000);
+        let used = self.slots[..sample]
+            .iter()
+            .filter(|s| s.data.load(Ordering::Relaxed) != 0)
+            .count();
+        used * 1000 / sample
+    }
+}
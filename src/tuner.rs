@@ -0,0 +1,324 @@
+//! Texel-style tuning of the hand-written evaluation.
+//!
+//! Every position is traced once (see `Board::trace`) into a sparse list of
+//! parameter coefficients, after which evaluating it with a new set of weights is
+//! a dot product. The error is the mean squared difference between the game result
+//! and `sigmoid(eval)`, minimized with Adam over all tunable parameters.
+
+use std::io;
+use std::path::Path;
+
+use crate::eval::{EvalWeights, Score, MAX_PHASE};
+use crate::Board;
+
+/// A traced training position.
+#[derive(Clone, Debug)]
+pub struct Position {
+    /// Game phase, clamped to `0..=MAX_PHASE`.
+    pub phase: u8,
+    /// Result from white's point of view: 1 for a win, 0.5 for a draw, 0 for a loss.
+    pub result: f32,
+    /// Non-zero `(parameter index, white count - black count)` pairs.
+    pub coefficients: Box<[(u16, i16)]>,
+}
+
+fn parse_result(token: &str) -> Option<f32> {
+    let token = token.trim_matches(['[', ']', '"', ';', '(', ')']);
+    match token {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" | "1/2" => Some(0.5),
+        // Require a decimal point so a trailing fullmove number is never taken as a result.
+        _ if token.contains('.') => token.parse().ok().filter(|r| (0.0..=1.0).contains(r)),
+        _ => None,
+    }
+}
+
+/// Splits a line into FEN and result. Accepts EPD with a `c9` opcode
+/// (`<epd> c9 "1-0";`) or a FEN followed by a result such as `1-0`, `[0.5]` or `0.0`.
+pub fn parse_line(line: &str) -> Option<(String, f32)> {
+    let line = line.trim();
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    if let Some(i) = fields.iter().position(|&f| f == "c9") {
+        let result = parse_result(fields.get(i + 1)?)?;
+        return Some((fields.get(..4)?.join(" "), result));
+    }
+    let (&last, fen) = fields.split_last()?;
+    let result = parse_result(last)?;
+    let fen = fen
+        .iter()
+        .map(|f| f.trim_end_matches([';', ',', '|']))
+        .filter(|f| !f.is_empty())
+        .collect::<Vec<_>>();
+    Some((fen.join(" "), result))
+}
+
+impl Position {
+    /// Traces the position `board` is set up in, which must not be a known
+    /// endgame; see `Board::is_known_endgame`.
+    pub fn new(board: &Board, result: f32) -> Self {
+        debug_assert!(!board.is_known_endgame(), "{} is a known endgame", board.to_fen());
+        let params = board.trace().params();
+        let coefficients = params
+            .iter()
+            .enumerate()
+            .filter(|(_, [white, black])| white != black)
+            .map(|(i, [white, black])| (i as u16, (white - black) as i16))
+            .collect();
+        Self {
+            phase: board.psqt().phase.clamp(0, MAX_PHASE) as u8,
+            result,
+            coefficients,
+        }
+    }
+}
+
+/// Reads and traces every labeled position in `path` on `threads` threads.
+/// Returns the positions and the number of lines skipped: those that could not
+/// be parsed and known endgames, whose evaluation the weights do not decide.
+pub fn load_positions(path: impl AsRef<Path>, threads: usize) -> io::Result<(Vec<Position>, usize)> {
+    let text = std::fs::read_to_string(path)?;
+    let lines = text.lines().filter(|l| !l.trim().is_empty()).collect::<Vec<_>>();
+    let chunk = lines.len().div_ceil(threads.max(1)).max(1);
+    let results = std::thread::scope(|s| {
+        let handles = lines
+            .chunks(chunk)
+            .map(|lines| {
+                s.spawn(move || {
+                    let mut board = Board::new();
+                    let mut positions = Vec::with_capacity(lines.len());
+                    let mut skipped = 0;
+                    for line in lines {
+                        match parse_line(line) {
+                            Some((fen, result)) if board.set_fen(&fen).is_ok() && !board.is_known_endgame() => {
+                                positions.push(Position::new(&board, result));
+                            }
+                            _ => skipped += 1,
+                        }
+                    }
+                    (positions, skipped)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("loader thread panicked"))
+            .collect::<Vec<_>>()
+    });
+    let skipped = results.iter().map(|(_, skipped)| skipped).sum();
+    let positions = results.into_iter().flat_map(|(p, _)| p).collect();
+    Ok((positions, skipped))
+}
+
+fn sigmoid(k: f64, eval: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+pub struct Tuner {
+    positions: Vec<Position>,
+    base: EvalWeights,
+    /// Current `[mg, eg]` value of every tunable parameter.
+    params: Vec<[f64; 2]>,
+    threads: usize,
+    /// Scaling constant of the sigmoid; see `optimize_k`.
+    pub k: f64,
+    // Adam state.
+    m: Vec<[f64; 2]>,
+    v: Vec<[f64; 2]>,
+    t: i32,
+}
+
+impl Tuner {
+    pub fn new(positions: Vec<Position>, base: EvalWeights, threads: usize) -> Self {
+        let params = base
+            .params()
+            .into_iter()
+            .map(|s| [s.mg as f64, s.eg as f64])
+            .collect::<Vec<_>>();
+        let len = params.len();
+        Self {
+            positions,
+            base,
+            params,
+            threads: threads.max(1),
+            k: 1.0,
+            m: vec![[0.0; 2]; len],
+            v: vec![[0.0; 2]; len],
+            t: 0,
+        }
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    /// White-relative evaluation of `pos` with the current parameters.
+    pub fn evaluate(&self, pos: &Position) -> f64 {
+        let (mut mg, mut eg) = (0.0, 0.0);
+        for &(i, c) in pos.coefficients.iter() {
+            let [p_mg, p_eg] = self.params[i as usize];
+            mg += p_mg * c as f64;
+            eg += p_eg * c as f64;
+        }
+        let phase = pos.phase as f64;
+        let max = MAX_PHASE as f64;
+        (mg * phase + eg * (max - phase)) / max
+    }
+
+    /// Runs `f` over chunks of the positions in parallel and collects the results.
+    fn map_chunks<T: Send>(&self, f: impl Fn(&[Position]) -> T + Sync) -> Vec<T> {
+        let chunk = self.positions.len().div_ceil(self.threads).max(1);
+        std::thread::scope(|s| {
+            let handles = self
+                .positions
+                .chunks(chunk)
+                .map(|positions| s.spawn(|| f(positions)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("tuner thread panicked"))
+                .collect()
+        })
+    }
+
+    /// Mean squared error of the predicted results with scaling constant `k`.
+    pub fn error(&self, k: f64) -> f64 {
+        let total = self
+            .map_chunks(|positions| {
+                positions
+                    .iter()
+                    .map(|pos| (pos.result as f64 - sigmoid(k, self.evaluate(pos))).powi(2))
+                    .sum::<f64>()
+            })
+            .into_iter()
+            .sum::<f64>();
+        total / self.positions.len().max(1) as f64
+    }
+
+    /// Finds the `k` that minimizes the error for the current weights with a golden
+    /// section search, so the tuning starts from a well-calibrated sigmoid.
+    pub fn optimize_k(&mut self) -> f64 {
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut lo, mut hi) = (0.0, 4.0);
+        while hi - lo > 1e-4 {
+            let a = hi - ratio * (hi - lo);
+            let b = lo + ratio * (hi - lo);
+            if self.error(a) < self.error(b) {
+                hi = b;
+            } else {
+                lo = a;
+            }
+        }
+        self.k = (lo + hi) / 2.0;
+        self.k
+    }
+
+    fn gradient(&self) -> Vec<[f64; 2]> {
+        let k = self.k;
+        let partials = self.map_chunks(|positions| {
+            let mut grad = vec![[0.0; 2]; self.params.len()];
+            for pos in positions {
+                let s = sigmoid(k, self.evaluate(pos));
+                // d(error)/d(eval) up to the constant factor applied below.
+                let d = (pos.result as f64 - s) * s * (1.0 - s);
+                let mg = d * pos.phase as f64 / MAX_PHASE as f64;
+                let eg = d * (MAX_PHASE - pos.phase as i32) as f64 / MAX_PHASE as f64;
+                for &(i, c) in pos.coefficients.iter() {
+                    grad[i as usize][0] += mg * c as f64;
+                    grad[i as usize][1] += eg * c as f64;
+                }
+            }
+            grad
+        });
+        let scale = -2.0 * k * 10f64.ln() / 400.0 / self.positions.len().max(1) as f64;
+        let mut grad = vec![[0.0; 2]; self.params.len()];
+        for partial in partials {
+            for (g, p) in grad.iter_mut().zip(partial) {
+                g[0] += p[0] * scale;
+                g[1] += p[1] * scale;
+            }
+        }
+        grad
+    }
+
+    /// One full-batch Adam step with learning rate `lr`, in centipawns.
+    pub fn step(&mut self, lr: f64) {
+        const BETA1: f64 = 0.9;
+        const BETA2: f64 = 0.999;
+        const EPSILON: f64 = 1e-8;
+        let grad = self.gradient();
+        self.t += 1;
+        let correction1 = 1.0 - BETA1.powi(self.t);
+        let correction2 = 1.0 - BETA2.powi(self.t);
+        for (i, g) in grad.iter().enumerate() {
+            for (j, &g) in g.iter().enumerate() {
+                self.m[i][j] = BETA1 * self.m[i][j] + (1.0 - BETA1) * g;
+                self.v[i][j] = BETA2 * self.v[i][j] + (1.0 - BETA2) * g * g;
+                let m = self.m[i][j] / correction1;
+                let v = self.v[i][j] / correction2;
+                self.params[i][j] -= lr * m / (v.sqrt() + EPSILON);
+            }
+        }
+    }
+
+    /// The current parameters rounded to whole centipawns.
+    pub fn weights(&self) -> EvalWeights {
+        let mut weights = self.base.clone();
+        let params = self
+            .params
+            .iter()
+            .map(|[mg, eg]| Score::new(mg.round() as i32, eg.round() as i32))
+            .collect::<Vec<_>>();
+        weights.set_params(&params);
+        weights
+    }
+}
+
+/// Formats `weights` as an `EvalWeights { .. }` initializer that can replace the body
+/// of `EvalWeights::default`. Piece-square tables take two lines per rank, a8 first.
+pub fn to_rust_source(weights: &EvalWeights) -> String {
+    let w = weights;
+    let mut out = String::from("// Generated by the tuner.\nEvalWeights {\n");
+    let mut field = |name: &str, value: String| out.push_str(&format!("    {name}: {value},\n"));
+    field("material", score_array(&w.material, 1));
+    let pst: Vec<String> = w.pst.iter().map(|table| score_array(table, 2)).collect();
+    field("pst", format!("[\n        {},\n    ]", pst.join(",\n        ")));
+    field("tempo", score(w.tempo));
+    field("doubled", score(w.doubled));
+    field("isolated", score(w.isolated));
+    field("backward", score(w.backward));
+    field("connected", score_array(&w.connected, 1));
+    field("passed", score_array(&w.passed, 1));
+    field("passed_free_path", score_array(&w.passed_free_path, 1));
+    field("passed_own_king_distance", score(w.passed_own_king_distance));
+    field("passed_enemy_king_distance", score(w.passed_enemy_king_distance));
+    field("candidate", score_array(&w.candidate, 1));
+    field("pawn_island", score(w.pawn_island));
+    field("mobility_knight", score_array(&w.mobility_knight, 1));
+    field("mobility_bishop", score_array(&w.mobility_bishop, 1));
+    field("mobility_rook", score_array(&w.mobility_rook, 1));
+    field("mobility_queen", score_array(&w.mobility_queen, 1));
+    field("king_danger", score_array(&w.king_danger, 1));
+    field("pawn_shield", score_array(&w.pawn_shield, 1));
+    field("pawn_storm", score_array(&w.pawn_storm, 1));
+    field("king_semi_open_file", score(w.king_semi_open_file));
+    field("king_open_file", score(w.king_open_file));
+    field("phase", format!("{:?}", w.phase));
+    field("king_attack_weight", format!("{:?}", w.king_attack_weight));
+    out.push_str("}\n");
+    out
+}
+
+fn score(score: Score) -> String {
+    format!("Score::new({}, {})", score.mg, score.eg)
+}
+
+/// An array literal with four scores a line, nested `depth` levels into the initializer.
+fn score_array(scores: &[Score], depth: usize) -> String {
+    let indent = "    ".repeat(depth);
+    let lines: Vec<String> = scores
+        .chunks(4)
+        .map(|chunk| chunk.iter().map(|&s| score(s)).collect::<Vec<_>>().join(", "))
+        .collect();
+    format!("[\n{indent}    {},\n{indent}]", lines.join(&format!(",\n{indent}    ")))
+}
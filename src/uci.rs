@@ -0,0 +1,276 @@
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::book::Book;
+use crate::endgame::Endgames;
+use crate::search::{mate_in, Bound, Limits, SearchInfo, Searcher};
+use crate::syzygy::Tablebase;
+use crate::{Board, Side, Variant};
+
+const DEFAULT_HASH_MB: usize = 16;
+
+/// Runs the UCI protocol on stdin/stdout until `quit` or end of input.
+pub fn run() {
+    let mut uci = Uci::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !uci.handle(&line) {
+            uci.stop();
+            return;
+        }
+    }
+    // Let a search started by piped input finish.
+    uci.searcher();
+}
+
+struct Uci {
+    board: Board,
+    /// `None` while a search thread owns it.
+    searcher: Option<Searcher>,
+    search: Option<JoinHandle<Searcher>>,
+    stop: Arc<AtomicBool>,
+    multipv: usize,
+    book: Option<Book>,
+    own_book: bool,
+    rng: fastrand::Rng,
+    tablebase: Option<Tablebase>,
+    /// Write castling as the king taking its own rook.
+    chess960: bool,
+    variant: Variant,
+}
+
+impl Uci {
+    fn new() -> Self {
+        let searcher = Searcher::new(1, DEFAULT_HASH_MB);
+        Self {
+            board: Board::new(),
+            stop: searcher.stop_handle(),
+            searcher: Some(searcher),
+            search: None,
+            multipv: 1,
+            book: None,
+            own_book: false,
+            rng: fastrand::Rng::new(),
+            tablebase: None,
+            chess960: false,
+            variant: Variant::Standard,
+        }
+    }
+
+    /// Handles one command, returning false on `quit`.
+    fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+                println!("option name Hash type spin default {DEFAULT_HASH_MB} min 1 max 65536");
+                println!("option name Threads type spin default 1 min 1 max 512");
+                println!("option name MultiPV type spin default 1 min 1 max 256");
+                println!("option name OwnBook type check default false");
+                println!("option name BookFile type string default <empty>");
+                println!("option name SyzygyPath type string default <empty>");
+                println!("option name EndgameTables type check default false");
+                println!("option name UCI_Chess960 type check default false");
+                let vars: Vec<String> = Variant::ALL.iter().map(|variant| format!("var {variant}")).collect();
+                println!("option name UCI_Variant type combo default chess {}", vars.join(" "));
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                self.searcher().clear();
+                self.board = Board::from_variant_fen(self.variant.start_fen(), self.variant).expect("start position is valid");
+            }
+            Some("setoption") => self.set_option(tokens),
+            Some("position") => self.position(tokens),
+            Some("go") => self.go(tokens),
+            Some("stop") => self.stop(),
+            Some("quit") => return false,
+            _ => {}
+        }
+        true
+    }
+
+    /// Waits for a running search to finish and takes the searcher back.
+    fn searcher(&mut self) -> &mut Searcher {
+        if let Some(search) = self.search.take() {
+            self.searcher = Some(search.join().expect("search thread panicked"));
+        }
+        self.searcher.as_mut().expect("searcher is either idle or searching")
+    }
+
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.searcher();
+        // The search may have finished before seeing the flag.
+        self.stop.store(false, Ordering::Relaxed);
+    }
+
+    fn set_option<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) {
+        // setoption name <id> [value <x>], where the name may contain spaces.
+        let tokens: Vec<&str> = tokens.collect();
+        let value_at = tokens.iter().position(|&t| t == "value").unwrap_or(tokens.len());
+        let name = tokens.get(1..value_at).unwrap_or_default().join(" ");
+        let value = tokens.get(value_at + 1..).unwrap_or_default().join(" ");
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => match value.parse() {
+                Ok(mb) => self.searcher().set_hash(mb),
+                Err(_) => println!("info string invalid Hash value '{value}'"),
+            },
+            "threads" => match value.parse() {
+                Ok(threads) => self.searcher().set_threads(threads),
+                Err(_) => println!("info string invalid Threads value '{value}'"),
+            },
+            "multipv" => match value.parse() {
+                Ok(multipv) => self.multipv = multipv,
+                Err(_) => println!("info string invalid MultiPV value '{value}'"),
+            },
+            "ownbook" => self.own_book = value == "true",
+            "bookfile" if value.is_empty() || value == "<empty>" => self.book = None,
+            "bookfile" => match Book::load(&value) {
+                Ok(book) => self.book = Some(book),
+                Err(err) => {
+                    println!("info string could not load book '{value}': {err}");
+                    self.book = None;
+                }
+            },
+            "syzygypath" if value.is_empty() || value == "<empty>" => self.tablebase = None,
+            "syzygypath" => match Tablebase::open(&value) {
+                Ok(tablebase) => {
+                    println!("info string found tablebases with up to {} pieces", tablebase.max_pieces());
+                    self.tablebase = Some(tablebase);
+                }
+                Err(err) => {
+                    println!("info string could not read tablebases '{value}': {err}");
+                    self.tablebase = None;
+                }
+            },
+            "uci_chess960" => self.chess960 = value == "true",
+            "uci_variant" => match value.parse() {
+                Ok(variant) => self.variant = variant,
+                Err(err) => println!("info string {err}"),
+            },
+            "endgametables" => {
+                let endgames = (value == "true").then(|| Arc::new(Endgames::standard()));
+                self.searcher().set_endgames(endgames);
+            }
+            _ => println!("info string unknown option '{name}'"),
+        }
+    }
+
+    fn position<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        let fen = match tokens.next() {
+            Some("startpos") => {
+                tokens.next();
+                self.variant.start_fen().to_string()
+            }
+            Some("fen") => tokens.by_ref().take_while(|&t| t != "moves").collect::<Vec<_>>().join(" "),
+            _ => return,
+        };
+        let mut board = match Board::from_variant_fen(&fen, self.variant) {
+            Ok(board) => board,
+            Err(err) => {
+                println!("info string invalid fen: {err}");
+                return;
+            }
+        };
+        for text in tokens {
+            let Some(mv) = board.parse_uci_move(text) else {
+                println!("info string illegal move {text}");
+                return;
+            };
+            board.make_move(&mv);
+        }
+        self.board = board;
+    }
+
+    fn go<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) {
+        let side = self.board.side_to_move();
+        let mut limits = Limits {
+            multipv: self.multipv,
+            ..Limits::default()
+        };
+        let mut tokens = tokens.peekable();
+        while let Some(token) = tokens.next() {
+            let mut number = || tokens.next().and_then(|t| t.parse::<u64>().ok());
+            let millis = |ms: Option<u64>| ms.map(Duration::from_millis);
+            match (token, side) {
+                ("depth", _) => limits.depth = number().map(|d| d as u32),
+                ("nodes", _) => limits.nodes = number(),
+                ("movetime", _) => limits.movetime = millis(number()),
+                ("wtime", Side::WHITE) | ("btime", Side::BLACK) => limits.time = millis(number()),
+                ("winc", Side::WHITE) | ("binc", Side::BLACK) => {
+                    limits.increment = millis(number()).unwrap_or_default()
+                }
+                ("wtime" | "btime" | "winc" | "binc", _) => {
+                    number();
+                }
+                ("movestogo", _) => limits.moves_to_go = number().map(|n| n as u32),
+                ("infinite", _) => limits.infinite = true,
+                ("searchmoves", _) => {
+                    while let Some(mv) = tokens.peek().and_then(|t| self.board.parse_uci_move(t)) {
+                        limits.searchmoves.push(mv);
+                        tokens.next();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.searcher();
+        let use_book = self.own_book && !limits.infinite && self.variant == Variant::Standard;
+        if let Some(book) = self.book.as_ref().filter(|_| use_book)
+            && let Some(mv) = book.pick_move(&self.board, &mut self.rng)
+        {
+            println!("bestmove {}", mv.to_uci(self.chess960));
+            return;
+        }
+        // Only search the moves that keep the tablebase result.
+        if limits.searchmoves.is_empty()
+            && let Some(moves) = self.tablebase.as_ref().and_then(|tb| tb.root_moves(&self.board))
+        {
+            limits.searchmoves = moves;
+        }
+        let mut searcher = self.searcher.take().expect("searcher is idle");
+        let board = self.board.clone();
+        let chess960 = self.chess960;
+        self.search = Some(std::thread::spawn(move || {
+            let result = searcher.search(&board, &limits, |info| print_info(info, chess960));
+            let best = result.best_move.to_uci(chess960);
+            match result.ponder {
+                Some(ponder) => println!("bestmove {best} ponder {}", ponder.to_uci(chess960)),
+                None if result.best_move.is_null() => println!("bestmove 0000"),
+                None => println!("bestmove {best}"),
+            }
+            searcher
+        }));
+    }
+}
+
+fn print_info(info: &SearchInfo, chess960: bool) {
+    let score = match mate_in(info.score) {
+        Some(moves) => format!("mate {moves}"),
+        None => format!("cp {}", info.score),
+    };
+    let bound = match info.bound {
+        Bound::Exact => "",
+        Bound::Lower => " lowerbound",
+        Bound::Upper => " upperbound",
+    };
+    let pv: Vec<String> = info.pv.iter().map(|mv| mv.to_uci(chess960)).collect();
+    println!(
+        "info depth {} seldepth {} multipv {} score {score}{bound} nodes {} nps {} hashfull {} time {} pv {}",
+        info.depth,
+        info.seldepth,
+        info.multipv,
+        info.nodes,
+        info.nps(),
+        info.hashfull,
+        info.time.as_millis(),
+        pv.join(" "),
+    );
+}
@@ -0,0 +1,357 @@
+//! Chess variants. A board is played under one `Variant`; move generation,
+//! make/unmake and the search ask it wherever the rules differ from standard
+//! chess.
+
+use std::fmt;
+use std::str::FromStr;
+
+mod crazyhouse;
+
+use crate::utils::lsb1;
+use crate::{
+    Board, CastlingRights, GenType, Move, Moves, Side, BLACK, KING_PIECE_TYPE, PROMO_QUEEN, RANK1, RANK2,
+    RANK7, RANK8, STARTPOS, WHITE,
+};
+
+/// d4, e4, d5 and e5.
+const CENTER: u64 = 0x0000_0018_1800_0000;
+
+/// Checks that win a Three-check game.
+pub const CHECKS_TO_WIN: u8 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Bringing the king to one of the four center squares wins.
+    KingOfTheHill,
+    /// Giving the third check wins.
+    ThreeCheck,
+    /// A capture explodes the capturing and captured pieces along with every
+    /// piece but pawns next to them. Exploding the enemy king wins; kings
+    /// cannot capture and standing next to the enemy king is never check.
+    Atomic,
+    /// Captures are compulsory and the king is an ordinary piece that pawns
+    /// may promote to. Losing every piece or being stalemated wins.
+    Antichess,
+    /// Captured pieces change sides and go into the capturer's pocket, from
+    /// where they can be dropped on an empty square instead of moving.
+    Crazyhouse,
+    /// White has no king but a horde of pawns, which may also double-push from
+    /// the first rank. Black wins by taking every white piece.
+    Horde,
+    /// Checks are not allowed, and the first king on the eighth rank wins. If
+    /// white gets there first, black may still draw by following at once.
+    RacingKings,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 8] = [
+        Variant::Standard,
+        Variant::KingOfTheHill,
+        Variant::ThreeCheck,
+        Variant::Atomic,
+        Variant::Antichess,
+        Variant::Crazyhouse,
+        Variant::Horde,
+        Variant::RacingKings,
+    ];
+
+    /// Name as used by the `UCI_Variant` option.
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "chess",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::ThreeCheck => "3check",
+            Variant::Atomic => "atomic",
+            Variant::Antichess => "antichess",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Horde => "horde",
+            Variant::RacingKings => "racingkings",
+        }
+    }
+
+    /// The variant's initial position.
+    pub fn start_fen(self) -> &'static str {
+        match self {
+            Variant::Horde => "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+            Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+            _ => STARTPOS,
+        }
+    }
+
+    /// Whether a position of this variant may have `count` kings for `side`.
+    pub(crate) fn allows_kings(self, side: Side, count: u32) -> bool {
+        match self {
+            Variant::Antichess => true,
+            Variant::Horde if side == WHITE => count == 0,
+            _ => count == 1,
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownVariant(pub String);
+
+impl fmt::Display for UnknownVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown variant '{}'", self.0)
+    }
+}
+
+impl std::error::Error for UnknownVariant {}
+
+impl FromStr for Variant {
+    type Err = UnknownVariant;
+
+    /// Accepts the UCI names as well as the usual spellings of PGN `Variant` tags.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        match name.to_ascii_lowercase().as_str() {
+            "chess" | "standard" => Ok(Variant::Standard),
+            "kingofthehill" | "koth" => Ok(Variant::KingOfTheHill),
+            "3check" | "threecheck" => Ok(Variant::ThreeCheck),
+            "atomic" => Ok(Variant::Atomic),
+            "antichess" | "giveaway" => Ok(Variant::Antichess),
+            "crazyhouse" | "zh" => Ok(Variant::Crazyhouse),
+            "horde" => Ok(Variant::Horde),
+            "racingkings" => Ok(Variant::RacingKings),
+            _ => Err(UnknownVariant(s.to_string())),
+        }
+    }
+}
+
+impl Board {
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Plays the position under `variant` from now on, typically right after
+    /// `from_fen`. Antichess has no castling, so the rights are dropped.
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        if variant == Variant::Antichess {
+            self.castling = CastlingRights {
+                rook_files: self.castling.rook_files,
+                ..CastlingRights::default()
+            };
+        }
+        (self.hash, self.pawn_hash) = self.compute_hashes();
+    }
+
+    /// Checks `side` has given, which only Three-check counts.
+    pub fn checks_given(&self, side: Side) -> u8 {
+        self.checks[side]
+    }
+
+    /// The side that has won by a rule of the variant rather than by mate.
+    /// Such positions have no legal moves. Antichess wins by running out of
+    /// moves, which is not reported here.
+    pub fn variant_winner(&self) -> Option<Side> {
+        if self.variant == Variant::RacingKings {
+            return self.racing_kings_winner();
+        }
+        let won = |side: Side| match self.variant {
+            Variant::Standard | Variant::Antichess | Variant::Crazyhouse | Variant::RacingKings => false,
+            Variant::KingOfTheHill => self.kings[side] & CENTER != 0,
+            Variant::ThreeCheck => self.checks[side] >= CHECKS_TO_WIN,
+            Variant::Atomic => self.kings[!side] == 0,
+            Variant::Horde => self.pieces[!side] == 0,
+        };
+        [WHITE, BLACK].into_iter().find(|&side| won(side))
+    }
+
+    /// Whether the game is drawn by a rule of the variant: both kings reaching
+    /// the eighth rank in Racing Kings.
+    pub fn is_variant_draw(&self) -> bool {
+        self.variant == Variant::RacingKings && self.kings[WHITE] & RANK8 != 0 && self.kings[BLACK] & RANK8 != 0
+    }
+
+    /// Whether the variant's rules have ended the game.
+    pub(crate) fn is_variant_over(&self) -> bool {
+        self.variant != Variant::Standard && (self.variant_winner().is_some() || self.is_variant_draw())
+    }
+
+    fn racing_kings_winner(&self) -> Option<Side> {
+        match (self.kings[WHITE] & RANK8 != 0, self.kings[BLACK] & RANK8 != 0) {
+            (true, true) | (false, false) => None,
+            (false, true) => Some(BLACK),
+            (true, false) => {
+                // Black has one move left to draw by reaching the eighth rank as well.
+                let mut moves = Moves::new();
+                if self.side_to_move == BLACK {
+                    self.generate_variant(&mut moves, BLACK, GenType::All);
+                }
+                let follows = moves
+                    .iter()
+                    .any(|mv| mv.moving_piece() == KING_PIECE_TYPE && RANK8 & (1 << mv.to_square()) != 0);
+                (!follows).then_some(WHITE)
+            }
+        }
+    }
+
+    /// Ranks `side`'s pawns may double-push from.
+    pub(crate) fn double_push_ranks(&self, side: Side) -> u64 {
+        match side {
+            WHITE if self.variant == Variant::Horde => RANK1 | RANK2,
+            WHITE => RANK2,
+            BLACK => RANK7,
+        }
+    }
+
+    /// Legal moves for the variants whose legality differs from standard chess.
+    pub(crate) fn generate_variant(&self, moves: &mut Moves, side: Side, kind: GenType) {
+        let mut pseudo = Moves::new();
+        match self.variant {
+            Variant::Atomic => {
+                self.king_moves(&mut pseudo, side, kind, false);
+                if kind.quiets() && !self.is_check() {
+                    self.castling_moves(&mut pseudo, side);
+                }
+                self.other_moves(&mut pseudo, side, kind, false);
+                moves.extend(pseudo.into_iter().filter(|mv| self.atomic_is_legal(mv)));
+            }
+            Variant::Antichess => {
+                self.king_moves(&mut pseudo, side, GenType::All, false);
+                self.other_moves(&mut pseudo, side, GenType::All, false);
+                for mv in pseudo {
+                    if mv.is_promotion() && mv.promotion_piece() == PROMO_QUEEN {
+                        let mut king = mv;
+                        king.set_promotes_to_king();
+                        pseudo.push(king);
+                    }
+                }
+                let forced = pseudo.iter().any(Move::is_capture);
+                moves.extend(pseudo.into_iter().filter(|mv| {
+                    let tactical = mv.is_capture() || mv.is_promotion();
+                    (!forced || mv.is_capture())
+                        && match kind {
+                            GenType::Captures => tactical,
+                            GenType::Quiets => !tactical,
+                            _ => true,
+                        }
+                }));
+            }
+            Variant::RacingKings => {
+                self.generate_with(&mut pseudo, side, kind, true);
+                moves.extend(pseudo.into_iter().filter(|mv| !self.gives_check(mv)));
+            }
+            _ => unreachable!("{} uses the standard generator", self.variant),
+        }
+    }
+
+    /// Whether the side to move is in check by atomic rules: attacked by a
+    /// piece other than the king while the kings are apart.
+    pub(crate) fn atomic_in_check(&self) -> bool {
+        let side = self.side_to_move;
+        let (own, theirs) = (self.kings[side], self.kings[!side]);
+        if own == 0 || theirs == 0 {
+            return false;
+        }
+        let king = lsb1(own);
+        self.king_attack_table[king] & theirs == 0
+            && self.attackers_to(king, !side, self.occupied) & !theirs != 0
+    }
+
+    /// Whether the pseudo-legal `mv` is legal in Atomic: it may not blow up
+    /// the mover's king, and unless it blows up the other king it may not
+    /// leave the mover in check.
+    fn atomic_is_legal(&self, mv: &Move) -> bool {
+        let side = self.side_to_move;
+        let (from, to) = (mv.from_square(), mv.destination());
+        let mut occupied = self.occupied & !(1 << from);
+        let mut own = self.kings[side];
+        let mut theirs = self.kings[!side];
+        if mv.is_capture() {
+            if mv.moving_piece() == KING_PIECE_TYPE {
+                return false;
+            }
+            occupied &= !(self.blast(to) | (1 << to) | (1 << Board::capture_square(mv, side)));
+            own &= occupied;
+            theirs &= occupied;
+        } else {
+            occupied |= 1 << to;
+            if mv.is_castling() {
+                let (rook_from, rook_to) = Board::castling_rook_squares(mv);
+                occupied = (occupied & !(1 << rook_from)) | (1 << rook_to) | (1 << to);
+            }
+            if mv.moving_piece() == KING_PIECE_TYPE {
+                own = 1 << to;
+            }
+        }
+        if own == 0 {
+            return false;
+        }
+        if theirs == 0 {
+            return true;
+        }
+        let king = lsb1(own);
+        self.king_attack_table[king] & theirs != 0 || self.attackers_to(king, !side, occupied) & !theirs == 0
+    }
+
+    /// Pieces other than pawns that a capture on `square` blows up, besides
+    /// the two taking part.
+    pub(crate) fn blast(&self, square: usize) -> u64 {
+        self.king_attack_table[square] & self.occupied & !(self.pawns[WHITE] | self.pawns[BLACK])
+    }
+
+    /// Removes the pieces a capture landing on `to` blows up, the capturing
+    /// one included, and records them for `undo_explosion`. Returns the
+    /// squares blown up around `to`.
+    pub(crate) fn explode(&mut self, to: usize) -> u64 {
+        let blast = self.blast(to);
+        let mut exploded = [0u8; 8];
+        let mut squares = blast;
+        let mut i = 0;
+        while squares != 0 {
+            let square = lsb1(squares);
+            squares &= squares - 1;
+            let side = self.side_at(square).expect("blast covers occupied squares");
+            exploded[i] = (side as u8) << 3 | self.arr[square];
+            self.remove_piece(side, self.arr[square], square);
+            i += 1;
+        }
+        let history = self.history.last_mut().expect("explode during make_move");
+        history.blast = blast;
+        history.exploded = exploded;
+        let side = self.side_at(to).expect("the capturing piece stands on the target");
+        self.remove_piece(side, self.arr[to], to);
+        blast
+    }
+
+    /// Puts back what `explode` removed for `mv`, which `side` played.
+    pub(crate) fn undo_explosion(&mut self, mv: &Move, side: Side) {
+        let history = *self.history.last().expect("unmake_move without make_move");
+        let piece = if mv.is_promotion() { mv.promoted_to() } else { mv.moving_piece() };
+        self.put_piece(side, piece, mv.to_square());
+        let mut blast = history.blast;
+        let mut i = 0;
+        while blast != 0 {
+            let square = lsb1(blast);
+            blast &= blast - 1;
+            let packed = history.exploded[i];
+            let owner = if packed >> 3 == 0 { WHITE } else { BLACK };
+            self.put_piece(owner, packed & 0b111, square);
+            i += 1;
+        }
+    }
+
+    /// Antichess evaluation: having fewer pieces, and fewer pawns close to
+    /// running out of moves, is better.
+    pub(crate) fn evaluate_antichess(&self) -> i32 {
+        let side = self.side_to_move;
+        let count = |side: Side| self.pieces[side].count_ones() as i32 * 100;
+        let pawns = |side: Side| self.pawns[side].count_ones() as i32 * 10;
+        (count(!side) - count(side)) + (pawns(!side) - pawns(side)) / 2
+    }
+
+    pub(crate) fn is_custom_legality(&self) -> bool {
+        matches!(self.variant, Variant::Atomic | Variant::Antichess | Variant::RacingKings)
+    }
+}
@@ -0,0 +1,80 @@
+//! Crazyhouse pockets and drops.
+
+use crate::utils::lsb1;
+use crate::{zobrist, Board, Move, Moves, Side, PAWN_PIECE_TYPE, QUEEN_PIECE_TYPE, RANK1, RANK8};
+
+impl Board {
+    /// Number of pieces of type `piece`, a pawn to a queen, that `side` holds in hand.
+    pub fn pocket(&self, side: Side, piece: u8) -> u8 {
+        self.pockets[side][piece as usize - 1]
+    }
+
+    /// Pieces that were promoted from pawns.
+    pub fn promoted(&self) -> u64 {
+        self.promoted
+    }
+
+    fn set_pocket(&mut self, side: Side, piece: u8, count: u8) {
+        let slot = &mut self.pockets[side][piece as usize - 1];
+        self.hash ^= zobrist::pocket_key(side, piece, *slot) ^ zobrist::pocket_key(side, piece, count);
+        *slot = count;
+    }
+
+    /// Moves pieces between board and pockets for `mv`, which `side` is about to
+    /// play: a drop empties a slot and a capture fills one, with a promoted
+    /// piece going back in as a pawn.
+    pub(crate) fn update_pockets(&mut self, mv: &Move, side: Side) {
+        let (from, to) = (mv.from_square(), mv.to_square());
+        if mv.is_drop() {
+            let piece = mv.moving_piece();
+            self.set_pocket(side, piece, self.pocket(side, piece) - 1);
+            return;
+        }
+        if mv.is_capture() {
+            let square = Board::capture_square(mv, side);
+            let piece = if self.promoted & (1 << square) != 0 { PAWN_PIECE_TYPE } else { mv.captured_piece() };
+            self.set_pocket(side, piece, self.pocket(side, piece) + 1);
+            self.promoted &= !(1 << square);
+        }
+        if self.promoted & (1 << from) != 0 {
+            self.promoted ^= (1 << from) | (1 << to);
+        }
+        if mv.is_promotion() {
+            self.promoted |= 1 << to;
+        }
+    }
+
+    /// Appends the drops from `side`'s pocket onto `targets`. Pawns may not be
+    /// dropped on the first or last rank.
+    pub(crate) fn drop_moves(&self, moves: &mut Moves, side: Side, targets: u64) {
+        for piece in PAWN_PIECE_TYPE..=QUEEN_PIECE_TYPE {
+            if self.pocket(side, piece) == 0 {
+                continue;
+            }
+            let mut squares = if piece == PAWN_PIECE_TYPE { targets & !(RANK1 | RANK8) } else { targets };
+            while squares != 0 {
+                let to = lsb1(squares);
+                squares &= squares - 1;
+                let mut mv = Move::new();
+                mv.set_from_square(to);
+                mv.set_to_square(to);
+                mv.set_moving_piece(piece);
+                mv.set_prev_ep(self.ep);
+                mv.set_castling_rights(self.castling);
+                mv.set_is_drop();
+                moves.push(mv);
+            }
+        }
+    }
+
+    /// Midgame material of the pieces in hand, from the side to move's point of view.
+    pub(crate) fn pocket_score(&self) -> i32 {
+        let side = self.side_to_move;
+        let mut score = 0;
+        for piece in PAWN_PIECE_TYPE..=QUEEN_PIECE_TYPE {
+            let value = self.weights.material[piece as usize - 1].mg;
+            score += value * (self.pocket(side, piece) as i32 - self.pocket(!side, piece) as i32);
+        }
+        score
+    }
+}
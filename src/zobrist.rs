@@ -0,0 +1,84 @@
+use crate::{CastlingRights, Side};
+
+// Keys are generated at compile time with splitmix64 so hashes are stable between runs.
+const fn splitmix64(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut arr = [0u64; N];
+    let mut state = seed;
+    let mut i = 0;
+    while i < N {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        arr[i] = splitmix64(state);
+        i += 1;
+    }
+    arr
+}
+
+// Indexed by (side * 6 + piece - 1) * 64 + square.
+const PIECE_KEYS: [u64; 768] = keys(0x5EED_0001);
+const CASTLING_KEYS: [u64; 4] = keys(0x5EED_0002);
+const EP_KEYS: [u64; 8] = keys(0x5EED_0003);
+const SIDE_KEY: u64 = keys::<1>(0x5EED_0004)[0];
+// Checks given in Three-check, indexed by side * 3 + checks - 1.
+const CHECK_KEYS: [u64; 6] = keys(0x5EED_0005);
+// Crazyhouse pocket contents, indexed by ((side * 5 + piece - 1) * POCKET_SIZE) + count - 1.
+const POCKET_SIZE: usize = 32;
+const POCKET_KEYS: [u64; 2 * 5 * POCKET_SIZE] = keys(0x5EED_0006);
+
+pub fn piece_key(side: Side, piece: u8, square: usize) -> u64 {
+    PIECE_KEYS[(side as usize * 6 + piece as usize - 1) * 64 + square]
+}
+
+pub fn castling_key(rights: CastlingRights) -> u64 {
+    let mut key = 0;
+    let rights = [
+        rights.white_queenside,
+        rights.white_kingside,
+        rights.black_queenside,
+        rights.black_kingside,
+    ];
+    for (i, allowed) in rights.into_iter().enumerate() {
+        if allowed {
+            key ^= CASTLING_KEYS[i];
+        }
+    }
+    key
+}
+
+pub fn ep_key(ep: Option<u8>) -> u64 {
+    match ep {
+        Some(file) => EP_KEYS[file as usize],
+        None => 0,
+    }
+}
+
+pub fn side_key(side: Side) -> u64 {
+    match side {
+        Side::WHITE => 0,
+        Side::BLACK => SIDE_KEY,
+    }
+}
+
+pub fn check_key(side: Side, checks: u8) -> u64 {
+    match checks {
+        0 => 0,
+        checks => CHECK_KEYS[side as usize * 3 + checks.min(3) as usize - 1],
+    }
+}
+
+/// Key for `side` holding `count` pieces of type `piece`, a pawn to a queen, in hand.
+pub fn pocket_key(side: Side, piece: u8, count: u8) -> u64 {
+    match count {
+        0 => 0,
+        count => {
+            let count = (count as usize).min(POCKET_SIZE);
+            POCKET_KEYS[(side as usize * 5 + piece as usize - 1) * POCKET_SIZE + count - 1]
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use chess::analysis::{analyze_game, AnalysisOptions, MoveClass};
+use chess::pgn::{Game, NAG_BLUNDER};
+use chess::search::{Limits, Searcher};
+use chess::Side;
+
+#[test]
+fn blunder_is_flagged_with_the_best_move() {
+    let moves = ["e4", "e5", "Nf3", "Nc6", "Bc4", "Nd4", "Nxe5", "Qg5", "Nxf7"];
+    let game = Game {
+        moves: moves.iter().map(|san| san.to_string()).collect(),
+        ..Game::default()
+    };
+    let options = AnalysisOptions {
+        limits: Limits { depth: Some(10), ..Limits::default() },
+        ..AnalysisOptions::default()
+    };
+    let analysis = analyze_game(&game, &mut Searcher::new(1, 16), &options).unwrap();
+    assert_eq!(analysis.moves.len(), moves.len());
+
+    let blunder = &analysis.moves[8];
+    assert_eq!(blunder.played.to_uci(false), "e5f7");
+    assert_eq!(blunder.best.to_uci(false), "c4f7");
+    assert_eq!(blunder.best_line[0], blunder.best);
+    assert_eq!(blunder.class, MoveClass::Blunder);
+    assert!(blunder.score_before > blunder.score_after + 300, "{blunder:?}");
+    assert!(blunder.accuracy < 50.0);
+    let white = &analysis.players[Side::WHITE];
+    assert_eq!((white.moves, analysis.players[Side::BLACK].moves), (5, 4));
+    assert!(white.blunders >= 1 && white.accuracy < 100.0);
+
+    let annotated = analysis.annotate(&game, &options);
+    let note = &annotated.annotations[8];
+    assert_eq!(note.nags, [NAG_BLUNDER]);
+    assert!(note.comment.as_deref().unwrap().ends_with("Blunder. Bxf7+ was best."), "{note:?}");
+    assert_eq!(note.variations[0][0], "Bxf7+");
+    assert!(annotated.tag("WhiteAccuracy").is_some() && annotated.tag("BlackAccuracy").is_some());
+    assert!(annotated.to_string().contains("Nxf7??"));
+}
@@ -0,0 +1,163 @@
+use chess::book::builder::{BookBuilder, BuildOptions};
+use chess::book::{decode_move, encode_move, Book};
+use chess::pgn::PgnReader;
+use chess::Board;
+
+/// Plays `moves`, in UCI notation, from the start position.
+fn play(moves: &[&str]) -> Board {
+    let mut board = Board::new();
+    for uci in moves {
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.make_move(&mv);
+    }
+    board
+}
+
+// The examples from the Polyglot book format description.
+#[test]
+fn polyglot_reference_keys() {
+    let cases: &[(&[&str], u64)] = &[
+        (&[], 0x463b96181691fc9c),
+        (&["e2e4"], 0x823c9b50fd114196),
+        (&["e2e4", "d7d5"], 0x0756b94461c50fb0),
+        (&["e2e4", "d7d5", "e4e5"], 0x662fafb965db29d4),
+        (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a48b5a8e47ff78),
+        (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"], 0x652a607ca3f242c1),
+        (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"], 0x00fdd303c946bdd9),
+        (&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"], 0x3c8123ea7b067637),
+        (&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"], 0x5c3f9b829b279560),
+    ];
+    for &(moves, key) in cases {
+        assert_eq!(play(moves).polyglot_key(), key, "{moves:?}");
+    }
+    // Setting a position up from its FEN gives the same key.
+    let board = play(&["e2e4", "d7d5", "e4e5", "f7f5"]);
+    assert_eq!(Board::from_fen(&board.to_fen()).unwrap().polyglot_key(), 0x22a48b5a8e47ff78);
+}
+
+/// A Polyglot move from its squares, a1 = 0, and promotion piece.
+fn raw(from: u16, to: u16, promotion: u16) -> u16 {
+    promotion << 12 | from << 6 | to
+}
+
+#[test]
+fn move_encoding() {
+    let board = Board::new();
+    let e4 = decode_move(&board, raw(12, 28, 0)).unwrap();
+    assert_eq!(e4.to_uci(false), "e2e4");
+    assert_eq!(encode_move(&e4), raw(12, 28, 0));
+    assert_eq!(decode_move(&board, raw(12, 36, 0)), None);
+
+    // Castling is the king taking its own rook.
+    let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    for (to, uci) in [(7, "e1g1"), (0, "e1c1")] {
+        let castle = decode_move(&board, raw(4, to, 0)).unwrap();
+        assert!(castle.is_castling());
+        assert_eq!(castle.to_uci(false), uci);
+        assert_eq!(encode_move(&castle), raw(4, to, 0));
+    }
+    // Castling is never written as the king's two-square step.
+    assert_eq!(decode_move(&board, raw(4, 6, 0)), None);
+
+    let board = Board::from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    for (piece, letter) in [(1, 'n'), (2, 'b'), (3, 'r'), (4, 'q')] {
+        let promotion = decode_move(&board, raw(48, 57, piece)).unwrap();
+        assert_eq!(promotion.to_uci(false), format!("a7b8{letter}"));
+        assert_eq!(encode_move(&promotion), raw(48, 57, piece));
+    }
+    assert_eq!(decode_move(&board, raw(48, 57, 0)), None);
+}
+
+#[test]
+fn book_lookup() {
+    let start = Board::new();
+    let after_e4 = play(&["e2e4"]);
+    let mut entries = [
+        (start.polyglot_key(), raw(12, 28, 0), 10),
+        (start.polyglot_key(), raw(11, 27, 0), 30),
+        // Illegal in the position, so skipped.
+        (start.polyglot_key(), raw(12, 36, 0), 50),
+        (after_e4.polyglot_key(), raw(50, 34, 0), 0),
+    ];
+    entries.sort_by_key(|&(key, _, _)| key);
+    let bytes: Vec<u8> = entries
+        .iter()
+        .flat_map(|&(key, mv, weight)| {
+            [&key.to_be_bytes()[..], &mv.to_be_bytes(), &u16::to_be_bytes(weight), &[0; 4]].concat()
+        })
+        .collect();
+    let book = Book::from_bytes(&bytes).unwrap();
+    assert_eq!(book.len(), 4);
+    assert_eq!(book.to_bytes(), bytes);
+
+    let moves: Vec<(String, u16)> = book.moves(&start).iter().map(|(mv, w)| (mv.to_uci(false), *w)).collect();
+    assert_eq!(moves, [("e2e4".to_string(), 10), ("d2d4".to_string(), 30)]);
+    assert_eq!(book.best_move(&start).unwrap().to_uci(false), "d2d4");
+    // A weightless move is still played when it is the only one.
+    let mut rng = fastrand::Rng::with_seed(1);
+    assert_eq!(book.pick_move(&after_e4, &mut rng).unwrap().to_uci(false), "c7c5");
+    assert_eq!(book.best_move(&play(&["d2d4"])), None);
+
+    assert!(Book::from_bytes(&bytes[1..]).is_err());
+    let mut unsorted = bytes[48..].to_vec();
+    unsorted.extend_from_slice(&bytes[..48]);
+    assert!(Book::from_bytes(&unsorted).is_err());
+}
+
+const GAMES: &str = r#"[White "A"]
+[Black "B"]
+[WhiteElo "2400"]
+[BlackElo "2000"]
+
+1. e4 e5 2. Nf3 {a comment} Nc6 1-0
+
+[White "C"]
+[Black "D"]
+[WhiteElo "2100"]
+
+1. e4 c5 (1... e5 2. Nf3) 2. Nf3 0-1
+
+[White "E"]
+[Black "F"]
+
+1. d4 d5 1/2-1/2
+"#;
+
+fn build(options: BuildOptions) -> Book {
+    let mut builder = BookBuilder::new(options);
+    for game in PgnReader::new(GAMES.as_bytes()) {
+        builder.add_game(&game.unwrap()).unwrap();
+    }
+    assert_eq!(builder.games(), 3);
+    builder.build()
+}
+
+fn book_moves(book: &Book, moves: &[&str]) -> Vec<(String, u16)> {
+    book.moves(&play(moves)).iter().map(|(mv, weight)| (mv.to_uci(false), *weight)).collect()
+}
+
+#[test]
+fn book_from_pgn() {
+    let options = BuildOptions { max_ply: 2, ..BuildOptions::default() };
+    let book = build(options.clone());
+    // Two points per win and one per draw, for the side that moved.
+    assert_eq!(book_moves(&book, &[]), [("e2e4".to_string(), 2), ("d2d4".to_string(), 1)]);
+    // 1... e5 only lost, and the variation is not counted.
+    assert_eq!(book_moves(&book, &["e2e4"]), [("c7c5".to_string(), 2)]);
+    assert_eq!(book_moves(&book, &["d2d4"]), [("d7d5".to_string(), 1)]);
+    // Beyond `max_ply`.
+    assert_eq!(book_moves(&book, &["e2e4", "e7e5"]), []);
+    assert_eq!(book.len(), 4);
+    assert_eq!(Book::from_bytes(&book.to_bytes()).unwrap().len(), 4);
+
+    let book = build(BuildOptions { min_games: 2, ..options.clone() });
+    assert_eq!(book_moves(&book, &[]), [("e2e4".to_string(), 2)]);
+    assert_eq!(book.len(), 1);
+
+    // Only the 2400 and 2100 players' moves count.
+    let book = build(BuildOptions { min_elo: Some(2100), max_ply: 4, ..options });
+    assert_eq!(book_moves(&book, &[]), [("e2e4".to_string(), 2)]);
+    assert_eq!(book_moves(&book, &["e2e4", "e7e5"]), [("g1f3".to_string(), 2)]);
+    assert_eq!(book_moves(&book, &["e2e4", "c7c5"]), []);
+    assert_eq!(book.len(), 2);
+}
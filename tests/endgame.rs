@@ -0,0 +1,180 @@
+use chess::endgame::{Dtm, DtmTable, Endgames};
+use chess::{Board, Moves, Side, QUEEN_PIECE_TYPE, ROOK_PIECE_TYPE};
+
+/// A FEN with `pieces` placed on their squares, a1 = 0.
+fn fen(pieces: &[(char, usize)], white_to_move: bool) -> String {
+    let mut ranks = Vec::new();
+    for rank in (0..8).rev() {
+        let mut text = String::new();
+        let mut empty = 0;
+        for file in 0..8 {
+            match pieces.iter().find(|(_, square)| *square == rank * 8 + file) {
+                Some((piece, _)) => {
+                    if empty > 0 {
+                        text.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    text.push(*piece);
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            text.push_str(&empty.to_string());
+        }
+        ranks.push(text);
+    }
+    format!("{} {} - - 0 1", ranks.join("/"), if white_to_move { "w" } else { "b" })
+}
+
+/// Seeded random legal positions with `pieces` on distinct squares.
+fn random_positions(pieces: &[char], count: usize, seed: u64) -> Vec<Board> {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let mut boards = Vec::new();
+    while boards.len() < count {
+        let mut squares: Vec<usize> = Vec::new();
+        while squares.len() < pieces.len() {
+            let square = rng.usize(..64);
+            if !squares.contains(&square) {
+                squares.push(square);
+            }
+        }
+        let placed: Vec<(char, usize)> = pieces.iter().copied().zip(squares).collect();
+        if placed.iter().any(|&(piece, square)| piece == 'P' && !(8..56).contains(&square)) {
+            continue;
+        }
+        let white_to_move = rng.bool();
+        // The side that just moved must not be left in check.
+        let Ok(other) = Board::from_fen(&fen(&placed, !white_to_move)) else {
+            continue;
+        };
+        if other.is_check() {
+            continue;
+        }
+        boards.push(Board::from_fen(&fen(&placed, white_to_move)).unwrap());
+    }
+    boards
+}
+
+fn moves(board: &Board) -> Moves {
+    let mut moves = Moves::new();
+    board.generate_moves(&mut moves, board.side_to_move());
+    moves
+}
+
+fn probe(table: &DtmTable, fen: &str) -> Option<Dtm> {
+    table.probe(&Board::from_fen(fen).unwrap())
+}
+
+#[test]
+fn known_distances_to_mate() {
+    let krk = DtmTable::generate("KRvK").unwrap();
+    assert_eq!(krk.name(), "KRvK");
+    assert_eq!(krk.longest_mate(), 32);
+    assert_eq!(probe(&krk, "k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(Dtm::Win(1)));
+    assert_eq!(probe(&krk, "k6R/8/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Loss(0)));
+    // The lone king takes the rook.
+    assert_eq!(probe(&krk, "K7/8/8/8/8/8/1R6/k7 b - - 0 1"), Some(Dtm::Draw));
+    // Other material is not covered.
+    assert_eq!(probe(&krk, "k7/8/1K6/8/8/8/8/7Q w - - 0 1"), None);
+
+    let kqk = DtmTable::generate("KQvK").unwrap();
+    assert_eq!(kqk.longest_mate(), 20);
+    assert_eq!(probe(&kqk, "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(probe(&kqk, "k7/8/1K6/8/8/8/8/2Q5 w - - 0 1"), Some(Dtm::Win(1)));
+    // Black's queen mates just as well.
+    assert_eq!(probe(&kqk, "2q5/8/8/8/8/1k6/8/K7 b - - 0 1"), Some(Dtm::Win(1)));
+
+    let bytes = krk.to_bytes().to_vec();
+    let loaded = DtmTable::from_bytes("KRvK", &bytes).unwrap();
+    assert_eq!(probe(&loaded, "k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(Dtm::Win(1)));
+    assert!(DtmTable::from_bytes("KQvK", &bytes[1..]).is_err());
+    assert!(DtmTable::generate("KRRvK").is_none());
+}
+
+/// Every table value follows from the values after each move: a win in n
+/// plies has a move to a loss in n - 1 and none to a shorter loss, a loss in
+/// n has every move lead to a win in at most n - 1, and a draw has no move to
+/// a loss. Captures and moves out of the table count as draws.
+#[test]
+fn distances_agree_with_successors() {
+    for (name, pieces) in [("KRvK", ['K', 'R', 'k']), ("KQvK", ['K', 'Q', 'k'])] {
+        let mut endgames = Endgames::new();
+        endgames.add(DtmTable::generate(name).unwrap());
+        for mut board in random_positions(&pieces, 300, 5) {
+            let dtm = endgames.probe(&board).unwrap();
+            let mut children = Vec::new();
+            for mv in moves(&board).iter() {
+                board.make_move(mv);
+                children.push(endgames.probe(&board).unwrap_or(Dtm::Draw));
+                board.unmake_move(mv);
+            }
+            let fen = board.to_fen();
+            let shortest_loss = children
+                .iter()
+                .filter_map(|&child| if let Dtm::Loss(n) = child { Some(n) } else { None })
+                .min();
+            match dtm {
+                Dtm::Win(n) => assert_eq!(shortest_loss, Some(n - 1), "{fen}"),
+                Dtm::Loss(0) => assert!(children.is_empty() && board.is_check(), "{fen}"),
+                Dtm::Loss(n) => {
+                    let longest_win = children
+                        .iter()
+                        .map(|&child| if let Dtm::Win(m) = child { Some(m) } else { None })
+                        .collect::<Option<Vec<u32>>>()
+                        .and_then(|wins| wins.into_iter().max());
+                    assert_eq!(longest_win, Some(n - 1), "{fen}");
+                }
+                Dtm::Draw => assert_eq!(shortest_loss, None, "{fen}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn kpk_evaluation() {
+    let eval = |fen: &str| Board::from_fen(fen).unwrap().evaluate();
+    // Black has the opposition in front of the pawn.
+    assert_eq!(eval("8/3k4/8/3K4/3P4/8/8/8 w - - 0 1"), 0);
+    assert!(eval("8/3k4/8/3K4/3P4/8/8/8 b - - 0 1") <= -200);
+    // A king on the sixth rank in front of the pawn wins either way.
+    assert!(eval("3k4/8/3K4/3P4/8/8/8/8 w - - 0 1") >= 200);
+    assert_eq!(eval("k7/8/K7/P7/8/8/8/8 w - - 0 1"), 0);
+    assert_eq!(eval("8/5k2/8/3P4/8/8/8/4K3 w - - 0 1"), 0);
+    // The pawn outruns the king, also for black.
+    assert!(eval("8/8/8/8/8/k7/6P1/K7 w - - 0 1") >= 200);
+    assert!(eval("k7/6p1/K7/8/8/8/8/8 b - - 0 1") >= 200);
+}
+
+/// A KPK position is won for the pawn's side exactly when, with that side to
+/// move, some move reaches a won position, and with the other side to move,
+/// every move does. A queen or rook promotion wins unless the new piece is
+/// taken or the promotion stalemates; taking the pawn draws.
+#[test]
+fn kpk_agrees_with_successors() {
+    let won = |board: &Board| board.evaluate() != 0;
+    for mut board in random_positions(&['K', 'P', 'k'], 1000, 9) {
+        let list = moves(&board);
+        let attacker = board.side_to_move() == Side::WHITE;
+        let mut results = Vec::new();
+        for mv in list.iter() {
+            board.make_move(mv);
+            let result = if mv.is_promotion() {
+                let replies = moves(&board);
+                let taken = replies.iter().any(|reply| reply.is_capture());
+                let stalemate = replies.is_empty() && !board.is_check();
+                matches!(mv.promoted_to(), QUEEN_PIECE_TYPE | ROOK_PIECE_TYPE) && !taken && !stalemate
+            } else {
+                board.is_known_endgame() && won(&board)
+            };
+            board.unmake_move(mv);
+            results.push(result);
+        }
+        let expected = if attacker {
+            results.iter().any(|&won| won)
+        } else {
+            !results.is_empty() && results.iter().all(|&won| won)
+        };
+        assert_eq!(won(&board), expected, "{}", board.to_fen());
+    }
+}
@@ -0,0 +1,182 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use chess::engine::{BestMove, EngineError, EngineEvent, EngineInfo, EngineScore, UciEngine};
+use chess::search::{Bound, Limits};
+use chess::Board;
+
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+fn mock_engine() -> UciEngine {
+    let script = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mock_engine.sh");
+    UciEngine::spawn("sh", [script]).expect("mock engine starts")
+}
+
+fn depth(depth: u32) -> Limits {
+    Limits {
+        depth: Some(depth),
+        ..Limits::default()
+    }
+}
+
+/// The text of the next `info string`, skipping other events.
+fn next_string(engine: &mut UciEngine) -> String {
+    loop {
+        if let EngineEvent::Info(EngineInfo { string: Some(string), .. }) = engine.next_event(TIMEOUT).unwrap() {
+            return string;
+        }
+    }
+}
+
+#[test]
+fn handshake_and_options() {
+    let mut engine = mock_engine();
+    assert_eq!(engine.name(), Some("Mock Engine 1.0"));
+    assert_eq!(engine.author(), Some("The Tests"));
+
+    let options = engine.options();
+    assert_eq!(options.len(), 4);
+    assert_eq!(options[0].name, "Hash");
+    assert_eq!(options[0].kind, "spin");
+    assert_eq!(options[0].default.as_deref(), Some("16"));
+    assert_eq!((options[0].min, options[0].max), (Some(1), Some(1024)));
+    assert_eq!(options[1].vars, ["Solid", "Normal", "Risky"]);
+    assert_eq!(options[2].name, "Book File");
+    assert_eq!(options[2].default.as_deref(), Some(""));
+    assert_eq!(options[3].name, "Clear Hash");
+    assert_eq!(options[3].kind, "button");
+
+    engine.set_option("Hash", "64").unwrap();
+    assert_eq!(next_string(&mut engine), "setoption name Hash value 64");
+    engine.is_ready().unwrap();
+    engine.new_game().unwrap();
+}
+
+#[test]
+fn positions() {
+    let mut engine = mock_engine();
+    let board = Board::new();
+    engine.set_position(&board, &[]).unwrap();
+    assert_eq!(next_string(&mut engine), "position startpos");
+
+    let e4 = board.parse_uci_move("e2e4").unwrap();
+    engine.set_position(&board, &[e4]).unwrap();
+    assert_eq!(next_string(&mut engine), "position startpos moves e2e4");
+    assert_eq!(engine.position().unwrap().to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let board = Board::from_fen(fen).unwrap();
+    let castle = board.parse_uci_move("e1g1").unwrap();
+    engine.set_position(&board, &[castle]).unwrap();
+    assert_eq!(next_string(&mut engine), format!("position fen {fen} moves e1g1"));
+    engine.set_option("UCI_Chess960", "true").unwrap();
+    assert_eq!(next_string(&mut engine), "setoption name UCI_Chess960 value true");
+    engine.set_position(&board, &[castle]).unwrap();
+    assert_eq!(next_string(&mut engine), format!("position fen {fen} moves e1h1"));
+}
+
+#[test]
+fn blocking_search() {
+    let mut engine = mock_engine();
+    let board = Board::new();
+    assert!(matches!(engine.go(&depth(3), TIMEOUT, |_| {}), Err(EngineError::NoPosition)));
+
+    engine.set_position(&board, &[]).unwrap();
+    let mut infos = Vec::new();
+    let best = engine.go(&depth(3), TIMEOUT, |info| infos.push(info.clone())).unwrap();
+    let mv = |s: &str| board.parse_uci_move(s).unwrap();
+    let mut after_e4 = board.clone();
+    after_e4.make_move(&mv("e2e4"));
+    assert_eq!(
+        best,
+        BestMove {
+            best: Some(mv("e2e4")),
+            ponder: after_e4.parse_uci_move("e7e5"),
+        }
+    );
+
+    // The position echo comes first.
+    assert_eq!(infos[0].string.as_deref(), Some("position startpos"));
+    assert_eq!(infos[1].depth, Some(1));
+    assert_eq!(infos[1].seldepth, Some(2));
+    assert_eq!(infos[1].multipv, Some(1));
+    assert_eq!(infos[1].score, Some(EngineScore::Cp(25)));
+    assert_eq!(infos[1].bound, Bound::Exact);
+    assert_eq!(infos[1].nodes, Some(20));
+    assert_eq!(infos[1].nps, Some(20000));
+    assert_eq!(infos[1].time, Some(Duration::from_millis(1)));
+    assert_eq!(infos[1].pv, [mv("e2e4")]);
+    assert_eq!(infos[2].score, Some(EngineScore::Cp(30)));
+    assert_eq!(infos[2].bound, Bound::Lower);
+    assert!(infos[2].pv.is_empty());
+    assert_eq!(infos[3].score, Some(EngineScore::Mate(-2)));
+    assert_eq!(infos[3].bound, Bound::Upper);
+    assert_eq!(infos[3].hashfull, Some(5));
+    // The pv stops before the illegal e1e8.
+    let pv: Vec<String> = infos[3].pv.iter().map(|mv| mv.to_uci(false)).collect();
+    assert_eq!(pv, ["e2e4", "e7e5"]);
+    assert_eq!(infos[4].string.as_deref(), Some("searching go depth 3"));
+}
+
+#[test]
+fn illegal_best_move() {
+    let mut engine = mock_engine();
+    let board = Board::new();
+    let e4 = board.parse_uci_move("e2e4").unwrap();
+    engine.set_position(&board, &[e4]).unwrap();
+    match engine.go(&depth(3), TIMEOUT, |_| {}) {
+        Err(EngineError::IllegalMove(mv)) => assert_eq!(mv, "e2e4"),
+        other => panic!("expected an illegal move, got {other:?}"),
+    }
+}
+
+#[test]
+fn polling_search() {
+    let mut engine = mock_engine();
+    let board = Board::new();
+    engine.set_position(&board, &[]).unwrap();
+    engine.is_ready().unwrap();
+    let infinite = Limits {
+        infinite: true,
+        ..Limits::default()
+    };
+
+    engine.start_search(&infinite).unwrap();
+    match engine.next_event(TIMEOUT).unwrap() {
+        EngineEvent::Info(info) => {
+            assert_eq!(info.score, Some(EngineScore::Cp(12)));
+            assert_eq!(info.pv, [board.parse_uci_move("d2d4").unwrap()]);
+        }
+        other => panic!("expected info, got {other:?}"),
+    }
+    // Nothing more comes until the search is stopped.
+    assert!(matches!(engine.next_event(Some(Duration::from_millis(100))), Err(EngineError::Timeout)));
+    assert_eq!(engine.try_event().unwrap(), None);
+
+    engine.stop().unwrap();
+    let best = loop {
+        if let Some(EngineEvent::BestMove(best)) = engine.try_event().unwrap() {
+            break best;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    };
+    assert_eq!(best.best, board.parse_uci_move("d2d4"));
+    assert!(best.ponder.is_some());
+}
+
+#[test]
+fn search_timeout() {
+    let mut engine = mock_engine();
+    engine.set_position(&Board::new(), &[]).unwrap();
+    let infinite = Limits {
+        infinite: true,
+        ..Limits::default()
+    };
+    let result = engine.go(&infinite, Some(Duration::from_millis(100)), |_| {});
+    assert!(matches!(result, Err(EngineError::Timeout)));
+
+    // The stopped search's `bestmove` does not answer the next one.
+    let best = engine.go(&depth(3), TIMEOUT, |_| {}).unwrap();
+    assert_eq!(best.best, Board::new().parse_uci_move("e2e4"));
+}
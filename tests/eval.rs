@@ -0,0 +1,155 @@
+use chess::eval::{PawnTable, Score};
+use chess::{Board, Moves, Side};
+
+const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R b KQ - 3 8",
+    "2r3k1/1p3ppp/p2p4/3Pp3/2P5/1P3P2/P5PP/5RK1 w - e6 0 24",
+];
+
+/// The position with colours swapped and the board flipped vertically.
+fn mirror(fen: &str) -> String {
+    let fields: Vec<&str> = fen.split(' ').collect();
+    let swap_case = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() })
+            .collect()
+    };
+    let ranks: Vec<String> = fields[0].split('/').rev().map(swap_case).collect();
+    let side = if fields[1] == "w" { "b" } else { "w" };
+    let mut castling: Vec<char> = swap_case(fields[2]).chars().collect();
+    castling.sort_by_key(|c| (c.is_ascii_lowercase(), *c != 'K' && *c != 'k'));
+    let ep = match fields[3].as_bytes() {
+        [file, rank] => format!("{}{}", *file as char, (b'1' + b'8' - rank) as char),
+        _ => "-".to_string(),
+    };
+    format!("{} {side} {} {ep} {} {}", ranks.join("/"), castling.iter().collect::<String>(), fields[4], fields[5])
+}
+
+/// Visits every position of a seeded random walk of `plies` moves and back,
+/// with the move made or unmade last.
+fn walk(fen: &str, plies: usize, mut visit: impl FnMut(&Board)) {
+    let mut board = Board::from_fen(fen).unwrap();
+    let mut rng = fastrand::Rng::with_seed(11);
+    let mut played = Vec::new();
+    visit(&board);
+    for _ in 0..plies {
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move());
+        if moves.is_empty() {
+            break;
+        }
+        let mv = moves[rng.usize(..moves.len())];
+        board.make_move(&mv);
+        played.push(mv);
+        visit(&board);
+    }
+    while let Some(mv) = played.pop() {
+        board.unmake_move(&mv);
+        visit(&board);
+    }
+}
+
+#[test]
+fn incremental_psqt_matches_recomputation() {
+    for fen in POSITIONS {
+        walk(fen, 60, |board| assert_eq!(*board.psqt(), board.compute_psqt(), "{}", board.to_fen()));
+    }
+}
+
+#[test]
+fn evaluation_is_colour_symmetric() {
+    for fen in POSITIONS {
+        walk(fen, 30, |board| {
+            let fen = board.to_fen();
+            let mirrored = Board::from_fen(&mirror(&fen)).unwrap();
+            assert_eq!(board.evaluate(), mirrored.evaluate(), "{fen} against {}", mirror(&fen));
+        });
+    }
+}
+
+#[test]
+fn cached_evaluation_matches_evaluation() {
+    // Small enough that entries get replaced along the walks.
+    let mut pawn_table = PawnTable::new(16);
+    for fen in POSITIONS {
+        walk(fen, 60, |board| {
+            // The second probe finds the entry the first one stored.
+            assert_eq!(board.evaluate_cached(&mut pawn_table), board.evaluate(), "{}", board.to_fen());
+            assert_eq!(board.evaluate_cached(&mut pawn_table), board.evaluate(), "{}", board.to_fen());
+        });
+    }
+}
+
+#[test]
+fn unmake_restores_hashes() {
+    for fen in POSITIONS {
+        walk(fen, 60, |board| {
+            assert_eq!((board.hash(), board.pawn_hash()), board.compute_hashes(), "{}", board.to_fen());
+        });
+        let mut seen = Vec::new();
+        walk(fen, 60, |board| seen.push((board.hash(), board.pawn_hash())));
+        let plies = seen.len() / 2;
+        for i in 0..plies {
+            assert_eq!(seen[i], seen[seen.len() - 1 - i], "{fen}");
+        }
+    }
+}
+
+#[test]
+fn mobility_counts_safe_squares() {
+    let mobility = |fen: &str| {
+        let board = Board::from_fen(fen).unwrap();
+        board.mobility(&board.attack_info())
+    };
+    let weights = Board::new().weights().clone();
+    // The knight reaches b3 and c2, unless a pawn guards b3.
+    assert_eq!(mobility("7k/8/8/8/8/8/8/N6K w - - 0 1"), weights.mobility_knight[2]);
+    assert_eq!(mobility("7k/8/8/8/2p5/8/8/N6K w - - 0 1"), weights.mobility_knight[1]);
+    // Squares with own pieces on them do not count.
+    assert_eq!(mobility("7k/8/8/8/8/8/P7/R6K w - - 0 1"), weights.mobility_rook[6]);
+    assert_eq!(mobility("r6k/p7/8/8/8/8/8/7K w - - 0 1"), -weights.mobility_rook[6]);
+    assert_eq!(mobility("7k/8/8/8/8/8/8/Q6K w - - 0 1"), weights.mobility_queen[20]);
+
+    let board = Board::from_fen("7k/8/8/8/8/8/8/N6K w - - 0 1").unwrap();
+    let info = board.attack_info();
+    assert_eq!(info.by_piece[Side::WHITE][1], 1 << 17 | 1 << 10);
+    assert_eq!(info.all[Side::BLACK], 1 << 62 | 1 << 54 | 1 << 55);
+}
+
+#[test]
+fn king_safety_terms() {
+    let king_safety = |fen: &str| {
+        let board = Board::from_fen(fen).unwrap();
+        board.king_safety(&board.attack_info())
+    };
+    let weights = Board::new().weights().clone();
+    let shield = weights.pawn_shield;
+
+    assert_eq!(king_safety("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1"), Score::ZERO);
+    assert_eq!(
+        king_safety("6k1/5ppp/8/8/8/8/5PP1/6K1 w - - 0 1"),
+        shield[2] + weights.king_semi_open_file - shield[0]
+    );
+    assert_eq!(
+        king_safety("1k6/ppp5/8/8/8/8/5PP1/6K1 w - - 0 1"),
+        shield[2] + weights.king_open_file - shield[0]
+    );
+    assert_eq!(king_safety("6k1/5ppp/8/8/8/6P1/5P1P/6K1 w - - 0 1"), shield[1] - shield[0]);
+    assert_eq!(king_safety("1k6/ppp5/8/8/8/7p/5PPP/6K1 w - - 0 1"), weights.pawn_storm[1]);
+
+    // One attacker on the king zone adds no danger, two do.
+    let queen = "6k1/5ppp/8/8/7q/8/5PPP/6K1 w - - 0 1";
+    assert_eq!(Board::from_fen(queen).unwrap().attack_info().king_attackers[Side::BLACK], 1);
+    assert_eq!(king_safety(queen), Score::ZERO);
+    let queen_and_knight = Board::from_fen("6k1/5ppp/8/8/7q/5n2/5PPP/6K1 w - - 0 1").unwrap();
+    let info = queen_and_knight.attack_info();
+    assert_eq!(info.king_attackers[Side::BLACK], 2);
+    let units = info.king_attack_units[Side::BLACK] as usize;
+    assert!(units > 0);
+    assert_eq!(queen_and_knight.king_safety(&info), weights.king_danger[units.min(63)]);
+}
@@ -0,0 +1,29 @@
+#!/bin/sh
+# A stand-in UCI engine for tests/engine.rs. It echoes position and setoption
+# commands back as info strings and answers every search with the same lines,
+# which only make sense from the initial position.
+while read -r line; do
+    case "$line" in
+        uci)
+            echo "id name Mock Engine 1.0"
+            echo "id author The Tests"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "option name Style type combo default Normal var Solid var Normal var Risky"
+            echo "option name Book File type string default <empty>"
+            echo "option name Clear Hash type button"
+            echo "uciok"
+            ;;
+        isready) echo "readyok" ;;
+        position*|setoption*) echo "info string $line" ;;
+        "go infinite") echo "info depth 1 score cp 12 nodes 20 pv d2d4" ;;
+        go*)
+            echo "info depth 1 seldepth 2 multipv 1 score cp 25 nodes 20 nps 20000 time 1 pv e2e4"
+            echo "info depth 2 score cp 30 lowerbound nodes 90"
+            echo "info depth 3 score mate -2 upperbound nodes 400 time 3 hashfull 5 pv e2e4 e7e5 e1e8 g1f3"
+            echo "info string searching $line"
+            echo "bestmove e2e4 ponder e7e5"
+            ;;
+        stop) echo "bestmove d2d4 ponder d7d5" ;;
+        quit) exit 0 ;;
+    esac
+done
@@ -0,0 +1,64 @@
+use chess::{Board, MateResult, Outcome, Side};
+
+/// The mate `find_mate` finds within `max_moves`, checked move by move and
+/// ending in checkmate, in UCI notation.
+fn mate(fen: &str, max_moves: u32) -> Option<Vec<String>> {
+    let board = Board::from_fen(fen).unwrap();
+    let MateResult::Mate(line) = board.find_mate(max_moves) else {
+        return None;
+    };
+    let mut position = board.clone();
+    for mv in &line {
+        assert!(position.is_legal(mv), "{fen}: {mv} is illegal");
+        position.make_move(mv);
+    }
+    let winner = board.side_to_move();
+    assert_eq!(position.outcome(), Some(Outcome::Checkmate(winner)), "{fen}");
+    Some(line.iter().map(|mv| mv.to_uci(false)).collect())
+}
+
+#[test]
+fn mate_in_one() {
+    let fen = "6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1";
+    assert_eq!(mate(fen, 1).unwrap(), ["d1d8"]);
+    assert_eq!(mate(fen, 3).unwrap(), ["d1d8"]);
+    // Black mates too.
+    assert_eq!(mate("3r2k1/8/8/8/8/8/5PPP/6K1 b - - 0 1", 1).unwrap(), ["d8d1"]);
+}
+
+#[test]
+fn mate_in_two() {
+    let fen = "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1";
+    assert_eq!(mate(fen, 1), None);
+    assert_eq!(mate(fen, 2).unwrap()[0], "d5f6");
+    // The first move does not have to check.
+    let quiet = "k7/8/2K5/8/8/8/8/6R1 w - - 0 1";
+    assert_eq!(mate(quiet, 2).unwrap(), ["c6b6", "a8b8", "g1g8"]);
+    let board = Board::from_fen(quiet).unwrap();
+    assert_eq!(board.find_mate_by_checks(2), MateResult::NoMate);
+}
+
+#[test]
+fn mate_in_three() {
+    let fen = "r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 0 1";
+    assert_eq!(mate(fen, 2), None);
+    let line = mate(fen, 3).unwrap();
+    assert_eq!(line.len(), 5);
+    assert_eq!(line[0], "f6a6");
+    assert!(matches!(Board::from_fen(fen).unwrap().find_mate_by_checks(3), MateResult::Mate(_)));
+}
+
+#[test]
+fn no_mate_within_the_limit() {
+    assert_eq!(mate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 2), None);
+    assert_eq!(mate("2k5/8/8/8/8/8/8/R3K3 w Q - 0 1", 3), None);
+    // A mate in four is out of reach of three moves.
+    let fen = "r1b2rk1/pp1p1pp1/1b1p2B1/n1qQ2p1/8/5N2/P3RPPP/4R1K1 w - - 0 1";
+    assert_eq!(mate(fen, 3), None);
+    assert_eq!(mate(fen, 4).unwrap().len(), 7);
+    // Stalemate and checkmate positions have nothing to play.
+    assert_eq!(mate("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", 2), None);
+    let mated = Board::from_fen("k6R/8/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(mated.find_mate(2), MateResult::NoMate);
+    assert_eq!(mated.outcome(), Some(Outcome::Checkmate(Side::WHITE)));
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use chess::{Board, FenError, GenType, Move, Moves, Variant};
+
+const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    // Checks by castling, by promotion, and uncovered by pawns, en passant included.
+    "5k2/8/8/8/8/8/3N4/R3K2R w KQ - 0 1",
+    "4k3/1P4P1/8/8/8/8/8/B3K3 w - - 0 1",
+    "8/7k/8/4pP2/8/8/8/1B2K3 w - e6 0 1",
+    // Chess960, with the king castling onto its rook's square in the last one.
+    "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+    "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+    "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+];
+
+fn generate(board: &Board, kind: GenType) -> Vec<Move> {
+    let mut moves = Moves::new();
+    board.generate(&mut moves, board.side_to_move(), kind);
+    moves.to_vec()
+}
+
+fn check_position(board: &mut Board, depth: u32) {
+    let legal = generate(board, GenType::All);
+    let captures = generate(board, GenType::Captures);
+    let quiets = generate(board, GenType::Quiets);
+    let fen = board.to_fen();
+
+    let legal_set: HashSet<Move> = legal.iter().copied().collect();
+    let captures_set: HashSet<Move> = captures.iter().copied().collect();
+    let quiets_set: HashSet<Move> = quiets.iter().copied().collect();
+    assert_eq!(captures.len() + quiets.len(), legal.len(), "{fen}");
+    assert!(captures_set.is_disjoint(&quiets_set), "{fen}");
+    assert_eq!(&captures_set | &quiets_set, legal_set, "{fen}");
+    assert!(captures.iter().all(|mv| mv.is_capture() || mv.is_promotion()), "{fen}");
+
+    let evasions = generate(board, GenType::Evasions);
+    if board.is_check() {
+        assert_eq!(evasions, legal, "{fen}");
+    } else {
+        assert!(evasions.is_empty(), "{fen}");
+    }
+
+    let quiet_checks: HashSet<Move> = generate(board, GenType::QuietChecks).into_iter().collect();
+    for mv in quiets {
+        board.make_move(&mv);
+        let check = board.is_check();
+        board.unmake_move(&mv);
+        assert_eq!(quiet_checks.contains(&mv), check, "{fen} {mv}");
+    }
+    let checks = generate(board, GenType::Checks);
+    let checks_set: HashSet<Move> = checks.iter().copied().collect();
+    assert_eq!(checks.len(), checks_set.len(), "{fen}");
+    for &mv in &legal {
+        board.make_move(&mv);
+        let check = board.is_check();
+        board.unmake_move(&mv);
+        assert_eq!(checks_set.contains(&mv), check, "{fen} {mv}");
+    }
+
+    if depth > 1 {
+        for mv in legal {
+            board.make_move(&mv);
+            check_position(board, depth - 1);
+            board.unmake_move(&mv);
+        }
+    }
+}
+
+#[test]
+fn captures_and_quiets_partition_legal_moves() {
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        check_position(&mut board, 2);
+    }
+}
+
+fn check_pseudo_legal(board: &mut Board, depth: u32, foreign: &mut Vec<Move>) {
+    let legal = generate(board, GenType::All);
+    let mut pseudo = Moves::new();
+    board.generate_pseudo_legal(&mut pseudo, board.side_to_move());
+    let fen = board.to_fen();
+
+    let filtered: HashSet<Move> = pseudo.iter().copied().filter(|mv| board.is_legal(mv)).collect();
+    assert_eq!(filtered, legal.iter().copied().collect(), "{fen}");
+    assert!(pseudo.iter().all(|mv| board.is_pseudo_legal(mv)), "{fen}");
+    // Moves from other positions stand in for stale transposition table entries.
+    for mv in foreign.iter() {
+        assert_eq!(board.is_pseudo_legal(mv), pseudo.contains(mv), "{fen} {mv}");
+    }
+
+    foreign.extend(pseudo.iter().copied());
+    if depth > 1 {
+        for mv in legal {
+            board.make_move(&mv);
+            check_pseudo_legal(board, depth - 1, foreign);
+            board.unmake_move(&mv);
+        }
+    }
+}
+
+#[test]
+fn pseudo_legal_moves_filter_to_legal_moves() {
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        check_pseudo_legal(&mut board, 2, &mut Vec::new());
+    }
+}
+
+#[test]
+fn back_rank_pawns_are_rejected() {
+    assert_eq!(Board::from_fen("P3k3/8/8/8/8/8/3n4/4K3 w - - 0 1").err(), Some(FenError::PawnOnBackRank(56)));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/p3K3 b - - 0 1").err(), Some(FenError::PawnOnBackRank(0)));
+    assert!(Board::from_fen("4k3/8/8/8/8/8/8/P3K3 w - - 0 1").is_err());
+    // Horde pawns start on the first rank.
+    assert!(Board::from_variant_fen(Variant::Horde.start_fen(), Variant::Horde).is_ok());
+}
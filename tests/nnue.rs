@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use chess::nnue::{Accumulator, Network, NnueState, SimdBackend};
+use chess::{Board, Moves, Side};
+
+const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+fn backends() -> Vec<SimdBackend> {
+    let mut backends = vec![SimdBackend::Scalar];
+    #[cfg(target_arch = "x86_64")]
+    backends.extend([SimdBackend::Sse2, SimdBackend::Avx2]);
+    backends
+}
+
+/// Accumulators and evaluations along a seeded random walk of `plies` moves
+/// and back, checking each incremental accumulator against a fresh one.
+fn walk(net: &Arc<Network>, fen: &str, backend: SimdBackend, plies: usize) -> Vec<(Accumulator, i32)> {
+    let mut board = Board::from_fen(fen).unwrap();
+    board.set_network(Some(net.clone()));
+    board.nnue_mut().unwrap().set_backend(backend);
+    let mut rng = fastrand::Rng::with_seed(7);
+    let mut seen = Vec::new();
+    let mut record = |board: &Board| {
+        let state = board.nnue().unwrap();
+        let fresh = NnueState::new(net.clone(), board);
+        assert_eq!(state.accumulator(), fresh.accumulator(), "{} with {backend:?}", board.to_fen());
+        seen.push((state.accumulator().clone(), state.evaluate(Side::WHITE)));
+    };
+
+    record(&board);
+    let mut played = Vec::new();
+    for _ in 0..plies {
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move());
+        if moves.is_empty() {
+            break;
+        }
+        let mv = moves[rng.usize(..moves.len())];
+        board.make_move(&mv);
+        played.push(mv);
+        record(&board);
+    }
+    while let Some(mv) = played.pop() {
+        board.unmake_move(&mv);
+        record(&board);
+    }
+    seen
+}
+
+#[test]
+fn backends_agree() {
+    let mut rng = fastrand::Rng::with_seed(3);
+    for backend in backends() {
+        let mut acc: Vec<i16> = (0..64).map(|_| rng.i16(..)).collect();
+        let row: Vec<i16> = (0..64).map(|_| rng.i16(..)).collect();
+        let weights: Vec<i16> = (0..64).map(|_| rng.i8(..) as i16).collect();
+
+        let mut expected = acc.clone();
+        SimdBackend::Scalar.add(&mut expected, &row);
+        let mut added = acc.clone();
+        backend.add(&mut added, &row);
+        assert_eq!(added, expected, "{backend:?}");
+
+        SimdBackend::Scalar.sub(&mut expected, &row);
+        backend.sub(&mut added, &row);
+        assert_eq!(added, expected, "{backend:?}");
+        assert_eq!(added, acc, "{backend:?}");
+
+        acc.iter_mut().step_by(3).for_each(|a| *a = a.signum() * 127);
+        assert_eq!(
+            backend.dot_clipped(&acc, &weights),
+            SimdBackend::Scalar.dot_clipped(&acc, &weights),
+            "{backend:?}"
+        );
+    }
+}
+
+#[test]
+fn incremental_updates_match_refresh() {
+    let net = Arc::new(Network::random(32, 1));
+    for fen in POSITIONS {
+        let scalar = walk(&net, fen, SimdBackend::Scalar, 40);
+        for backend in backends() {
+            assert!(scalar == walk(&net, fen, backend, 40), "{fen} with {backend:?}");
+        }
+    }
+}
+
+#[test]
+fn network_bytes_round_trip() {
+    let net = Network::random(16, 2);
+    let loaded = Network::from_bytes(&net.to_bytes()).unwrap();
+    let board = Board::new();
+    let (a, b) = (NnueState::new(Arc::new(net), &board), NnueState::new(Arc::new(loaded), &board));
+    assert_eq!(a.accumulator(), b.accumulator());
+    assert_eq!(a.evaluate(Side::WHITE), b.evaluate(Side::WHITE));
+}
@@ -0,0 +1,111 @@
+use chess::packed::{PackError, PackedPosition, PackedReader, PackedWriter, RECORD_SIZE};
+use chess::pgn::GameResult;
+use chess::{Board, FenError, Moves, Variant};
+
+const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    // Partial castling rights, black to move and large counters.
+    "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 37 112",
+    // En passant for either side.
+    "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+    "4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+fn round_trip(board: &Board) -> Board {
+    Board::from_packed(&board.to_packed().unwrap()).unwrap()
+}
+
+#[test]
+fn positions_round_trip() {
+    for fen in POSITIONS {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(round_trip(&board).to_fen(), *fen);
+    }
+    // Along random games, too.
+    let mut rng = fastrand::Rng::with_seed(13);
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        for _ in 0..60 {
+            let mut moves = Moves::new();
+            board.generate_moves(&mut moves, board.side_to_move());
+            if moves.is_empty() {
+                break;
+            }
+            board.make_move(&moves[rng.usize(..moves.len())]);
+            let unpacked = round_trip(&board);
+            assert_eq!(unpacked.to_fen(), board.to_fen());
+            assert_eq!(unpacked.hash(), board.hash(), "{}", board.to_fen());
+        }
+    }
+}
+
+#[test]
+fn record_layout() {
+    let mut packed = Board::new().to_packed().unwrap();
+    let bytes = packed.0;
+    assert_eq!(u64::from_le_bytes(bytes[..8].try_into().unwrap()), 0xffff_0000_0000_ffff);
+    // Castling rooks, knights, bishops, queen and king, then eight pawns.
+    assert_eq!(u128::from_le_bytes(bytes[8..24].try_into().unwrap()) as u64, 0x0000_0000_6125_4216);
+    assert_eq!(bytes[24], 64);
+    assert_eq!((bytes[25], u16::from_le_bytes([bytes[26], bytes[27]])), (0, 1));
+    assert_eq!((packed.score(), packed.result(), packed.ply()), (0, Some(GameResult::Draw), 0));
+
+    packed.set_score(-345);
+    packed.set_result(GameResult::BlackWins);
+    assert_eq!((packed.score(), packed.result()), (-345, Some(GameResult::BlackWins)));
+    assert_eq!(packed.0[28..31], [0xa7, 0xfe, 0]);
+
+    let black = Board::from_fen(POSITIONS[3]).unwrap().to_packed().unwrap();
+    assert_eq!((black.0[24], black.0[25]), (0x80 | 64, 37));
+    assert_eq!(black.ply(), 2 * 111 + 1);
+    let ep = Board::from_fen(POSITIONS[5]).unwrap().to_packed().unwrap();
+    assert_eq!(ep.0[24], 0x80 | 19);
+}
+
+#[test]
+fn invalid_records() {
+    let crazyhouse = Board::from_variant_fen(POSITIONS[0], Variant::Crazyhouse).unwrap();
+    assert!(matches!(crazyhouse.to_packed(), Err(PackError::Variant(Variant::Crazyhouse))));
+
+    let mut packed = Board::new().to_packed().unwrap();
+    packed.0[8] = 0x07;
+    assert!(matches!(Board::from_packed(&packed), Err(PackError::InvalidPiece(7))));
+    // A white pawn on a1.
+    packed.0[8] = 0x10;
+    assert!(matches!(Board::from_packed(&packed), Err(PackError::Fen(FenError::PawnOnBackRank(0)))));
+    packed.0[30] = 3;
+    assert_eq!(packed.result(), None);
+    assert!(matches!(Board::from_packed(&PackedPosition([0xff; RECORD_SIZE])), Err(PackError::TooManyPieces(64))));
+}
+
+#[test]
+fn reader_and_writer_round_trip() {
+    let records: Vec<PackedPosition> = POSITIONS
+        .iter()
+        .enumerate()
+        .map(|(i, fen)| {
+            let mut packed = Board::from_fen(fen).unwrap().to_packed().unwrap();
+            packed.set_score(i as i16 * 100 - 300);
+            packed.set_result(GameResult::WhiteWins);
+            packed
+        })
+        .collect();
+    let mut writer = PackedWriter::new(Vec::new());
+    for packed in &records {
+        writer.write(packed).unwrap();
+    }
+    let bytes = writer.into_inner();
+    assert_eq!(bytes.len(), records.len() * RECORD_SIZE);
+
+    let read: Vec<PackedPosition> = PackedReader::new(&bytes[..]).map(Result::unwrap).collect();
+    assert_eq!(read, records);
+    assert_eq!(PackedReader::new(&[][..]).count(), 0);
+
+    // A partial record at the end is an error.
+    let mut reader = PackedReader::new(&bytes[..RECORD_SIZE + 5]);
+    assert!(reader.next().unwrap().is_ok());
+    assert_eq!(reader.next().unwrap().unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+}
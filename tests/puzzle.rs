@@ -0,0 +1,98 @@
+use chess::puzzle::themes::{detect, Theme};
+use chess::puzzle::{find_puzzle, Goal, Puzzle, PuzzleOptions};
+use chess::search::{Limits, Searcher};
+use chess::Board;
+
+/// The side to move's material minus its opponent's, in pawns.
+fn material(board: &Board) -> i32 {
+    let fen = board.to_fen();
+    let balance: i32 = fen
+        .split(' ')
+        .next()
+        .unwrap()
+        .chars()
+        .map(|c| {
+            let value = match c.to_ascii_lowercase() {
+                'p' => 1,
+                'n' | 'b' => 3,
+                'r' => 5,
+                'q' => 9,
+                _ => 0,
+            };
+            if c.is_ascii_uppercase() { value } else { -value }
+        })
+        .sum();
+    if fen.contains(" w ") { balance } else { -balance }
+}
+
+fn options() -> PuzzleOptions {
+    PuzzleOptions {
+        limits: Limits { depth: Some(8), ..Limits::default() },
+        ..PuzzleOptions::default()
+    }
+}
+
+fn puzzle(fen: &str) -> Option<Puzzle> {
+    let board = Board::from_fen(fen).unwrap();
+    find_puzzle(&board, material(&board), &mut Searcher::new(1, 16), &options())
+}
+
+fn solution(puzzle: &Puzzle) -> String {
+    puzzle.solution.iter().map(|mv| mv.to_uci(false)).collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn puzzles_with_one_solution() {
+    let fork = puzzle("3q3k/6pp/8/4N3/8/8/PPP5/K7 w - - 0 2").unwrap();
+    assert_eq!(solution(&fork), "e5f7 h8g8 f7d8");
+    assert_eq!((fork.goal, fork.themes.as_slice()), (Goal::Material(9), &[Theme::Fork][..]));
+
+    let back_rank = puzzle("6k1/5ppp/p7/8/8/7P/5PP1/3R2K1 w - - 0 2").unwrap();
+    assert_eq!(solution(&back_rank), "d1d8");
+    assert_eq!((back_rank.goal, back_rank.themes.as_slice()), (Goal::Mate(1), &[Theme::BackRankMate][..]));
+    assert_eq!(back_rank.to_string(), format!("{},d1d8,{},mateIn1 backRankMate", back_rank.board.to_fen(), back_rank.rating));
+
+    let skewer = puzzle("8/8/8/8/q2k4/8/8/4K2R w K - 0 2").unwrap();
+    assert_eq!(solution(&skewer), "h1h4 d4d3 h4a4");
+    assert!(skewer.themes.contains(&Theme::Skewer));
+
+    let discovered = puzzle("q3k3/8/8/8/4B3/8/8/K3R3 w - - 0 2").unwrap();
+    assert_eq!(solution(&discovered), "e4a8");
+    assert!(discovered.themes.contains(&Theme::DiscoveredAttack));
+}
+
+#[test]
+fn puzzles_with_two_winning_moves_are_rejected() {
+    // axb8=Q+ and axb8=R+ both win; so do Bxc6+ and d5 against the pinned knight.
+    for fen in ["1r2k3/P6p/6p1/8/8/8/6PP/4K3 w - - 0 1", "4k3/p7/2n5/1B6/3P4/8/8/4K3 w - - 0 1"] {
+        let board = Board::from_fen(fen).unwrap();
+        let limits = Limits { multipv: 2, ..options().limits };
+        let lines = Searcher::new(1, 16).search(&board, &limits, |_| {}).lines;
+        let win_score = options().win_score;
+        assert!(lines.iter().all(|line| line.score >= win_score), "{fen}");
+        assert!(puzzle(fen).is_none(), "{fen}");
+    }
+}
+
+#[test]
+fn themes_of_crafted_solutions() {
+    let themes = |fen: &str, moves: &[&str]| {
+        let start = Board::from_fen(fen).unwrap();
+        let mut board = start.clone();
+        let solution: Vec<_> = moves
+            .iter()
+            .map(|uci| {
+                let mv = board.parse_uci_move(uci).unwrap();
+                board.make_move(&mv);
+                mv
+            })
+            .collect();
+        detect(&start, &solution)
+    };
+    // The knight on c6 is pinned to its king.
+    assert_eq!(themes("4k3/8/2n5/8/8/8/8/3BK3 w - - 0 1", &["d1a4"]), [Theme::Pin]);
+    assert_eq!(themes("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1", &["e7e8q"]), [Theme::Promotion]);
+    // Only the solver's moves count.
+    assert_eq!(themes("4k3/8/2n5/8/8/8/8/3BK3 b - - 0 1", &["e8f8", "d1a4"]), []);
+    assert_eq!(themes("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &["e2e4"]), []);
+}
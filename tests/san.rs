@@ -0,0 +1,76 @@
+use chess::{Board, Moves};
+
+const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "7k/8/8/8/8/Q7/8/Q1Q4K w - - 0 1",
+    "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+];
+
+/// SAN of the move written `uci` in `fen`.
+fn san(fen: &str, uci: &str) -> String {
+    let board = Board::from_fen(fen).unwrap();
+    board.san(&board.parse_uci_move(uci).unwrap())
+}
+
+fn parse(fen: &str, san: &str) -> Option<String> {
+    Board::from_fen(fen).unwrap().parse_san(san).map(|mv| mv.to_uci(false))
+}
+
+#[test]
+fn san_round_trips() {
+    for fen in POSITIONS {
+        let board = Board::from_fen(fen).unwrap();
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move());
+        for mv in moves.iter() {
+            let san = board.san(mv);
+            assert_eq!(board.parse_san(&san).as_ref(), Some(mv), "{san} in {fen}");
+        }
+    }
+}
+
+#[test]
+fn disambiguation() {
+    let rooks = "7k/8/8/R7/8/8/8/R4R1K w - - 0 1";
+    assert_eq!(san(rooks, "a1d1"), "Rad1");
+    assert_eq!(san(rooks, "a1a3"), "R1a3");
+    assert_eq!(san(rooks, "a5a3"), "R5a3");
+    assert_eq!(san(rooks, "a1b1"), "Rab1");
+    assert_eq!(parse(rooks, "Rd1"), None);
+    assert_eq!(parse(rooks, "Ra3"), None);
+    assert_eq!(parse(rooks, "Rfd1").as_deref(), Some("f1d1"));
+
+    let queens = "8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1";
+    assert_eq!(san(queens, "a1b2"), "Qa1b2");
+    assert_eq!(san(queens, "a3b2"), "Q3b2");
+    assert_eq!(parse(queens, "Qa1b2").as_deref(), Some("a1b2"));
+
+    // The pinned knight on f3 cannot go to d2, so no rival.
+    let pinned = "k7/8/8/3b4/8/5N2/8/1N5K w - - 0 1";
+    assert_eq!(san(pinned, "b1d2"), "Nd2");
+}
+
+#[test]
+fn special_moves() {
+    let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    assert_eq!(san(castling, "e1h1"), "O-O");
+    assert_eq!(san(castling, "e1a1"), "O-O-O");
+    assert_eq!(parse(castling, "0-0-0").as_deref(), Some("e1c1"));
+
+    let promotion = "k2r4/4P3/8/8/8/8/8/K7 w - - 0 1";
+    assert_eq!(san(promotion, "e7e8q"), "e8=Q");
+    assert_eq!(san(promotion, "e7d8q"), "exd8=Q+");
+    assert_eq!(san(promotion, "e7d8n"), "exd8=N");
+    assert_eq!(parse(promotion, "exd8=R+").as_deref(), Some("e7d8r"));
+    assert_eq!(parse(promotion, "exd8"), None);
+
+    assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+    assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+    assert_eq!(san("6k1/6pp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8+");
+    // Suffixes are optional when parsing.
+    assert_eq!(parse("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8").as_deref(), Some("a1a8"));
+    assert_eq!(parse("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8#!?").as_deref(), Some("a1a8"));
+}
@@ -0,0 +1,111 @@
+use chess::search::{mate_in, Bound, Limits, SearchInfo, SearchResult, Searcher};
+use chess::{Board, Move, Moves};
+
+const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+fn search(fen: &str, limits: Limits) -> (SearchResult, Vec<SearchInfo>) {
+    search_with(&mut Searcher::new(1, 16), fen, limits)
+}
+
+fn search_with(searcher: &mut Searcher, fen: &str, limits: Limits) -> (SearchResult, Vec<SearchInfo>) {
+    let board = Board::from_fen(fen).unwrap();
+    let mut infos = Vec::new();
+    let result = searcher.search(&board, &limits, |info| infos.push(info.clone()));
+    (result, infos)
+}
+
+fn uci(mv: &Move) -> String {
+    mv.to_uci(false)
+}
+
+fn legal_moves(fen: &str) -> Moves {
+    let board = Board::from_fen(fen).unwrap();
+    let mut moves = Moves::new();
+    board.generate_moves(&mut moves, board.side_to_move());
+    moves
+}
+
+#[test]
+fn multipv_lines_are_distinct_and_ordered() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let limits = Limits { depth: Some(5), multipv: 4, ..Limits::default() };
+    let (result, infos) = search(fen, limits);
+    let lines = &result.lines;
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines.iter().map(|line| line.multipv).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    let mut first_moves: Vec<String> = lines.iter().map(|line| uci(&line.pv[0])).collect();
+    assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score), "{lines:?}");
+    assert_eq!((result.best_move, result.score), (lines[0].pv[0], lines[0].score));
+    first_moves.sort();
+    first_moves.dedup();
+    assert_eq!(first_moves.len(), 4);
+    // Every iteration reports each line once its score is exact.
+    let last: Vec<usize> = infos
+        .iter()
+        .filter(|info| info.depth == result.depth && info.bound == Bound::Exact)
+        .map(|info| info.multipv)
+        .collect();
+    assert_eq!(last, [1, 2, 3, 4]);
+
+    // The mate comes first, and every line beyond it is worse.
+    let limits = Limits { depth: Some(4), multipv: 3, ..Limits::default() };
+    let (result, _) = search(BACK_RANK, limits);
+    assert_eq!(uci(&result.lines[0].pv[0]), "a1a8");
+    assert_eq!(mate_in(result.lines[0].score), Some(1));
+    assert!(result.lines[1..].iter().all(|line| mate_in(line.score).is_none()));
+}
+
+#[test]
+fn multipv_beyond_legal_moves() {
+    let fen = "k7/8/1K6/8/8/8/8/7R b - - 0 1";
+    let count = legal_moves(fen).len();
+    let limits = Limits { depth: Some(3), multipv: 10, ..Limits::default() };
+    let (result, _) = search(fen, limits);
+    assert_eq!(result.lines.len(), count);
+}
+
+#[test]
+fn searchmoves_restrict_the_root() {
+    let board = Board::new();
+    let searchmoves = vec![board.parse_uci_move("a2a3").unwrap(), board.parse_uci_move("h2h3").unwrap()];
+    let limits = Limits { depth: Some(4), multipv: 5, searchmoves: searchmoves.clone(), ..Limits::default() };
+    let (result, infos) = search(chess::STARTPOS, limits);
+    assert!(searchmoves.contains(&result.best_move));
+    assert_eq!(result.lines.len(), 2);
+    assert!(infos.iter().all(|info| searchmoves.contains(&info.pv[0])));
+
+    // Without the mating move there is no mate to find.
+    let others: Vec<Move> = legal_moves(BACK_RANK).iter().copied().filter(|mv| uci(mv) != "a1a8").collect();
+    let limits = Limits { depth: Some(4), searchmoves: others, ..Limits::default() };
+    let (result, _) = search(BACK_RANK, limits);
+    assert_ne!(uci(&result.best_move), "a1a8");
+    assert_eq!(mate_in(result.score), None);
+}
+
+#[test]
+fn threads_find_the_mate() {
+    let fen = "r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 0 1";
+    let mut searcher = Searcher::new(4, 16);
+    assert_eq!(searcher.threads(), 4);
+    let limits = Limits { depth: Some(7), ..Limits::default() };
+    let (result, infos) = search_with(&mut searcher, fen, limits.clone());
+    assert_eq!(uci(&result.best_move), "f6a6");
+    assert_eq!(mate_in(result.score), Some(3));
+
+    // Reports and the result count the helpers' nodes along with the main thread's.
+    let reported = infos.last().unwrap().nodes;
+    assert!(result.nodes >= reported, "{} < {reported}", result.nodes);
+    // A node limit counts every thread's nodes.
+    let node_limit = Limits { nodes: Some(20_000), ..Limits::default() };
+    let (result, _) = search_with(&mut searcher, chess::STARTPOS, node_limit);
+    assert!((20_000..30_000).contains(&result.nodes), "{}", result.nodes);
+
+    // What the first search stored in the shared table makes a repeat cheaper.
+    let mut searcher = Searcher::new(4, 16);
+    let (first, _) = search_with(&mut searcher, fen, limits.clone());
+    let (second, _) = search_with(&mut searcher, fen, limits);
+    assert_eq!(mate_in(second.score), Some(3));
+    assert!(second.nodes < first.nodes, "{} >= {}", second.nodes, first.nodes);
+    searcher.set_threads(0);
+    assert_eq!(searcher.threads(), 1);
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use chess::book::{encode_move, Book};
+use chess::packed::PackedReader;
+use chess::pgn::GameResult;
+use chess::search::{Limits, Searcher};
+use chess::selfplay::{play_game, OutputFormat, SelfPlayGame, SelfPlayOptions};
+use chess::{Board, Side};
+
+fn options() -> SelfPlayOptions {
+    SelfPlayOptions {
+        limits: Limits { depth: Some(3), ..Limits::default() },
+        random_plies: 4,
+        max_plies: 40,
+        ..SelfPlayOptions::default()
+    }
+}
+
+/// Replays the game, checking that every move is legal and every record is
+/// a position from the game, labeled with its result.
+fn check_game(game: &SelfPlayGame, max_plies: usize) {
+    assert!(!game.moves.is_empty() && game.moves.len() <= max_plies);
+    let mut board = Board::new();
+    let mut seen = vec![board.to_fen()];
+    for mv in &game.moves {
+        assert!(board.is_legal(mv), "{mv} in {}", board.to_fen());
+        board.make_move(mv);
+        seen.push(board.to_fen());
+    }
+    if let Some(outcome) = board.outcome() {
+        assert_eq!(outcome.result(), game.result);
+    } else if game.moves.len() == max_plies {
+        assert_eq!(game.result, GameResult::Draw);
+    }
+
+    assert!(!game.positions.is_empty());
+    for packed in &game.positions {
+        assert_eq!(packed.result(), Some(game.result));
+        let board = Board::from_packed(packed).unwrap();
+        assert!(seen.contains(&board.to_fen()), "{}", board.to_fen());
+        assert!(!board.is_check());
+    }
+}
+
+#[test]
+fn games_are_valid_and_repeatable() {
+    let options = options();
+    let mut searcher = Searcher::new(1, 4);
+    let game = play_game(&options, &mut searcher, 1);
+    check_game(&game, 40);
+    assert_eq!(play_game(&options, &mut searcher, 1).moves, game.moves);
+    check_game(&play_game(&options, &mut searcher, 2), 40);
+}
+
+#[test]
+fn book_moves_come_first() {
+    let board = Board::new();
+    let e4 = board.parse_uci_move("e2e4").unwrap();
+    let entry = [&board.polyglot_key().to_be_bytes()[..], &encode_move(&e4).to_be_bytes(), &[0, 1, 0, 0, 0, 0]].concat();
+    let options = SelfPlayOptions {
+        book: Some(Arc::new(Book::from_bytes(&entry).unwrap())),
+        random_plies: 0,
+        max_plies: 12,
+        ..options()
+    };
+    let game = play_game(&options, &mut Searcher::new(1, 4), 3);
+    check_game(&game, 12);
+    assert_eq!(game.moves[0], e4);
+}
+
+#[test]
+fn records_are_written_in_both_formats() {
+    let game = play_game(&options(), &mut Searcher::new(1, 4), 5);
+
+    let mut packed = Vec::new();
+    OutputFormat::Packed.write_game(&mut packed, &game).unwrap();
+    let read: Vec<_> = PackedReader::new(&packed[..]).map(Result::unwrap).collect();
+    assert_eq!(read, game.positions);
+
+    let mut epd = Vec::new();
+    OutputFormat::Epd.write_game(&mut epd, &game).unwrap();
+    let epd = String::from_utf8(epd).unwrap();
+    let lines: Vec<&str> = epd.lines().collect();
+    assert_eq!(lines.len(), game.positions.len());
+    for (line, packed) in lines.iter().zip(&game.positions) {
+        let board = Board::from_packed(packed).unwrap();
+        let fen = board.to_fen();
+        let fields: Vec<&str> = fen.split(' ').collect();
+        assert!(line.starts_with(&fields[..4].join(" ")), "{line}");
+        assert!(line.ends_with(&format!("c9 \"{}\";", game.result)), "{line}");
+        let score = if board.side_to_move() == Side::BLACK { -packed.score() } else { packed.score() };
+        assert!(line.contains(&format!("; ce {score};")), "{line}");
+    }
+}
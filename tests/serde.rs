@@ -0,0 +1,84 @@
+#![cfg(feature = "serde")]
+
+use chess::pgn::{Game, GameResult};
+use chess::{chess960_fen, Board, CastlingRights, Move, Side, Variant, STARTPOS};
+
+const POSITIONS: &[(Variant, &str)] = &[
+    (Variant::Standard, STARTPOS),
+    (Variant::Standard, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+    (Variant::Standard, "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"),
+    (Variant::ThreeCheck, "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 2+3 0 3"),
+    (Variant::Crazyhouse, "4k3/1Q~6/8/8/4b3/8/Kpp5/8[NNnp] b - - 0 1"),
+    (Variant::Horde, "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"),
+    (Variant::Atomic, "8/8/8/8/8/8/2k5/rR4KR w - - 0 1"),
+];
+
+fn boards() -> Vec<Board> {
+    let mut boards: Vec<Board> =
+        POSITIONS.iter().map(|&(variant, fen)| Board::from_variant_fen(fen, variant).unwrap()).collect();
+    boards.push(Board::from_fen(&chess960_fen(0).unwrap()).unwrap());
+    boards
+}
+
+#[test]
+fn board_json() {
+    let json = serde_json::to_string(&Board::new()).unwrap();
+    assert_eq!(json, format!("\"{STARTPOS}\""));
+    for board in boards() {
+        let json = serde_json::to_string(&board).unwrap();
+        let back: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_fen(), board.to_fen(), "{json}");
+        assert_eq!(back.variant(), board.variant());
+    }
+    let atomic: Board = serde_json::from_str("\"atomic:8/8/8/8/8/8/2k5/rR4KR w - - 0 1\"").unwrap();
+    assert_eq!(atomic.variant(), Variant::Atomic);
+    assert!(serde_json::from_str::<Board>("\"8/8/8 w - - 0 1\"").is_err());
+}
+
+#[test]
+fn board_bincode() {
+    // Variant, occupancy, 32 nibbles and "w KQkq - 0 1", after bincode's length prefix.
+    assert_eq!(bincode::serialize(&Board::new()).unwrap().len(), 8 + 1 + 8 + 16 + 12);
+    for board in boards() {
+        let bytes = bincode::serialize(&board).unwrap();
+        let back: Board = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(back.to_fen(), board.to_fen());
+        assert_eq!(back.variant(), board.variant());
+        assert_eq!(back.hash(), board.hash());
+    }
+    assert!(bincode::deserialize::<Board>(&[4, 0, 0, 0, 0, 0, 0, 0, 0, 1]).is_err());
+}
+
+#[test]
+fn moves_and_records() {
+    let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    for uci in ["e1g1", "e5f7", "d5e6", "a2a4"] {
+        let mv = board.parse_uci_move(uci).unwrap();
+        let back: Move = serde_json::from_str(&serde_json::to_string(&mv).unwrap()).unwrap();
+        assert_eq!(back, mv);
+        assert_eq!(bincode::deserialize::<Move>(&bincode::serialize(&mv).unwrap()).unwrap(), mv);
+    }
+
+    let castling = board.castling();
+    let json = serde_json::to_string(&castling).unwrap();
+    assert_eq!(serde_json::from_str::<CastlingRights>(&json).unwrap(), castling);
+    assert_eq!(serde_json::to_string(&Side::BLACK).unwrap(), "\"BLACK\"");
+    assert_eq!(serde_json::from_str::<Variant>("\"crazyhouse\"").unwrap(), Variant::Crazyhouse);
+
+    let game = Game {
+        tags: vec![("White".into(), "Anderssen".into()), ("Black".into(), "Kieseritzky".into())],
+        moves: ["e4", "e5", "f4", "exf4"].map(String::from).to_vec(),
+        annotations: Vec::new(),
+        result: Some(GameResult::WhiteWins),
+    };
+    let back: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+    assert_eq!(back, game);
+    let back: Game = bincode::deserialize(&bincode::serialize(&game).unwrap()).unwrap();
+    assert_eq!(back, game);
+
+    // Records written before games had annotations.
+    let json = r#"{"tags":[],"moves":["e4","e5"],"result":"Draw"}"#;
+    let back: Game = serde_json::from_str(json).unwrap();
+    assert!(back.annotations.is_empty());
+    assert_eq!(back.moves, ["e4", "e5"]);
+}
@@ -0,0 +1,58 @@
+use chess::tournament::sprt::{MatchScore, Sprt, SprtStatus};
+
+fn score(wins: u32, draws: u32, losses: u32) -> MatchScore {
+    MatchScore { wins, draws, losses }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+}
+
+#[test]
+fn bounds() {
+    let (lower, upper) = Sprt::default().bounds();
+    assert_close(lower, -2.944438979);
+    assert_close(upper, 2.944438979);
+    let sprt = Sprt {
+        alpha: 0.1,
+        beta: 0.05,
+        ..Sprt::default()
+    };
+    assert_close(sprt.bounds().0, (0.05f64 / 0.9).ln());
+    assert_close(sprt.bounds().1, 9.5f64.ln());
+}
+
+#[test]
+fn elo() {
+    assert_eq!(score(0, 0, 0).elo(), None);
+    assert_eq!(score(10, 0, 0).elo(), None);
+    assert_eq!(score(0, 0, 10).elo(), None);
+    let (elo, margin) = score(60, 20, 20).elo().unwrap();
+    assert_close(elo, 147.190714);
+    assert_close(margin, 66.013382);
+    let (elo, _) = score(20, 20, 60).elo().unwrap();
+    assert_close(elo, -147.190714);
+    assert_close(score(5, 10, 5).elo().unwrap().0, 0.0);
+    assert_eq!(score(60, 20, 20).to_string(), "+60 =20 -20, Elo +147.2 +/- 66.0");
+}
+
+#[test]
+fn llr() {
+    let sprt = Sprt::default();
+    assert_eq!(sprt.llr(&MatchScore::default()), 0.0);
+    assert_close(sprt.llr(&score(520, 1000, 480)), 0.737650);
+    assert!(sprt.llr(&score(480, 1000, 520)) < 0.0);
+    assert_eq!(sprt.status(&score(520, 1000, 480)), SprtStatus::Continue);
+    assert_eq!(sprt.status(&score(2000, 4000, 1000)), SprtStatus::AcceptH1);
+    assert_eq!(sprt.status(&score(1000, 4000, 2000)), SprtStatus::AcceptH0);
+}
+
+#[test]
+fn identical_results_end_the_test() {
+    let sprt = Sprt::default();
+    assert_eq!(sprt.status(&score(14, 0, 0)), SprtStatus::Continue);
+    assert_eq!(sprt.status(&score(15, 0, 0)), SprtStatus::AcceptH1);
+    assert_eq!(sprt.status(&score(0, 0, 15)), SprtStatus::AcceptH0);
+    let draws = (1..10_000).find(|&n| sprt.status(&score(0, n, 0)) != SprtStatus::Continue);
+    assert_eq!(draws.map(|n| sprt.status(&score(0, n, 0))), Some(SprtStatus::AcceptH0));
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use chess::syzygy::{Tablebase, Wdl};
+use chess::{Board, Moves};
+
+/// Three and four piece tables: KQvK, KRvK, KPvK and KRvKR, both `.rtbw` and `.rtbz`.
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/syzygy")
+}
+
+/// The fixture tables. Missing tables fail the test rather than skip it.
+fn tablebase() -> Tablebase {
+    let tablebase = Tablebase::open(fixtures()).expect("Syzygy fixture directory");
+    assert!(tablebase.max_pieces() >= 4, "no Syzygy tables in {}", fixtures().display());
+    tablebase
+}
+
+fn board(fen: &str) -> Board {
+    Board::from_fen(fen).expect("valid FEN")
+}
+
+const KNOWN: &[(&str, Wdl)] = &[
+    ("8/8/8/4k3/8/8/8/KQ6 w - - 0 1", Wdl::Win),
+    ("8/8/8/4k3/8/8/8/KQ6 b - - 0 1", Wdl::Loss),
+    // The black king takes the undefended rook.
+    ("8/8/8/8/8/2k5/2R5/K7 b - - 0 1", Wdl::Draw),
+    ("8/8/8/8/8/8/4P3/k3K3 w - - 0 1", Wdl::Win),
+    // The same with colours swapped.
+    ("K3k3/4p3/8/8/8/8/8/8 b - - 0 1", Wdl::Win),
+    ("r3k3/8/8/8/8/8/8/R3K3 w - - 0 1", Wdl::Win),
+    ("r3k3/8/8/8/8/8/8/R3K3 b - - 0 1", Wdl::Win),
+];
+
+#[test]
+fn positions_outside_the_tables_are_not_probed() {
+    let empty = Tablebase::new();
+    assert_eq!(empty.max_pieces(), 0);
+    assert_eq!(empty.probe_wdl(&board(KNOWN[0].0)), None);
+    assert_eq!(empty.probe_dtz(&board(KNOWN[0].0)), None);
+    assert_eq!(empty.root_moves(&board(KNOWN[0].0)), None);
+
+    let tb = tablebase();
+    assert_eq!(tb.probe_wdl(&board("r3k3/8/8/8/8/8/8/R3K3 w Q - 0 1")), None);
+    assert_eq!(tb.probe_wdl(&Board::new()), None);
+}
+
+#[test]
+fn wdl_of_known_positions() {
+    let tb = tablebase();
+    for &(fen, wdl) in KNOWN {
+        assert_eq!(tb.probe_wdl(&board(fen)), Some(wdl), "{fen}");
+    }
+}
+
+#[test]
+fn dtz_agrees_with_wdl() {
+    let tb = tablebase();
+    for &(fen, wdl) in KNOWN {
+        let dtz = tb.probe_dtz(&board(fen)).expect(fen);
+        assert_eq!(dtz.signum(), (wdl as i32).signum(), "{fen}: dtz {dtz}");
+    }
+}
+
+/// Every position's result is the best of its replies' results.
+#[test]
+fn wdl_agrees_with_replies() {
+    let tb = tablebase();
+    for &(fen, _) in KNOWN {
+        let mut board = board(fen);
+        let mut moves = Moves::new();
+        board.generate_moves(&mut moves, board.side_to_move());
+        let mut best = Wdl::Loss;
+        for mv in moves {
+            board.make_move(&mv);
+            let reply = tb.probe_wdl(&board).expect(fen);
+            board.unmake_move(&mv);
+            best = best.max(match reply {
+                Wdl::Win => Wdl::Loss,
+                Wdl::CursedWin => Wdl::BlessedLoss,
+                Wdl::Draw => Wdl::Draw,
+                Wdl::BlessedLoss => Wdl::CursedWin,
+                Wdl::Loss => Wdl::Win,
+            });
+        }
+        assert_eq!(tb.probe_wdl(&board), Some(best), "{fen}");
+    }
+}
+
+#[test]
+fn root_moves_keep_the_result() {
+    let tb = tablebase();
+    for &(fen, wdl) in KNOWN {
+        let mut board = board(fen);
+        let moves = tb.root_moves(&board).expect(fen);
+        assert!(!moves.is_empty(), "{fen}");
+        for mv in moves {
+            board.make_move(&mv);
+            let reply = tb.probe_wdl(&board).expect(fen);
+            board.unmake_move(&mv);
+            assert_eq!(reply as i32, -(wdl as i32), "{fen}: {mv}");
+        }
+    }
+
+    // Only the mates are kept when there are some.
+    let board = board("k7/8/1K6/8/8/8/8/7Q w - - 0 1");
+    let moves = tb.root_moves(&board).expect("KQvK");
+    let mut moves: Vec<String> = moves.iter().map(ToString::to_string).collect();
+    moves.sort();
+    assert_eq!(moves, ["h1b7", "h1h8"]);
+}
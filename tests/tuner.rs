@@ -0,0 +1,84 @@
+use chess::eval::{EvalWeights, Score};
+use chess::tuner::{load_positions, parse_line, to_rust_source, Position, Tuner};
+use chess::{Board, Side};
+
+const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R b KQ - 3 8",
+    "2r3k1/1p3ppp/p2p4/3Pp3/2P5/1P3P2/P5PP/5RK1 w - e6 0 24",
+    "6k1/5ppp/8/8/7q/5n2/5PPP/6K1 w - - 0 1",
+    "8/5pk1/6p1/1P6/P7/6P1/5PK1/8 b - - 0 40",
+];
+
+#[test]
+fn traced_evaluation_matches_evaluate() {
+    let boards: Vec<Board> = POSITIONS.iter().map(|fen| Board::from_fen(fen).unwrap()).collect();
+    let positions = boards.iter().map(|board| Position::new(board, 0.5)).collect();
+    let tuner = Tuner::new(positions, EvalWeights::default(), 1);
+    for (board, position) in boards.iter().zip(tuner.positions()) {
+        let white = match board.side_to_move() {
+            Side::WHITE => board.evaluate(),
+            Side::BLACK => -board.evaluate(),
+        };
+        // `evaluate` rounds the tapered score towards zero.
+        let traced = tuner.evaluate(position);
+        assert_eq!(traced.trunc() as i32, white, "{}: traced {traced}", board.to_fen());
+    }
+}
+
+#[test]
+fn rust_source_lists_every_weight() {
+    let mut weights = EvalWeights::default();
+    // Distinct values, so a parameter out of place shows up.
+    let params: Vec<Score> = (0..weights.params().len() as i32).map(|i| Score::new(i, -i)).collect();
+    weights.set_params(&params);
+    let source = to_rust_source(&weights);
+    assert!(source.contains("\nEvalWeights {\n"));
+    assert!(source.ends_with("}\n"));
+
+    let scores: Vec<(i32, i32)> = source
+        .split("Score::new(")
+        .skip(1)
+        .map(|rest| {
+            let (mg, rest) = rest.split_once(", ").unwrap();
+            let (eg, _) = rest.split_once(')').unwrap();
+            (mg.parse().unwrap(), eg.parse().unwrap())
+        })
+        .collect();
+    let expected: Vec<(i32, i32)> = weights.params().iter().map(|s| (s.mg, s.eg)).collect();
+    assert_eq!(scores, expected);
+    assert!(source.contains(&format!("    phase: {:?},\n", weights.phase)));
+    assert!(source.contains(&format!("    king_attack_weight: {:?},\n", weights.king_attack_weight)));
+}
+
+#[test]
+fn parses_labelled_lines() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(parse_line(&format!("{fen} [1.0]")), Some((fen.to_string(), 1.0)));
+    assert_eq!(parse_line(&format!("{fen} 1/2-1/2")), Some((fen.to_string(), 0.5)));
+    assert_eq!(
+        parse_line("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - c9 \"0-1\";"),
+        Some(("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -".to_string(), 0.0))
+    );
+    assert_eq!(parse_line(fen), None);
+}
+
+#[test]
+fn loading_skips_known_endgames() {
+    let path = std::env::temp_dir().join(format!("chess-tuner-{}.epd", std::process::id()));
+    let lines = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]",
+        // Evaluates to 0 whatever the weights, while the trace counts the pawn.
+        "8/5k2/8/3P4/8/8/8/4K3 w - - 0 1 [0.5]",
+        "not a position",
+    ];
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let loaded = load_positions(&path, 2);
+    std::fs::remove_file(&path).unwrap();
+    let (positions, skipped) = loaded.unwrap();
+    assert_eq!((positions.len(), skipped), (1, 2));
+}
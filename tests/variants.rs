@@ -0,0 +1,198 @@
+use chess::{Board, GenType, Moves, Side, Variant, STARTPOS};
+
+fn perft(variant: Variant, fen: &str, expected: &[u64]) {
+    let mut board = Board::from_variant_fen(fen, variant).unwrap();
+    for (depth, &nodes) in (1..).zip(expected) {
+        assert_eq!(board.perft(depth), nodes, "{variant} {fen} depth {depth}");
+    }
+    assert_eq!(board.to_fen(), Board::from_variant_fen(fen, variant).unwrap().to_fen());
+    assert_eq!(board.compute_hashes(), (board.hash(), board.pawn_hash()), "{variant} {fen}");
+}
+
+/// Checks that `GenType::Checks` gives exactly the legal moves after which the
+/// opponent is in check, down to `depth`.
+fn check_moves(board: &mut Board, depth: u32) {
+    let generate = |board: &Board, kind| {
+        let mut moves = Moves::new();
+        board.generate(&mut moves, board.side_to_move(), kind);
+        moves.to_vec()
+    };
+    let checks = generate(board, GenType::Checks);
+    for mv in generate(board, GenType::All) {
+        board.make_move(&mv);
+        let check = board.is_check();
+        if depth > 1 {
+            check_moves(board, depth - 1);
+        }
+        board.unmake_move(&mv);
+        assert_eq!(checks.contains(&mv), check, "{} {} {mv}", board.variant(), board.to_fen());
+    }
+}
+
+#[test]
+fn king_of_the_hill_perft() {
+    perft(Variant::KingOfTheHill, STARTPOS, &[20, 400, 8902, 197281]);
+    perft(Variant::KingOfTheHill, "8/8/8/8/8/3K4/8/k7 w - - 0 1", &[8, 15, 105, 457, 3052]);
+    perft(Variant::KingOfTheHill, "4k3/8/8/8/2r5/4K3/8/8 w - - 0 1", &[5, 95, 578, 10482, 59888]);
+    perft(
+        Variant::KingOfTheHill,
+        "rnbq1bnr/ppp1kppp/8/3pp3/3PP3/8/PPP1KPPP/RNBQ1BNR w - - 0 1",
+        &[32, 997, 32280],
+    );
+}
+
+#[test]
+fn three_check_perft() {
+    perft(Variant::ThreeCheck, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1", &[
+        20, 400, 8902, 197281,
+    ]);
+    perft(
+        Variant::ThreeCheck,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 1+1 0 1",
+        &[48, 2039, 97848],
+    );
+}
+
+#[test]
+fn atomic_perft() {
+    perft(Variant::Atomic, STARTPOS, &[20, 400, 8902, 197326]);
+    perft(
+        Variant::Atomic,
+        "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+        &[40, 1238, 45237],
+    );
+    perft(
+        Variant::Atomic,
+        "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+        &[28, 833, 23353],
+    );
+}
+
+#[test]
+fn antichess_perft() {
+    perft(Variant::Antichess, STARTPOS, &[20, 400, 8067, 153299]);
+    perft(Variant::Antichess, "8/1p6/8/8/8/8/P7/8 w - - 0 1", &[2, 4, 4, 3, 1, 0]);
+    // Promotion to a king is allowed.
+    perft(Variant::Antichess, "8/8/8/8/8/8/p7/8 b - - 0 1", &[5, 0]);
+}
+
+#[test]
+fn crazyhouse_perft() {
+    perft(Variant::Crazyhouse, STARTPOS, &[20, 400, 8902, 197281]);
+    perft(Variant::Crazyhouse, "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1", &[301, 75353]);
+    perft(
+        Variant::Crazyhouse,
+        "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1",
+        &[42, 1347, 58057],
+    );
+    perft(Variant::Crazyhouse, "4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1", &[20, 360, 5445, 132758]);
+}
+
+#[test]
+fn horde_perft() {
+    perft(Variant::Horde, "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1", &[
+        8, 128, 1274, 23310,
+    ]);
+    perft(Variant::Horde, "4k3/pp4q1/3P2p1/8/P3PP2/PPP2r2/PPP5/PPPP4 b - - 0 1", &[30, 241, 6633, 56539]);
+    perft(Variant::Horde, "k7/5p2/4p2P/3p2P1/2p2P2/1p2P2P/p2P2P1/2P2P2 w - - 0 1", &[13, 172, 2205, 33781]);
+}
+
+#[test]
+fn racing_kings_perft() {
+    perft(Variant::RacingKings, "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1", &[21, 421, 11264, 296242]);
+    perft(Variant::RacingKings, "4brn1/2K2k2/8/8/8/8/8/8 w - - 0 1", &[6, 33, 178, 3151, 12981]);
+}
+
+#[test]
+fn check_generation() {
+    for (variant, fen) in [
+        (Variant::Crazyhouse, "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1"),
+        (Variant::Crazyhouse, "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[Nn] b KQkq - 0 1"),
+        (Variant::Atomic, "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1"),
+        (Variant::ThreeCheck, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 1+1 0 1"),
+        (Variant::Horde, "4k3/pp4q1/3P2p1/8/P3PP2/PPP2r2/PPP5/PPPP4 b - - 0 1"),
+        (Variant::RacingKings, "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"),
+    ] {
+        check_moves(&mut Board::from_variant_fen(fen, variant).unwrap(), 2);
+    }
+}
+
+#[test]
+fn crazyhouse_pockets() {
+    let fen = "4k3/1Q~6/8/8/4b3/8/Kpp5/8[Nn] b - - 0 1";
+    let mut board = Board::from_variant_fen(fen, Variant::Crazyhouse).unwrap();
+    assert_eq!(board.to_fen(), fen);
+
+    // A promoted piece goes back into the pocket as a pawn.
+    let capture = board.parse_san("Bxb7").unwrap();
+    board.make_move(&capture);
+    assert_eq!(board.pocket(Side::BLACK, 1), 1);
+    assert_eq!(board.pocket(Side::BLACK, 5), 0);
+    assert_eq!(board.to_fen(), "4k3/1b6/8/8/8/8/Kpp5/8[Nnp] w - - 0 2");
+
+    let drop = board.parse_san("N@c3").unwrap();
+    assert_eq!(drop.to_string(), "N@c3");
+    assert_eq!(board.san(&drop), "N@c3");
+    board.make_move(&drop);
+    assert_eq!(board.pocket(Side::WHITE, 2), 0);
+    assert_eq!(board.compute_hashes(), (board.hash(), board.pawn_hash()));
+    board.unmake_move(&drop);
+    board.unmake_move(&capture);
+    assert_eq!(board.to_fen(), fen);
+
+    // No pawn drops on the back ranks.
+    let board = Board::from_variant_fen("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1", Variant::Crazyhouse).unwrap();
+    assert!(board.parse_uci_move("P@a1").is_none());
+    assert!(board.parse_uci_move("P@a8").is_none());
+    assert!(board.parse_san("@a2").is_some());
+}
+
+#[test]
+fn variants_end_games() {
+    let hill = Board::from_variant_fen("8/8/8/8/4K3/8/8/k7 b - - 0 1", Variant::KingOfTheHill).unwrap();
+    assert_eq!(hill.variant_winner(), Some(Side::WHITE));
+
+    let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 2+3 0 3";
+    let mut board = Board::from_variant_fen(fen, Variant::ThreeCheck).unwrap();
+    assert_eq!(board.checks_given(Side::WHITE), 1);
+    assert_eq!(board.to_fen(), fen);
+    let check = board.parse_uci_move("f1b5").unwrap();
+    board.make_move(&check);
+    assert_eq!(board.checks_given(Side::WHITE), 2);
+    assert!(board.to_fen().contains(" 1+3 "));
+    board.unmake_move(&check);
+    assert_eq!(board.to_fen(), fen);
+
+    // Taking on f7 blows up the black king on e8.
+    let mut atomic =
+        Board::from_variant_fen("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 1", Variant::Atomic)
+            .unwrap();
+    for mv in ["f3g5", "a7a6", "g5f7"] {
+        let mv = atomic.parse_uci_move(mv).unwrap();
+        atomic.make_move(&mv);
+    }
+    assert_eq!(atomic.variant_winner(), Some(Side::WHITE));
+    assert_eq!(atomic.piece_at(60), 0);
+
+    // Black can still follow the white king to the eighth rank and draw.
+    let mut racing = Board::from_variant_fen("2K5/6k1/8/8/8/8/8/8 b - - 0 1", Variant::RacingKings).unwrap();
+    assert_eq!(racing.variant_winner(), None);
+    let follow = racing.parse_uci_move("g7g8").unwrap();
+    racing.make_move(&follow);
+    assert!(racing.is_draw());
+    racing.unmake_move(&follow);
+    let late = Board::from_variant_fen("2K5/8/6k1/8/8/8/8/8 b - - 0 1", Variant::RacingKings).unwrap();
+    assert_eq!(late.variant_winner(), Some(Side::WHITE));
+
+    let horde = Board::from_variant_fen("4k3/8/8/8/8/8/8/8 w - - 0 1", Variant::Horde).unwrap();
+    assert_eq!(horde.variant_winner(), Some(Side::BLACK));
+}
+
+#[test]
+fn variant_names() {
+    for variant in Variant::ALL {
+        assert_eq!(variant.name().parse::<Variant>(), Ok(variant));
+    }
+    assert_eq!("King of the Hill".parse::<Variant>(), Ok(Variant::KingOfTheHill));
+    assert!("shogi".parse::<Variant>().is_err());
+}